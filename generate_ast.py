@@ -8,7 +8,11 @@ def define_ast(out_dir, base_name, types):
             f.write("use super::token::Token;\n")
         elif base_name == "Expr":
             f.write("use super::token::{Token, Literal};\n")
-        f.write("use std::fmt::Debug;\n\n#[derive(Clone, Debug)]\npub enum " + base_name + " {\n")
+        f.write("use std::fmt::Debug;\n\n#[derive(Clone, Debug)]\n")
+        if base_name == "Stmt":
+            # statements are only built once, by the parser, so boxing fields to save space isn't worth it
+            f.write("#[allow(clippy::large_enum_variant)]\n")
+        f.write("pub enum " + base_name + " {\n")
 
         for _type in types:
             type_name = _type.split("|")[0].strip()
@@ -72,9 +76,9 @@ if __name__ == "__main__":
         "Unary | Token, Expr",
         "Binary | Expr, Token, Expr",
         "Grouping | Expr",
-        "IdentExpr | Token",
+        "IdentExpr | Token, Option<usize>",
         "FnCall | Token, Vec<Expr>",
-        "ArrIdx | Token, Expr, Option<Box<Expr>>",
+        "ArrIdx | Token, Expr, Option<Box<Expr>>, Option<usize>",
         "ArrType | (Box<Expr>, Box<Expr>), Option<(Box<Expr>, Box<Expr>)>, Expr",
         "Literal | Literal"
     ])
@@ -86,13 +90,13 @@ if __name__ == "__main__":
         "Assign | Expr, Expr",
         "ProcCall | Token, Vec<Expr>",
         "Input | Expr",
-        "Output | Vec<Expr>",
-        "Ret | Expr",
+        "Output | Token, Vec<Expr>",
+        "Ret | Token, Expr",
         "Procedure | Token, Vec<(Token, Expr, bool)>, Stmt",
         "Function | Token, Vec<(Token, Expr, bool)>, Expr, Stmt",
         "ForTo | Token, Expr, Expr, Option<Expr>, Stmt",
-        "IfThen | Expr, Stmt, Option<Box<Stmt>>",
-        "Case | Expr, Vec<(Expr, Stmt)>, Option<Box<Stmt>>",
-        "Repeat | Expr, Stmt",
-        "WhileDo | Expr, Stmt",
+        "IfThen | Token, Expr, Stmt, Option<Box<Stmt>>",
+        "Case | Token, Expr, Vec<(Expr, Stmt)>, Option<Box<Stmt>>",
+        "Repeat | Token, Expr, Stmt",
+        "WhileDo | Token, Expr, Stmt",
     ])
\ No newline at end of file
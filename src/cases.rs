@@ -0,0 +1,349 @@
+use std::{io::{Read, Write}, process::{Command, Stdio}, thread, time::{Duration, Instant}};
+
+// default time limit for a single case, in seconds
+const DEFAULT_TIMEOUT: f64 = 5.0;
+
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub name: String,
+    pub input: Vec<String>,
+    pub expected: String,
+    pub timeout: f64,
+    pub marks: u32
+}
+
+pub enum Outcome {
+    Pass,
+    Fail(String), // actual output
+    Timeout(String), // output before the program was stopped
+    Error(String)
+}
+
+/// Parses a test case file. The format is a small subset of TOML:
+///
+/// ```toml
+/// timeout = 2          # default for every case (seconds)
+///
+/// [[case]]
+/// name = "adds two numbers"
+/// input = ["3", "4"]
+/// expected = """
+/// 7
+/// """
+/// timeout = 0.5        # optional
+/// marks = 2            # optional, defaults to 1
+/// ```
+pub fn parse_cases(source: &str) -> Result<Vec<Case>, CaseFileError> {
+    let mut parser = CaseParser { chars: source.chars().collect(), current: 0, line: 1 };
+    let mut default_timeout = DEFAULT_TIMEOUT;
+    let mut cases: Vec<Case> = Vec::new();
+
+    loop {
+        parser.skip_blank();
+        if parser.is_at_end() { break }
+        if parser.eat_str("[[") {
+            let table = parser.bare_key()?;
+            if table != "case" { return Err(parser.error(format!("unknown table [[{}]]", table))) }
+            if !parser.eat_str("]]") { return Err(parser.error("expected `]]`".into())) }
+            cases.push(Case {
+                name: format!("case {}", cases.len() + 1),
+                input: Vec::new(),
+                expected: String::new(),
+                timeout: default_timeout,
+                marks: 1
+            });
+            parser.end_of_line()?;
+            continue
+        }
+
+        let line = parser.line;
+        let key = parser.bare_key()?;
+        parser.skip_spaces();
+        if !parser.eat_str("=") { return Err(parser.error("expected `=` after key".into())) }
+        parser.skip_spaces();
+        let val = parser.value()?;
+        parser.end_of_line()?;
+
+        match (cases.last_mut(), key.as_str(), val) {
+            // a case can't be given no time at all, or a time that can't be measured
+            (_, "timeout", Value::Number(n)) if !n.is_finite() || n <= 0.0 =>
+                return Err(CaseFileError::new("`timeout` must be a positive number of seconds".into(), line)),
+            (None, "timeout", Value::Number(n)) => default_timeout = n,
+            (Some(case), "name", Value::String(s)) => case.name = s,
+            (Some(case), "input", Value::Array(lines)) => case.input = lines,
+            (Some(case), "input", Value::String(s)) => case.input = s.lines().map(String::from).collect(),
+            (Some(case), "expected", Value::String(s)) => case.expected = s,
+            (Some(case), "timeout", Value::Number(n)) => case.timeout = n,
+            (Some(case), "marks", Value::Number(n)) if n >= 0.0 && n.fract() == 0.0 => case.marks = n as u32,
+            (_, key, _) => return Err(CaseFileError::new(format!("unexpected key or value type for `{}`", key), line))
+        }
+    }
+
+    if cases.is_empty() { return Err(CaseFileError::new("no [[case]] entries found".into(), parser.line)) }
+    Ok(cases)
+}
+
+/// Runs the interpreter on `program` once per case and prints a report
+pub fn run_cases(program: &str, cases: &[Case]) -> bool {
+    let mut score = 0;
+    let mut total = 0;
+    let mut passed = 0;
+
+    for (i, case) in cases.iter().enumerate() {
+        total += case.marks;
+        let outcome = run_case(program, case);
+        match &outcome {
+            Outcome::Pass => {
+                score += case.marks;
+                passed += 1;
+                println!("[{}] PASS {}", i + 1, case.name)
+            },
+            Outcome::Fail(actual) => {
+                println!("[{}] FAIL {}", i + 1, case.name);
+                print!("{}", diff(&case.expected, actual));
+            },
+            Outcome::Timeout(actual) => {
+                println!("[{}] FAIL {} (timed out after {}s)", i + 1, case.name, case.timeout);
+                print!("{}", diff(&case.expected, actual));
+            },
+            Outcome::Error(msg) => println!("[{}] ERROR {}: {}", i + 1, case.name, msg)
+        }
+    }
+
+    println!();
+    println!("{}/{} cases passed, score {}/{}", passed, cases.len(), score, total);
+    passed == cases.len()
+}
+
+pub fn run_case(program: &str, case: &Case) -> Outcome {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => return Outcome::Error(err.to_string())
+    };
+    let mut child = match Command::new(exe)
+        .arg(program)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn() {
+        Ok(child) => child,
+        Err(err) => return Outcome::Error(err.to_string())
+    };
+
+    let mut input = case.input.join("\n");
+    input.push('\n');
+    let mut stdin = child.stdin.take().unwrap();
+    thread::spawn(move || { let _ = stdin.write_all(input.as_bytes()); });
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout = thread::spawn(move || { let mut buf = String::new(); let _ = stdout.read_to_string(&mut buf); buf });
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || { let mut buf = String::new(); let _ = stderr.read_to_string(&mut buf); buf });
+
+    let start = Instant::now();
+    let mut timed_out = false;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) => {},
+            Err(err) => return Outcome::Error(err.to_string())
+        }
+        if start.elapsed() > Duration::from_secs_f64(case.timeout) {
+            let _ = child.kill();
+            let _ = child.wait();
+            timed_out = true;
+            break
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let actual = stdout.join().unwrap_or_default() + &stderr.join().unwrap_or_default();
+    if timed_out { Outcome::Timeout(actual) }
+    else if normalise(&actual) == normalise(&case.expected) { Outcome::Pass }
+    else { Outcome::Fail(actual) }
+}
+
+// trailing spaces and blank lines at the end are ignored when comparing output
+fn normalise(output: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = output.lines().map(|line| line.trim_end()).collect();
+    while lines.last() == Some(&"") { lines.pop(); }
+    lines
+}
+
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = normalise(expected);
+    let actual = normalise(actual);
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out.push_str(&format!("      {}\n", e)),
+            (e, a) => {
+                if let Some(e) = e { out.push_str(&format!("    - {}\n", e)) }
+                if let Some(a) = a { out.push_str(&format!("    + {}\n", a)) }
+            }
+        }
+    }
+    out
+}
+
+enum Value {
+    String(String),
+    Number(f64),
+    Array(Vec<String>)
+}
+
+struct CaseParser {
+    chars: Vec<char>,
+    current: usize,
+    line: usize
+}
+
+impl CaseParser {
+    fn is_at_end(&self) -> bool {
+        self.current >= self.chars.len()
+    }
+    fn peak(&self) -> char {
+        self.chars.get(self.current).copied().unwrap_or('\0')
+    }
+    fn advance(&mut self) -> char {
+        let c = self.peak();
+        self.current += 1;
+        if c == '\n' { self.line += 1 }
+        c
+    }
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        if self.current + len <= self.chars.len() && self.chars[self.current..self.current + len].iter().copied().eq(s.chars()) {
+            for _ in 0..len { self.advance(); }
+            true
+        } else { false }
+    }
+    fn error(&self, msg: String) -> CaseFileError {
+        CaseFileError::new(msg, self.line)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peak() == ' ' || self.peak() == '\t' || self.peak() == '\r' { self.advance(); }
+    }
+    fn skip_comment(&mut self) {
+        if self.peak() == '#' {
+            while !self.is_at_end() && self.peak() != '\n' { self.advance(); }
+        }
+    }
+    // skips whitespace, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if self.peak() == '\n' { self.advance(); }
+            else { break }
+        }
+    }
+    fn end_of_line(&mut self) -> Result<(), CaseFileError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.is_at_end() || self.advance() == '\n' { Ok(()) }
+        else { Err(self.error("expected newline".into())) }
+    }
+
+    fn bare_key(&mut self) -> Result<String, CaseFileError> {
+        let mut key = String::new();
+        while self.peak().is_alphanumeric() || self.peak() == '_' || self.peak() == '-' {
+            key.push(self.advance());
+        }
+        if key.is_empty() { Err(self.error("expected key".into())) }
+        else { Ok(key) }
+    }
+
+    fn value(&mut self) -> Result<Value, CaseFileError> {
+        match self.peak() {
+            '"' => Ok(Value::String(self.string()?)),
+            '\'' => Ok(Value::String(self.literal_string()?)),
+            '[' => {
+                self.advance();
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.peak() == ']' { self.advance(); break }
+                    match self.value()? {
+                        Value::String(s) => items.push(s),
+                        _ => return Err(self.error("only strings are allowed in arrays".into()))
+                    }
+                    self.skip_blank();
+                    if self.peak() == ',' { self.advance(); }
+                    else if self.peak() != ']' { return Err(self.error("expected `,` or `]`".into())) }
+                }
+                Ok(Value::Array(items))
+            },
+            c if c.is_ascii_digit() || c == '-' || c == '+' => {
+                let mut num = String::new();
+                while self.peak().is_ascii_digit() || "+-._".contains(self.peak()) {
+                    let c = self.advance();
+                    if c != '_' { num.push(c) }
+                }
+                num.parse().map(Value::Number).map_err(|_| self.error(format!("invalid number {}", num)))
+            },
+            _ => Err(self.error("expected a value".into()))
+        }
+    }
+
+    // a newline straight after the opening quotes of a multi-line string is trimmed
+    fn trim_newline(&mut self) {
+        if !self.eat_str("\r\n") && self.peak() == '\n' { self.advance(); }
+    }
+    fn string(&mut self) -> Result<String, CaseFileError> {
+        let multiline = self.eat_str("\"\"\"");
+        if !multiline { self.advance(); }
+        else { self.trim_newline(); }
+
+        let mut text = String::new();
+        loop {
+            if self.is_at_end() { return Err(self.error("unterminated string".into())) }
+            if multiline && self.eat_str("\"\"\"") { break }
+            if !multiline && self.peak() == '"' { self.advance(); break }
+            if !multiline && self.peak() == '\n' { return Err(self.error("unterminated string".into())) }
+            match self.advance() {
+                '\\' => text.push(match self.advance() {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '"' => '"',
+                    '\\' => '\\',
+                    c => return Err(self.error(format!("unknown escape sequence \\{}", c)))
+                }),
+                '\r' if multiline => {},
+                c => text.push(c)
+            }
+        }
+        Ok(text)
+    }
+    fn literal_string(&mut self) -> Result<String, CaseFileError> {
+        let multiline = self.eat_str("'''");
+        if !multiline { self.advance(); }
+        else { self.trim_newline(); }
+
+        let mut text = String::new();
+        loop {
+            if self.is_at_end() || (!multiline && self.peak() == '\n') { return Err(self.error("unterminated string".into())) }
+            if multiline && self.eat_str("'''") { break }
+            if !multiline && self.peak() == '\'' { self.advance(); break }
+            match self.advance() {
+                '\r' if multiline => {},
+                c => text.push(c)
+            }
+        }
+        Ok(text)
+    }
+}
+
+pub struct CaseFileError {
+    msg: String,
+    line: usize
+}
+
+impl CaseFileError {
+    pub fn new(msg: String, line: usize) -> Self { Self { msg, line } }
+    pub fn print(&self) {
+        println!("test case file err at line {}: {}", self.line, self.msg)
+    }
+}
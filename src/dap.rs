@@ -0,0 +1,440 @@
+// a Debug Adapter Protocol server over stdin and stdout, so editors like VS Code can run a program
+// with breakpoints, stepping, the call stack and variables shown alongside the source
+use std::collections::{BTreeSet, VecDeque};
+use std::io::Result;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use super::env::{Environment, Observer, Variables};
+use super::interpreter::{Interpreter, RuntimeError};
+use super::json::{self, Value};
+use super::debugger::{Pause, Stepping};
+use super::lines::{stmt_line, stmt_lines};
+use super::lsp::{read_message, write_message};
+use super::parser::Parser;
+use super::resolver;
+use super::scanner::Scanner;
+use super::stmt::Stmt;
+use super::token::Literal;
+
+// the interpreter only ever runs one thread
+const THREAD: usize = 1;
+
+// sends responses and events, numbering every message
+#[derive(Debug)]
+struct Client {
+    seq: usize
+}
+
+impl Client {
+    fn send(&mut self, mut fields: Vec<(&str, Value)>) {
+        self.seq += 1;
+        fields.insert(0, ("seq", num(self.seq)));
+        // if the editor has gone there's no one to tell
+        let _ = write_message(&mut std::io::stdout(), &Value::object(fields));
+    }
+    fn respond(&mut self, request: &Value, body: Value) {
+        self.send(vec![
+            ("type", Value::String("response".into())),
+            ("request_seq", request.get("seq").cloned().unwrap_or(Value::Null)),
+            ("success", Value::Bool(true)),
+            ("command", request.get("command").cloned().unwrap_or(Value::Null)),
+            ("body", body)
+        ])
+    }
+    fn fail(&mut self, request: &Value, msg: &str) {
+        self.send(vec![
+            ("type", Value::String("response".into())),
+            ("request_seq", request.get("seq").cloned().unwrap_or(Value::Null)),
+            ("success", Value::Bool(false)),
+            ("command", request.get("command").cloned().unwrap_or(Value::Null)),
+            ("message", Value::String(msg.into()))
+        ])
+    }
+    fn event(&mut self, event: &str, body: Value) {
+        self.send(vec![("type", Value::String("event".into())), ("event", Value::String(event.into())), ("body", body)])
+    }
+}
+
+type Shared<T> = Arc<Mutex<T>>;
+
+pub fn run() -> Result<()> {
+    // requests are read on their own thread so a running program can check for new ones between
+    // statements without waiting
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        while let Ok(Some(body)) = read_message(&mut input) {
+            match json::parse(&body) {
+                Ok(msg) => if sender.send(msg).is_err() { break },
+                // stdout is for the protocol, so problems go to the editor's log on stderr
+                Err(err) => eprintln!("json err: {}", err.msg)
+            }
+        }
+    });
+    let requests = Arc::new(Mutex::new(receiver));
+    let client = Arc::new(Mutex::new(Client { seq: 0 }));
+
+    // set up the session until the editor says it's done configuring it
+    let mut session: Option<(Session, Stmt)> = None;
+    let mut breakpoints = Vec::new(); // any set before the program has loaded
+    let shared = Arc::clone(&client);
+    loop {
+        let Ok(msg) = requests.lock().unwrap().recv() else { return Ok(()) };
+        let mut client = client.lock().unwrap();
+        match command(&msg) {
+            "initialize" => client.respond(&msg, Value::object(vec![
+                ("supportsConfigurationDoneRequest", Value::Bool(true)),
+                ("supportsEvaluateForHovers", Value::Bool(true))
+            ])),
+            "launch" => {
+                let args = msg.get("arguments").cloned().unwrap_or(Value::Null);
+                let Some(Value::String(path)) = args.get("program") else {
+                    client.fail(&msg, "the launch configuration needs a \"program\" to run");
+                    continue
+                };
+                match load(path) {
+                    Ok(prog) => {
+                        let mut new = Session::new(path, &prog, &args, Arc::clone(&shared), Arc::clone(&requests));
+                        for request in breakpoints.drain(..) {
+                            let body = new.set_breakpoints(&request);
+                            client.respond(&request, body)
+                        }
+                        session = Some((new, prog));
+                        client.respond(&msg, Value::Null);
+                        client.event("initialized", Value::Null)
+                    },
+                    Err(err) => client.fail(&msg, &err)
+                }
+            },
+            "setBreakpoints" => match &mut session {
+                Some((session, _)) => client.respond(&msg, session.set_breakpoints(&msg)),
+                None => breakpoints.push(msg)
+            },
+            "setExceptionBreakpoints" => client.respond(&msg, Value::Null),
+            "threads" => client.respond(&msg, threads()),
+            "configurationDone" => {
+                client.respond(&msg, Value::Null);
+                if session.is_some() { break }
+            },
+            "disconnect" | "terminate" => { client.respond(&msg, Value::Null); std::process::exit(0) },
+            cmd => client.fail(&msg, &format!("{} isn't supported before the program is launched", cmd))
+        }
+    }
+
+    let Some((session, prog)) = session else { return Ok(()) };
+    let finished = Arc::clone(&client);
+    // each pseudocode call takes several native stack frames, so give the interpreter enough stack
+    let program = std::thread::Builder::new()
+        .stack_size(super::STACK_SIZE)
+        .spawn(move || {
+            let mut env = Environment::new();
+            env.set_stack_size(super::STACK_SIZE);
+            env.set_observer(Box::new(session));
+            let result = prog.interpret(&mut env);
+            let mut client = finished.lock().unwrap();
+            let code = match result {
+                Ok(()) => 0,
+                Err(err) => {
+                    client.event("output", output("stderr", &err.report()));
+                    1
+                }
+            };
+            client.event("exited", Value::object(vec![("exitCode", num(code))]));
+            client.event("terminated", Value::Null)
+        })?;
+    if program.join().is_err() {
+        // the panic hook has already described it on stderr
+        let mut client = client.lock().unwrap();
+        client.event("output", output("stderr", "runtime error: the interpreter crashed\n"));
+        client.event("exited", Value::object(vec![("exitCode", num(1))]));
+        client.event("terminated", Value::Null)
+    }
+
+    // the program has finished, but the editor still ends the session
+    loop {
+        let Ok(msg) = requests.lock().unwrap().recv() else { return Ok(()) };
+        let mut client = client.lock().unwrap();
+        match command(&msg) {
+            "disconnect" | "terminate" => { client.respond(&msg, Value::Null); std::process::exit(0) },
+            "threads" => client.respond(&msg, threads()),
+            "setBreakpoints" | "setExceptionBreakpoints" => client.respond(&msg, Value::Null),
+            _ => client.fail(&msg, "the program has finished")
+        }
+    }
+}
+
+// reads, parses and resolves a program, describing the first error
+fn load(path: &str) -> std::result::Result<Stmt, String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| format!("can't read {}: {}", path, err))?;
+    let tokens = Scanner::new(contents).scan_tokens().map_err(|err| format!("error on line {}: {}", err.line, err.msg))?;
+    let mut prog = Parser::new(tokens).try_parse().map_err(|err| format!("error on line {}: {}", err.token.line, err.msg))?;
+    resolver::resolve(&mut prog).map_err(|err| format!("error on line {}: {}", err.line, err.msg))?;
+    Ok(prog)
+}
+
+fn command(msg: &Value) -> &str {
+    match msg.get("command") {
+        Some(Value::String(command)) => command,
+        _ => ""
+    }
+}
+fn arg<'a>(msg: &'a Value, name: &str) -> Option<&'a Value> {
+    msg.get("arguments")?.get(name)
+}
+fn num_arg(msg: &Value, name: &str) -> Option<usize> {
+    match arg(msg, name)? {
+        Value::Number(num) if *num >= 0.0 => Some(*num as usize),
+        _ => None
+    }
+}
+fn num(val: usize) -> Value {
+    Value::Number(val as f64)
+}
+
+fn threads() -> Value {
+    Value::object(vec![("threads", Value::Array(vec![
+        Value::object(vec![("id", num(THREAD)), ("name", Value::String("main".into()))])
+    ]))])
+}
+fn output(category: &str, text: &str) -> Value {
+    Value::object(vec![("category", Value::String(category.into())), ("output", Value::String(text.into()))])
+}
+
+#[derive(Debug)]
+struct Session {
+    path: String,
+    lines: BTreeSet<usize>, // the lines a statement starts on, where breakpoints can go
+    stepping: Stepping,
+    stop: Option<&'static str>, // why to pause at the next statement whatever else happens
+    input: VecDeque<String>, // lines given in the launch configuration for INPUT to read
+    containers: Vec<Variables>, // what each variablesReference (counting from 1) refers to while paused
+    deferred: VecDeque<Value>, // requests that came while running, to answer at the next pause
+    client: Shared<Client>,
+    requests: Shared<Receiver<Value>>
+}
+
+// what to do after answering a request
+enum Then {
+    Wait,
+    Resume,
+    Stop
+}
+
+impl Session {
+    fn new(path: &str, prog: &Stmt, args: &Value, client: Shared<Client>, requests: Shared<Receiver<Value>>) -> Self {
+        let lines = stmt_lines(prog);
+        let input = match args.get("input") {
+            Some(Value::String(text)) => text.lines().map(String::from).collect(),
+            Some(Value::Array(vals)) => vals.iter().map(|val| match val {
+                Value::String(text) => text.clone(),
+                val => val.to_string()
+            }).collect(),
+            _ => VecDeque::new()
+        };
+        Self {
+            path: path.into(),
+            lines,
+            stepping: Stepping::new(false),
+            stop: if args.get("stopOnEntry") == Some(&Value::Bool(true)) { Some("entry") } else { None },
+            input,
+            containers: Vec::new(),
+            deferred: VecDeque::new(),
+            client,
+            requests
+        }
+    }
+
+    // replaces the breakpoints, which are only verified on lines with a statement to pause before
+    fn set_breakpoints(&mut self, msg: &Value) -> Value {
+        self.stepping.breakpoints.clear();
+        let mut results = Vec::new();
+        if let Some(Value::Array(requested)) = arg(msg, "breakpoints") {
+            for breakpoint in requested {
+                let Some(Value::Number(line)) = breakpoint.get("line") else { continue };
+                let line = *line as usize;
+                let verified = self.lines.contains(&line);
+                if verified { self.stepping.breakpoints.insert(line); }
+                let mut fields = vec![("verified", Value::Bool(verified)), ("line", num(line))];
+                if !verified { fields.push(("message", Value::String("there's no statement on this line".into()))) }
+                results.push(Value::object(fields));
+            }
+        }
+        Value::object(vec![("breakpoints", Value::Array(results))])
+    }
+
+    fn source(&self) -> Value {
+        let name = std::path::Path::new(&self.path).file_name().map_or(self.path.clone(), |name| name.to_string_lossy().into_owned());
+        Value::object(vec![("name", Value::String(name)), ("path", Value::String(self.path.clone()))])
+    }
+
+    // stores a group of variables for the editor to ask for, returning its reference
+    fn container(&mut self, vars: Variables) -> usize {
+        self.containers.push(vars);
+        self.containers.len()
+    }
+    // a variable as the editor shows it, with arrays expanding into their elements
+    fn variable(&mut self, name: &str, val: &Option<Literal>) -> Value {
+        let (text, reference) = match val {
+            Some(Literal::Array(vals)) => {
+                let elems = vals.iter().enumerate().map(|(i, val)| (format!("[{}]", i + 1), Some(val.clone()))).collect();
+                (format!("ARRAY[1:{}]", vals.len()), self.container(elems))
+            },
+            Some(val) => (val.to_string(), 0),
+            None => ("(no value)".into(), 0)
+        };
+        let mut fields = vec![("name", Value::String(name.into())), ("value", Value::String(text))];
+        if let Some(val) = val {
+            if !matches!(val, Literal::Array(_)) {
+                fields.push(("type", Value::String(super::env::Type::from_literal(val).keyword().into())))
+            }
+        }
+        fields.push(("variablesReference", num(reference)));
+        Value::object(fields)
+    }
+
+    // answers a request while the program is paused on `line`
+    fn handle(&mut self, msg: &Value, env: &Environment, line: usize) -> Then {
+        let client = Arc::clone(&self.client);
+        let mut client = client.lock().unwrap();
+        let depth = env.call_depth();
+        match command(msg) {
+            "threads" => client.respond(msg, threads()),
+            "setBreakpoints" => client.respond(msg, self.set_breakpoints(msg)),
+            "setExceptionBreakpoints" => client.respond(msg, Value::Null),
+            "stackTrace" => {
+                let calls = env.call_stack();
+                let frames = (0..=calls.len()).map(|frame| {
+                    let name = calls.get(frame).map_or("main program".into(), |(name, _)| name.clone());
+                    // callers are paused on the line they made the call from
+                    let line = if frame == 0 { line } else { calls[frame - 1].1 };
+                    Value::object(vec![
+                        ("id", num(frame)),
+                        ("name", Value::String(name)),
+                        ("source", self.source()),
+                        ("line", num(line)),
+                        ("column", num(1))
+                    ])
+                }).collect();
+                client.respond(msg, Value::object(vec![("stackFrames", Value::Array(frames)), ("totalFrames", num(calls.len() + 1))]))
+            },
+            "scopes" => {
+                let frame = num_arg(msg, "frameId").unwrap_or(0).min(depth);
+                let locals = self.container(env.frame_variables(frame));
+                let globals = self.container(env.global_variables());
+                let scope = |name: &str, reference: usize| Value::object(vec![
+                    ("name", Value::String(name.into())),
+                    ("variablesReference", num(reference)),
+                    ("expensive", Value::Bool(false))
+                ]);
+                client.respond(msg, Value::object(vec![("scopes", Value::Array(vec![scope("Locals", locals), scope("Globals", globals)]))]))
+            },
+            "variables" => match num_arg(msg, "variablesReference").and_then(|reference| self.containers.get(reference.wrapping_sub(1))) {
+                Some(vars) => {
+                    let vars = vars.clone();
+                    let vars = vars.iter().map(|(name, val)| self.variable(name, val)).collect();
+                    client.respond(msg, Value::object(vec![("variables", Value::Array(vars))]))
+                },
+                None => client.fail(msg, "those variables aren't available any more")
+            },
+            "evaluate" => {
+                let name = match arg(msg, "expression") { Some(Value::String(name)) => name.trim(), _ => "" };
+                let frame = num_arg(msg, "frameId").unwrap_or(0).min(depth);
+                let found = env.frame_variables(frame).into_iter().chain(env.global_variables()).find(|(var, _)| var == name);
+                match found {
+                    Some((name, val)) => {
+                        let var = self.variable(&name, &val);
+                        client.respond(msg, Value::object(vec![
+                            ("result", var.get("value").cloned().unwrap_or(Value::Null)),
+                            ("variablesReference", var.get("variablesReference").cloned().unwrap_or(num(0)))
+                        ]))
+                    },
+                    None => client.fail(msg, &format!("no variable called {} here", name))
+                }
+            },
+            "continue" => {
+                self.stepping.resume();
+                client.respond(msg, Value::object(vec![("allThreadsContinued", Value::Bool(true))]));
+                return Then::Resume
+            },
+            "next" => { self.stepping.next(depth); client.respond(msg, Value::Null); return Then::Resume },
+            "stepIn" => { self.stepping.step(); client.respond(msg, Value::Null); return Then::Resume },
+            "stepOut" => {
+                // outside a call there's nothing to step out of, so run to the end
+                self.stepping.out(depth);
+                client.respond(msg, Value::Null);
+                return Then::Resume
+            },
+            "pause" => client.respond(msg, Value::Null),
+            "disconnect" | "terminate" => { client.respond(msg, Value::Null); return Then::Stop },
+            cmd => client.fail(msg, &format!("{} isn't supported", cmd))
+        }
+        Then::Wait
+    }
+}
+
+impl Observer for Session {
+    fn before(&mut self, stmt: &Stmt, env: &Environment) -> std::result::Result<(), RuntimeError> {
+        // defining a procedure or function isn't worth stopping for
+        if matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..)) { return Ok(()) }
+        let Some(line) = stmt_line(stmt) else { return Ok(()) };
+
+        // requests that came while running: a few are answered now and the rest wait for a pause
+        loop {
+            let msg = match self.requests.lock().unwrap().try_recv() {
+                Ok(msg) => msg,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => std::process::exit(0)
+            };
+            let client = Arc::clone(&self.client);
+            let mut client = client.lock().unwrap();
+            match command(&msg) {
+                "threads" => client.respond(&msg, threads()),
+                "setBreakpoints" => client.respond(&msg, self.set_breakpoints(&msg)),
+                "pause" => { self.stop = Some("pause"); client.respond(&msg, Value::Null) },
+                "disconnect" | "terminate" => { client.respond(&msg, Value::Null); std::process::exit(0) },
+                _ => self.deferred.push_back(msg)
+            }
+        }
+
+        let reason = match (self.stop.take(), self.stepping.pause(line, env.call_depth())) {
+            (Some(reason), _) => reason,
+            (None, Some(Pause::Breakpoint)) => "breakpoint",
+            (None, Some(Pause::Step)) => "step",
+            (None, None) => return Ok(())
+        };
+        self.client.lock().unwrap().event("stopped", Value::object(vec![
+            ("reason", Value::String(reason.into())),
+            ("threadId", num(THREAD)),
+            ("allThreadsStopped", Value::Bool(true))
+        ]));
+
+        loop {
+            let msg = match self.deferred.pop_front() {
+                Some(msg) => msg,
+                None => match self.requests.lock().unwrap().recv() {
+                    Ok(msg) => msg,
+                    Err(_) => std::process::exit(0)
+                }
+            };
+            match self.handle(&msg, env, line) {
+                Then::Wait => {},
+                Then::Resume => break,
+                Then::Stop => std::process::exit(0)
+            }
+        }
+        self.containers.clear();
+        Ok(())
+    }
+
+    fn output(&mut self, text: &str) -> bool {
+        self.client.lock().unwrap().event("output", output("stdout", text));
+        true
+    }
+
+    fn input(&mut self) -> Option<std::result::Result<String, RuntimeError>> {
+        Some(self.input.pop_front().ok_or_else(|| {
+            RuntimeError::new("the program asked for input, but the launch configuration's \"input\" has run out".into())
+        }))
+    }
+}
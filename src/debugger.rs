@@ -0,0 +1,192 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use super::env::{Environment, Observer};
+use super::interpreter::RuntimeError;
+use super::lines::stmt_line;
+use super::stmt::Stmt;
+
+const HELP: &str = "commands:
+    s, step          run the next statement, going into any procedure or function it calls
+    n, next          run the next statement, including any calls it makes
+    o, out           run until the current procedure or function returns
+    c, continue      run until the next breakpoint
+    b, break <line>  pause whenever the statement on <line> is about to run
+    d, delete <line> remove the breakpoint on <line>
+    v, vars          list the variables that can be seen here
+    p, print <name>  show one variable
+    l, list          show the lines around the current one
+    q, quit          stop the program
+an empty line repeats the last command";
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Step,
+    Over(usize), // pause at the next statement not in a deeper call than this
+    Out(usize), // pause at the next statement in a shallower call than this
+    Continue
+}
+
+/// Why a program being debugged pauses before a statement
+#[derive(Debug, PartialEq)]
+pub enum Pause {
+    Step,
+    Breakpoint
+}
+
+/// When to pause a program being debugged: the lines with breakpoints, and how far the last
+/// step, next or out command runs. Shared by the command line debugger and the DAP server.
+#[derive(Debug)]
+pub struct Stepping {
+    pub breakpoints: BTreeSet<usize>,
+    mode: Mode
+}
+
+impl Stepping {
+    /// Starts off pausing before the first statement with `step`, or at the first breakpoint
+    pub fn new(step: bool) -> Self {
+        Self { breakpoints: BTreeSet::new(), mode: if step { Mode::Step } else { Mode::Continue } }
+    }
+
+    /// Pauses before the next statement, even in a call it makes
+    pub fn step(&mut self) { self.mode = Mode::Step }
+    /// Pauses before the next statement not in a deeper call than `depth`
+    pub fn next(&mut self, depth: usize) { self.mode = Mode::Over(depth) }
+    /// Pauses once the call `depth` deep returns, or at a breakpoint outside of any call
+    pub fn out(&mut self, depth: usize) { self.mode = if depth == 0 { Mode::Continue } else { Mode::Out(depth) } }
+    /// Pauses at the next breakpoint
+    pub fn resume(&mut self) { self.mode = Mode::Continue }
+
+    /// Whether to pause before the statement on `line`, in a call `depth` deep, and why
+    pub fn pause(&self, line: usize, depth: usize) -> Option<Pause> {
+        // a step that lands on a breakpoint is still a step
+        if self.breakpoints.contains(&line) && self.mode != Mode::Step { return Some(Pause::Breakpoint) }
+        let step = match self.mode {
+            Mode::Step => true,
+            Mode::Over(over) => depth <= over,
+            Mode::Out(out) => depth < out,
+            Mode::Continue => false
+        };
+        step.then_some(Pause::Step)
+    }
+}
+
+/// Pauses the program before statements so it can be run a step at a time, reading commands from
+/// stdin. Each time it pauses it shows the next line to run and any variables that changed.
+#[derive(Debug)]
+pub struct Debugger {
+    source: Vec<String>,
+    stepping: Stepping,
+    last_command: String,
+    last_vals: HashMap<(String, String), String> // each variable's value when it was last shown, by group and name
+}
+
+impl Debugger {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.lines().map(String::from).collect(),
+            stepping: Stepping::new(true),
+            last_command: "step".into(),
+            last_vals: HashMap::new()
+        }
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        self.source.get(line.wrapping_sub(1)).map_or("", |text| text.trim())
+    }
+
+    fn show_changes(&mut self, env: &Environment) {
+        for (group, vars) in env.variables() {
+            for (name, val) in vars {
+                let Some(val) = val.map(|val| val.to_string()) else { continue };
+                if self.last_vals.get(&(group.clone(), name.clone())) != Some(&val) {
+                    println!("    {} = {}", name, val);
+                    self.last_vals.insert((group.clone(), name), val);
+                }
+            }
+        }
+    }
+
+    fn show_vars(&self, env: &Environment) {
+        for (group, vars) in env.variables() {
+            println!("{}:", group);
+            if vars.is_empty() { println!("    (none)") }
+            for (name, val) in vars {
+                println!("    {} = {}", name, val.map_or("(no value)".into(), |val| val.to_string()));
+            }
+        }
+    }
+
+    fn show_lines(&self, line: usize) {
+        for num in line.saturating_sub(3).max(1)..=(line + 3).min(self.source.len()) {
+            println!("{} {:>3} | {}", if num == line { "->" } else { "  " }, num, self.source[num - 1]);
+        }
+    }
+
+    // reads commands until one of them carries on running the program
+    fn prompt(&mut self, line: usize, env: &Environment) {
+        loop {
+            print!("(debug) ");
+            std::io::stdout().flush().unwrap();
+            let mut input = String::new();
+            // with nothing left to read, let the program finish
+            if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                println!();
+                self.stepping.resume();
+                self.stepping.breakpoints.clear();
+                return
+            }
+            let input = match input.trim() {
+                "" => self.last_command.clone(),
+                input => input.to_string()
+            };
+            self.last_command = input.clone();
+            let (command, arg) = input.split_once(' ').map_or((input.as_str(), ""), |(command, arg)| (command, arg.trim()));
+            match command {
+                "s" | "step" => { self.stepping.step(); return },
+                "n" | "next" => { self.stepping.next(env.call_depth()); return },
+                "o" | "out" => match env.call_depth() {
+                    0 => println!("not in a procedure or function"),
+                    depth => { self.stepping.out(depth); return }
+                },
+                "c" | "continue" => { self.stepping.resume(); return },
+                "b" | "break" | "d" | "delete" => match arg.parse::<usize>() {
+                    Ok(num) if command.starts_with('b') => {
+                        self.stepping.breakpoints.insert(num);
+                        println!("breakpoint at line {}: {}", num, self.source_line(num));
+                    },
+                    Ok(num) => if self.stepping.breakpoints.remove(&num) { println!("removed breakpoint at line {}", num) }
+                        else { println!("no breakpoint at line {}", num) },
+                    Err(_) if arg.is_empty() && command.starts_with('b') => {
+                        if self.stepping.breakpoints.is_empty() { println!("no breakpoints") }
+                        for num in &self.stepping.breakpoints { println!("breakpoint at line {}: {}", num, self.source_line(*num)) }
+                    },
+                    Err(_) => println!("expected a line number")
+                },
+                "v" | "vars" => self.show_vars(env),
+                "p" | "print" => match env.variables().into_iter().flat_map(|(_, vars)| vars).find(|(name, _)| name == arg) {
+                    Some((name, val)) => println!("{} = {}", name, val.map_or("(no value)".into(), |val| val.to_string())),
+                    None => println!("no variable called {} here", arg)
+                },
+                "l" | "list" => self.show_lines(line),
+                "q" | "quit" => std::process::exit(0),
+                "h" | "help" => println!("{}", HELP),
+                _ => println!("unknown command {}, type help for a list", command)
+            }
+        }
+    }
+}
+
+impl Observer for Debugger {
+    fn before(&mut self, stmt: &Stmt, env: &Environment) -> Result<(), RuntimeError> {
+        // defining a procedure or function isn't worth stopping for
+        if matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..)) { return Ok(()) }
+        let Some(line) = stmt_line(stmt) else { return Ok(()) };
+        let Some(pause) = self.stepping.pause(line, env.call_depth()) else { return Ok(()) };
+
+        self.show_changes(env);
+        if pause == Pause::Breakpoint { println!("breakpoint at line {}", line) }
+        println!("-> {} | {}", line, self.source_line(line));
+        self.prompt(line, env);
+        Ok(())
+    }
+}
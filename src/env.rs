@@ -1,192 +1,491 @@
-use std::collections::HashMap;
-use crate::interpreter::Interpreter;
+use std::{collections::HashMap, rc::Rc, time::{Duration, Instant}};
+use crate::interpreter::{Interpreter, RuntimeError};
 
 use super::token::Literal;
 use super::stmt::Stmt;
+use super::expr::Expr;
 
-#[derive(Debug, Clone)]
+/// Holds every variable as a stack of scopes, innermost last, and the procedures and functions,
+/// which are all global. Blocks and calls push a scope when they start and pop it when they
+/// finish, and the resolver has already worked out which scope each variable is in.
+#[derive(Debug)]
 pub struct Environment {
-    parent_env: Option<Box<Environment>>,
-    procs: HashMap<String, Proc>,
-    funcs: HashMap<String, Func>, // name and function data (includes a block stmt)
+    scopes: Vec<Scope>,
+    procs: HashMap<String, Rc<Proc>>,
+    funcs: HashMap<String, Rc<Func>>, // name and function data (includes a block stmt)
+    ret: Option<Literal>, // set by RETURN, until the function call takes it
+    limits: Limits,
+    steps: u64,
+    start: Instant,
+    calls: Vec<Frame>, // procedures and functions currently running, innermost last
+    stack_limit: Option<usize>, // the native stack address calls mustn't go below
+    observer: Option<Box<dyn Observer>>
+}
+
+/// Variables' names and values, written out for someone watching the program
+pub type Variables = Vec<(String, Option<Literal>)>;
+
+/// Something that watches a program run, like the debugger, which is shown each statement just
+/// before it runs
+pub trait Observer: std::fmt::Debug + Send {
+    fn before(&mut self, stmt: &Stmt, env: &Environment) -> Result<(), RuntimeError>;
+    /// Takes text the program outputs, returning false to let it go to stdout as usual
+    fn output(&mut self, _text: &str) -> bool { false }
+    /// Gives the program a line of input instead of it being read from stdin
+    fn input(&mut self) -> Option<Result<String, RuntimeError>> { None }
+    /// Sees a loop about to check its condition, or a FOR loop step its counter, for another iteration
+    fn looping(&mut self, _stmt: &Stmt) {}
+    /// Sees a procedure or function call start, once it's on the call stack
+    fn entered(&mut self, _env: &Environment) {}
+    /// Sees a call end, while it's still on the call stack
+    fn leaving(&mut self, _env: &Environment) {}
+    /// Sees the program as it was when it stopped, whether it finished or hit an error
+    fn finished(&mut self, _env: &Environment) {}
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    #[allow(dead_code)]
     types: HashMap<String, String>, // name and type data
     decls: HashMap<String, Decl>, // var name and decl data (type, mutability)
     stack: HashMap<String, Literal>, // name and value
-    ret: Option<Literal>
+    refs: HashMap<String, Place> // BYREF parameters and the variable each one refers to
+}
+
+/// A variable in some scope, or an element of one when `path` holds array indices (from 0)
+#[derive(Clone, Debug)]
+struct Place {
+    scope: usize,
+    name: String,
+    path: Vec<usize>
+}
+
+enum Arg {
+    Value(Literal),
+    Ref(Place)
 }
 
 impl Environment {
-    pub fn new(parent_env: Option<Box<Environment>>) -> Self {
+    pub fn new() -> Self {
         Self {
-            parent_env,
+            scopes: vec![Scope::default()],
             procs: HashMap::new(),
             funcs: HashMap::new(),
-            types: HashMap::new(),
-            decls: HashMap::new(),
-            stack: HashMap::new(),
-            ret: None
+            ret: None,
+            limits: Limits::default(),
+            steps: 0,
+            start: Instant::now(),
+            calls: Vec::new(),
+            stack_limit: None,
+            observer: None
         }
     }
 
-    pub fn declare(&mut self, name: String, info: Decl)  -> Option<Decl> {
-        self.decls.insert(name, info)
-    }
-    pub fn assign(&mut self, mut name: String, val: Literal) -> Option<Literal> /*-> Result<Option<Literal>, RuntimeError>*/ {
-        if self.decls.contains_key(&name) {
-            let decl = self.decls.get(&name).unwrap();
-            if decl.mutable {
-                let mut dtype = decl.dtype.clone();
-                let mut is_ref = false;
-                if let Type::Ref(inner) = dtype {
-                    if let Literal::Ref(_, og_name) = self.stack.get(&name).unwrap()  {
-                        name = og_name.clone();
-                        dtype = *inner;
-                        is_ref = true;
-                    } else {
-                        panic!("idek")
-                    }
-                }
-                if dtype == Type::from_literal(&val) {
-                    if is_ref {
-                        if let Some(env) = &mut self.parent_env
-                            { env.stack.insert(name, val) }
-                        else { panic!("reference somehow in root environment") }
-                    } else {
-                        self.stack.insert(name, val)
-                    }
-                }
-                else { panic!("Type of variable {:?} does not match with value {:?}", dtype, val) }
-            } else { panic!("Cannot assign to constant") }
-        } else {
-            match &mut self.parent_env {
-                Some(env) => match env.assign_parents(name.clone(), val.clone()) {
-                    Some(lit) => Some(lit),
-                    None => {
-                        self.declare(name.clone(), Decl::new(true, Type::from_literal(&val)));
-                        self.stack.insert(name, val)
-                    }
-                },
-                None =>  {
-                    self.declare(name.clone(), Decl::new(true, Type::from_literal(&val)));
-                    self.stack.insert(name, val)
-                }
+    /// Lets calls use the `size` bytes of native stack the thread running the program has left,
+    /// so that recursion deeper than it can hold stops with an error even with no maximum depth
+    pub fn set_stack_size(&mut self, size: usize) {
+        // an eighth is kept spare for whatever the innermost call is doing when the limit's reached
+        self.stack_limit = Some(stack_address().saturating_sub(size - size / 8));
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.start = Instant::now();
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observer = Some(observer);
+    }
+    // the observer is taken out while it runs so it can look at the rest of the environment
+    fn notify<T>(&mut self, f: impl FnOnce(&mut dyn Observer, &Environment) -> T) -> Option<T> {
+        let mut observer = self.observer.take()?;
+        let result = f(observer.as_mut(), self);
+        self.observer = Some(observer);
+        Some(result)
+    }
+    /// Shows the observer, if there is one, the statement about to run
+    pub fn observe(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        self.notify(|observer, env| observer.before(stmt, env)).unwrap_or(Ok(()))
+    }
+
+    /// Shows the observer, if there is one, a loop starting another iteration
+    pub fn looping(&mut self, stmt: &Stmt) {
+        self.notify(|observer, _| observer.looping(stmt));
+    }
+
+    /// Lets the observer know the program has stopped
+    pub fn finish(&mut self) {
+        self.notify(|observer, env| observer.finished(env));
+    }
+
+    /// Writes some of the program's output
+    pub fn write(&mut self, text: &str) {
+        if self.notify(|observer, _| observer.output(text)) != Some(true) { print!("{}", text) }
+    }
+    /// Reads a line of input for the program, without the newline
+    pub fn read_line(&mut self) -> Result<String, RuntimeError> {
+        if let Some(line) = self.notify(|observer, _| observer.input()).flatten() { return line }
+        Ok(read_stdin())
+    }
+
+    pub fn step(&mut self) {
+        self.steps += 1;
+    }
+    /// Called once per loop iteration, so that a loop that never ends is stopped
+    /// with an error pointing at it rather than hanging the interpreter.
+    pub fn check_limits(&mut self, loop_kind: &str, line: usize) -> Result<(), RuntimeError> {
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.steps > max_steps {
+                return Err(RuntimeError::new(format!("{} loop at line {} did not finish within {} steps", loop_kind, line, max_steps)))
             }
         }
-    }
-    pub fn assign_idx(&mut self, name: String, idx: usize, val: Literal) -> Literal {
-        let vec = self.get_stack(&name);//.expect(format!("array {} doesn't exist", name).as_str());
-        let tmp = vec.clone();
-        let vec = if let Literal::Array(val) = vec { val }
-            else { panic!("variable is not an array") };
-        vec[idx-1] = val;
-        tmp
-    }
-    fn assign_parents(&mut self, name: String, val: Literal) -> Option<Literal> /*-> Result<Option<Literal>, RuntimeError>*/ {
-        if self.decls.contains_key(&name) {
-            let decl = self.decls.get(&name).unwrap();
-            if decl.mutable {
-                let dtype = decl.dtype.clone();
-                if dtype == Type::from_literal(&val)
-                    { self.stack.insert(name, val) }
-                else { panic!("Type of variable does not match with value") }
-            } else { panic!("Cannot assign to constant") }
-        } else {
-            match &mut self.parent_env {
-                Some(env) => env.assign_parents(name, val),
-                None => None
+        if let Some(timeout) = self.limits.timeout {
+            if self.start.elapsed() > timeout {
+                return Err(RuntimeError::new(format!("{} loop at line {} did not finish within {}s", loop_kind, line, timeout.as_secs_f64())))
             }
         }
+        Ok(())
     }
 
-    pub fn get_stack(&mut self, name: &String) -> /*Result<Literal, RuntimeError>*/ &mut Literal {
-        match self.decls.get(name) {
-            Some(_) => match self.stack.get_mut(name).unwrap() {
-                Literal::Ref(lit, _) => &mut **lit,
-                lit => lit
-            },
-            None => match &mut self.parent_env {
-                Some(env) => env.get_stack(name),
-                None => panic!("reference to undefined variable {}", name)
-            }
+    /// Runs `f` in a new innermost scope, which is dropped along with everything
+    /// declared in it once `f` returns.
+    pub fn in_scope<T>(&mut self, f: impl FnOnce(&mut Environment) -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        self.scopes.push(Scope::default());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+    fn top(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap()
+    }
+    // the scope a variable the resolver found `depth` scopes out is in, or the global scope
+    fn scope_idx(&self, depth: Option<usize>) -> usize {
+        match depth {
+            Some(depth) => self.scopes.len() - 1 - depth,
+            None => 0
         }
     }
-    pub fn get_proc(&self, name: &String) -> /*Result<Proc, RuntimeError>*/ Proc {
-        match self.decls.get(name) {
-            Some(_) => self.procs.get(name).expect(format!("{} is not a procedure", name).as_str()).clone(),
-            None => match &self.parent_env {
-                Some(env) => env.get_proc(name),
-                None => panic!("reference to undefined variable")
+
+    pub fn declare(&mut self, name: String, info: Decl)  -> Option<Decl> {
+        self.top().decls.insert(name, info)
+    }
+    // declares and assigns `name` in the innermost scope, hiding any outer variable with the same name
+    fn define(&mut self, name: &str, mutable: bool, val: Literal) {
+        self.declare(name.to_string(), Decl::new(mutable, Type::from_literal(&val)));
+        self.top().stack.insert(name.to_string(), val);
+    }
+    pub fn assign(&mut self, name: String, depth: Option<usize>, val: Literal) -> Result<Option<Literal>, RuntimeError> {
+        let idx = self.scope_idx(depth);
+        let scope = &mut self.scopes[idx];
+        let decl = match scope.decls.get(&name) {
+            Some(decl) => decl,
+            None => {
+                scope.decls.insert(name.clone(), Decl::new(true, Type::from_literal(&val)));
+                return Ok(scope.stack.insert(name, val))
             }
+        };
+        if !decl.mutable { return Err(RuntimeError::new("Cannot assign to constant".into())) }
+        if decl.dtype != Type::from_literal(&val) {
+            return Err(RuntimeError::new(format!("Type of variable {:?} does not match with value {:?}", decl.dtype, val)))
+        }
+        match scope.refs.get(&name).cloned() {
+            Some(place) => Ok(Some(std::mem::replace(self.get_place(&place)?, val))),
+            None => Ok(scope.stack.insert(name, val))
+        }
+    }
+    pub fn assign_idx(&mut self, name: String, depth: Option<usize>, idx: usize, val: Literal) -> Result<Literal, RuntimeError> {
+        let vec = if let Literal::Array(vec) = self.get_stack(&name, depth)? { vec }
+            else { return Err(RuntimeError::new("variable is not an array".into())) };
+        match idx.checked_sub(1).and_then(|idx| vec.get_mut(idx)) {
+            Some(elem) => Ok(std::mem::replace(elem, val)),
+            None => Err(RuntimeError::new(format!("index {} is out of bounds for array {}", idx, name)))
+        }
+    }
+
+    pub fn get_stack(&mut self, name: &str, depth: Option<usize>) -> Result<&mut Literal, RuntimeError> {
+        let idx = self.scope_idx(depth);
+        if !self.scopes[idx].decls.contains_key(name) {
+            return Err(RuntimeError::new(format!("reference to undefined variable {}", name)))
+        }
+        if let Some(place) = self.scopes[idx].refs.get(name).cloned() {
+            return self.get_place(&place)
         }
+        self.scopes[idx].stack.get_mut(name)
+            .ok_or_else(|| RuntimeError::new(format!("variable {} is used before it is assigned a value", name)))
     }
-    pub fn get_func(&self, name: &String) -> /*Result<Func, RuntimeError>*/ Func {
-        match self.decls.get(name) {
-            Some(_) => self.funcs.get(name).unwrap().clone(),
-            None => match &self.parent_env {
-                Some(env) => env.get_func(name),
-                None => panic!("reference to undefined variable")
+    fn get_place(&mut self, place: &Place) -> Result<&mut Literal, RuntimeError> {
+        let mut val = self.scopes[place.scope].stack.get_mut(&place.name)
+            .ok_or_else(|| RuntimeError::new(format!("variable {} is used before it is assigned a value", place.name)))?;
+        for idx in &place.path {
+            val = match val {
+                Literal::Array(arr) => arr.get_mut(*idx)
+                    .ok_or_else(|| RuntimeError::new(format!("index {} is out of bounds for array {}", idx + 1, place.name)))?,
+                _ => return Err(RuntimeError::new(format!("{} is not an array", place.name)))
             }
         }
+        Ok(val)
+    }
+    /// Works out which variable, or element of one, `expr` refers to, for passing it BYREF.
+    /// Passing on a BYREF parameter gives the place it refers to, so it can be forwarded any number of times.
+    fn place(&mut self, expr: &Expr, param: &str) -> Result<Place, RuntimeError> {
+        match expr {
+            Expr::IdentExpr(name, depth) => {
+                let idx = self.scope_idx(*depth);
+                let decl = self.scopes[idx].decls.get(&name.lexeme)
+                    .ok_or_else(|| RuntimeError::new(format!("reference to undefined variable {}", name.lexeme)))?;
+                if !decl.mutable {
+                    return Err(RuntimeError::new(format!("constant {} can't be passed BYREF", name.lexeme)))
+                }
+                if let Some(place) = self.scopes[idx].refs.get(&name.lexeme) { return Ok(place.clone()) }
+                Ok(Place { scope: idx, name: name.lexeme.clone(), path: Vec::new() })
+            },
+            Expr::ArrIdx(name, idx1, _, depth) => {
+                let idx1 = match idx1.interpret(self)? {
+                    Literal::Int(n) if n >= 1 => n as usize - 1,
+                    Literal::Int(n) => return Err(RuntimeError::new(format!("index {} is out of bounds for array {}", n, name.lexeme))),
+                    _ => return Err(RuntimeError::new("expected integer".into()))
+                };
+                let mut place = self.place(&Expr::IdentExpr(name.clone(), *depth), param)?;
+                place.path.push(idx1);
+                self.get_place(&place)?;
+                Ok(place)
+            },
+            _ => Err(RuntimeError::new(format!("BYREF parameter {} must be passed a variable or array element", param)))
+        }
+    }
+
+    pub fn get_proc(&self, name: &str) -> Result<Rc<Proc>, RuntimeError> {
+        self.procs.get(name).cloned().ok_or_else(|| RuntimeError::new(
+            if self.funcs.contains_key(name) { format!("{} is not a procedure", name) }
+            else { format!("reference to undefined procedure {}", name) }))
+    }
+    pub fn get_func(&self, name: &str) -> Result<Rc<Func>, RuntimeError> {
+        self.funcs.get(name).cloned().ok_or_else(|| RuntimeError::new(
+            if self.procs.contains_key(name) { format!("{} is not a function", name) }
+            else { format!("reference to undefined function {}", name) }))
     }
 
+    #[allow(dead_code)]
     pub fn del(&mut self, name: &String) {
-        self.decls.remove(name);
-        self.stack.remove(name);
+        self.top().decls.remove(name);
+        self.top().stack.remove(name);
     }
 
-    pub fn def_proc(&mut self, name: &String, arg_list: Vec<(String, Type, bool)>, block: Stmt) {
-        self.declare(name.clone(), Decl::new(false, Type::Proc));
-        self.procs.insert(name.clone(), Proc::new(block, arg_list));
+    pub fn def_const(&mut self, name: &str, val: Literal) {
+        self.define(name, false, val);
     }
-    pub fn def_func(&mut self, name: &String, arg_list: Vec<(String, Type, bool)>, ret_type: Type, block: Stmt) {
-        self.declare(name.clone(), Decl::new(false, Type::Func));
-        self.funcs.insert(name.clone(), Func::new(block, arg_list, ret_type));
+    pub fn def_proc(&mut self, name: &str, line: usize, arg_list: Vec<(String, Type, bool)>, block: Stmt) {
+        self.procs.insert(name.to_string(), Rc::new(Proc::new(block, arg_list, line)));
+    }
+    pub fn def_func(&mut self, name: &str, line: usize, arg_list: Vec<(String, Type, bool)>, ret_type: Type, block: Stmt) {
+        self.funcs.insert(name.to_string(), Rc::new(Func::new(block, arg_list, ret_type, line)));
     }
 
-    pub fn call_proc(&mut self, name: &String, arg_list: Vec<(String, Literal)>) {
-        let proc = self.get_proc(name);
-        let mut new_env = Environment::new(Some(Box::new(self.clone())));
-        if proc.arg_list.len() != arg_list.len() { panic!("wrong number of arguments") }
-        for i in 0..proc.arg_list.len() {
-            if Type::from_literal(&arg_list[i].1) == proc.arg_list[i].1 {
-                if proc.arg_list[i].2 {
-                    new_env.assign(proc.arg_list[i].0.clone(), Literal::Ref(Box::new(arg_list[i].1.clone()), arg_list[i].0.clone()));
-                } else { new_env.assign(proc.arg_list[i].0.clone(), arg_list[i].1.clone()); }
-            } else { panic!("mismatched types of procedure argument") }
+    // a call gets its own scope, which stays until observers have seen it end
+    fn enter_call(&mut self, frame: Frame) -> Result<(), RuntimeError> {
+        self.calls.push(Frame { scope: self.scopes.len(), ..frame });
+        self.scopes.push(Scope::default());
+        self.notify(|observer, env| observer.entered(env));
+        match self.limits.max_depth {
+            Some(max_depth) if self.calls.len() > max_depth =>
+                Err(RuntimeError::new(format!("maximum recursion depth of {} exceeded", max_depth))),
+            _ if self.stack_limit.is_some_and(|limit| stack_address() < limit) =>
+                Err(RuntimeError::new(format!("maximum recursion depth exceeded, the interpreter ran out of stack after {} calls", self.calls.len()))),
+            _ => Ok(())
         }
-        proc.run(&mut new_env);
-
-        new_env.update_parent(self);
-    }
-    pub fn call_func(&mut self, name: &String, arg_list: Vec<(String, Literal)>) -> Literal {
-        let func = self.get_func(name);
-        let mut new_env = Environment::new(Some(Box::new(self.clone())));
-        if func.arg_list.len() != arg_list.len() { panic!("wrong number of arguments") }
-        for i in 0..func.arg_list.len() {
-            if Type::from_literal(&arg_list[i].1) == func.arg_list[i].1 {
-                if func.arg_list[i].2 {
-                    new_env.assign(func.arg_list[i].0.clone(), Literal::Ref(Box::new(arg_list[i].1.clone()), arg_list[i].0.clone()));
+    }
+    // the first call an error passes through on its way out records the calls running at the time
+    fn exit_call<T>(&mut self, result: Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let result = result.map_err(|err| if err.has_trace() { err } else {
+            let trace = self.call_trace();
+            err.with_trace(trace)
+        });
+        self.notify(|observer, env| observer.leaving(env));
+        self.scopes.pop();
+        self.calls.pop();
+        result
+    }
+    /// Lists the procedure and function calls that are currently running
+    pub fn call_trace(&self) -> String {
+        call_trace(&self.calls)
+    }
+    /// How many procedure and function calls are running
+    pub fn call_depth(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// The variables that can be seen from the statement running now, innermost first: those of
+    /// the procedure or function running (if any), then the global ones. Each group is labelled and
+    /// its variables are in alphabetical order, with None for one that hasn't been given a value.
+    pub fn variables(&self) -> Vec<(String, Variables)> {
+        match self.calls.last() {
+            Some(frame) => vec![
+                (format!("{} {}", frame.kind, frame.name), self.frame_variables(0)),
+                ("globals".into(), self.global_variables())
+            ],
+            // blocks in the main program have their own scopes, but their variables are still globals
+            None => vec![("globals".into(), self.scope_variables(0..self.scopes.len()))]
+        }
+    }
+    /// The procedure and function calls running, innermost first, each with the line it was called from
+    pub fn call_stack(&self) -> Vec<(String, usize)> {
+        self.calls.iter().rev().map(|frame| (format!("{} {}", frame.kind, frame.name), frame.call_line)).collect()
+    }
+    /// The local variables of a call, counting out from the innermost one, or of the main program's
+    /// blocks (like FOR counters) when `frame` is the number of calls running
+    pub fn frame_variables(&self, frame: usize) -> Variables {
+        let idx = self.calls.len() - frame; // the call after this one in `calls`
+        let start = if idx == 0 { 1 } else { self.calls[idx - 1].scope };
+        let end = self.calls.get(idx).map_or(self.scopes.len(), |call| call.scope);
+        self.scope_variables(start..end)
+    }
+    pub fn global_variables(&self) -> Variables {
+        self.scope_variables(0..1)
+    }
+    fn scope_variables(&self, scopes: std::ops::Range<usize>) -> Variables {
+        let mut vars: Variables = Vec::new();
+        for scope in &self.scopes[scopes] {
+            for name in scope.decls.keys() {
+                let val = match scope.refs.get(name) {
+                    Some(place) => self.peek_place(place),
+                    None => scope.stack.get(name)
+                };
+                let val = val.cloned();
+                // an inner variable hides an outer one with the same name
+                match vars.iter_mut().find(|(var, _)| var == name) {
+                    Some(var) => var.1 = val,
+                    None => vars.push((name.clone(), val))
                 }
-                else { new_env.assign(func.arg_list[i].0.clone(), arg_list[i].1.clone()); }
-            } else { panic!("mismatched types of function argument") }
+            }
+        }
+        vars.sort_by(|(a, _), (b, _)| a.cmp(b));
+        vars
+    }
+    fn peek_place(&self, place: &Place) -> Option<&Literal> {
+        let mut val = self.scopes[place.scope].stack.get(&place.name)?;
+        for idx in &place.path {
+            val = match val {
+                Literal::Array(arr) => arr.get(*idx)?,
+                _ => return None
+            }
         }
-        let ret = func.run(&mut new_env);
-        
-        new_env.update_parent(self);
+        Some(val)
+    }
 
-        ret
+    pub fn call_proc(&mut self, name: &str, line: usize, args: &[Expr]) -> Result<(), RuntimeError> {
+        let proc = self.get_proc(name)?;
+        let args = self.eval_args(&proc.arg_list, args)?;
+        let result = self.enter_call(Frame::new("PROCEDURE", name, proc.line, line))
+            .and_then(|_| self.bind_args(&proc.arg_list, args, "procedure"))
+            .and_then(|_| proc.run(self));
+        self.exit_call(result)
+    }
+    pub fn call_func(&mut self, name: &str, line: usize, args: &[Expr]) -> Result<Literal, RuntimeError> {
+        let func = self.get_func(name)?;
+        let args = self.eval_args(&func.arg_list, args)?;
+        let result = self.enter_call(Frame::new("FUNCTION", name, func.line, line))
+            .and_then(|_| self.bind_args(&func.arg_list, args, "function"))
+            .and_then(|_| func.run(self));
+        self.exit_call(result)
+    }
+    // evaluates the arguments in the caller's scope, or works out what they refer to for BYREF parameters
+    fn eval_args(&mut self, params: &[(String, Type, bool)], args: &[Expr]) -> Result<Vec<Arg>, RuntimeError> {
+        if params.len() != args.len() { return Err(RuntimeError::new("wrong number of arguments".into())) }
+        let mut arg_list = Vec::new();
+        for (param, arg) in params.iter().zip(args) {
+            arg_list.push(if param.2 { Arg::Ref(self.place(arg, &param.0)?) }
+                else { Arg::Value(arg.interpret(self)?) });
+        }
+        Ok(arg_list)
+    }
+    // declares the parameters in the call's scope, BYREF ones as references to the caller's variable
+    fn bind_args(&mut self, params: &[(String, Type, bool)], arg_list: Vec<Arg>, kind: &str) -> Result<(), RuntimeError> {
+        for (param, arg) in params.iter().zip(arg_list) {
+            let dtype = match &arg {
+                Arg::Value(val) => Type::from_literal(val),
+                Arg::Ref(place) => Type::from_literal(self.get_place(place)?)
+            };
+            if dtype != param.1 {
+                return Err(RuntimeError::new(format!("mismatched types of {} argument", kind)))
+            }
+            match arg {
+                Arg::Value(val) => self.define(&param.0, true, val),
+                Arg::Ref(place) => {
+                    self.declare(param.0.clone(), Decl::new(true, dtype));
+                    self.top().refs.insert(param.0.clone(), place);
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub fn set_ret(&mut self, val: Literal) {
+    pub fn set_ret(&mut self, val: Literal) -> Result<(), RuntimeError> {
+        if self.calls.is_empty() { return Err(RuntimeError::new("RETURN outside of a procedure or function".into())) }
         self.ret = Some(val);
+        Ok(())
+    }
+    /// Whether a RETURN has run, meaning the rest of the procedure/function body is skipped
+    pub fn returning(&self) -> bool {
+        self.ret.is_some()
     }
-    pub fn reset_ret(&mut self) {
-        self.ret = None;
+    pub fn take_ret(&mut self) -> Option<Literal> {
+        self.ret.take()
     }
+}
+
+// roughly where the native stack is up to, which grows down as calls are made
+fn stack_address() -> usize {
+    let here = 0u8;
+    std::hint::black_box(&here) as *const u8 as usize
+}
 
-    pub fn update_parent(self, parent: &mut Environment) {
-        *parent = *self.parent_env.unwrap();
+#[derive(Clone, Debug)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub max_depth: Option<usize>
+}
+impl Default for Limits {
+    fn default() -> Self { Self { max_steps: Some(DEFAULT_MAX_STEPS), timeout: None, max_depth: Some(DEFAULT_MAX_DEPTH) } }
+}
+pub const DEFAULT_MAX_STEPS: u64 = 10_000_000;
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    kind: &'static str,
+    name: String,
+    line: usize, // where the procedure/function is defined
+    call_line: usize,
+    scope: usize // the first scope that belongs to the call
+}
+impl Frame {
+    pub fn new(kind: &'static str, name: &str, line: usize, call_line: usize) -> Self
+    { Self { kind, name: name.to_string(), line, call_line, scope: 0 } }
+}
+
+/// Reads a line of the program's input from stdin, without the newline
+pub fn read_stdin() -> String {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).unwrap();
+    line.trim().to_string()
+}
+
+/// Lists the procedure and function calls in `calls`, innermost first.
+/// Runs of the same call (as in deep recursion) are shown once with a count.
+pub fn call_trace(calls: &[Frame]) -> String {
+    let mut lines: Vec<(String, usize)> = Vec::new();
+    for frame in calls.iter().rev() {
+        let line = format!("    in {} {} (line {}) called from line {}", frame.kind, frame.name, frame.line, frame.call_line);
+        match lines.last_mut() {
+            Some((last, count)) if *last == line => *count += 1,
+            _ => lines.push((line, 0))
+        }
     }
+    lines.into_iter().map(|(line, repeats)| match repeats {
+        0 => line,
+        _ => format!("{} (repeated {} more times)", line, repeats)
+    }).collect::<Vec<_>>().join("\n")
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -201,13 +500,36 @@ pub enum Type {
     Array(Box<Type>, (usize, usize), Option<(usize, usize)>),
     //UDT(UDT)
 
-    Proc,
-    Func,
-    Type,
-    Ref(Box<Type>)
+    #[allow(dead_code)]
+    Type
 }
 
 impl Type {
+    /// The value a newly declared variable of this type starts with, if it has one (only arrays do)
+    pub fn default_value(&self) -> Result<Option<Literal>, RuntimeError> {
+        if let Type::Array(inner_type, (_, idx1len), _) = self {
+            let lit = match **inner_type {
+                Type::Bool => Literal::FALSE,
+                Type::Int => Literal::Int(0),
+                Type::Char => Literal::Char(0 as char),
+                Type::String => Literal::String("".into()),
+                _ => return Err(RuntimeError::new(format!("{:?}", inner_type)))
+            };
+            Ok(Some(Literal::Array(vec![lit; *idx1len])))
+        } else { Ok(None) }
+    }
+    /// The keyword the type is written as in pseudocode
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Type::Int => "INTEGER",
+            Type::Float => "REAL",
+            Type::Bool => "BOOLEAN",
+            Type::Char => "CHAR",
+            Type::String => "STRING",
+            Type::Date => "DATE",
+            Type::Array(..) | Type::Type => "TYPE"
+        }
+    }
     pub fn from_literal(lit: &Literal) -> Type {
         match lit {
             Literal::TRUE | Literal::FALSE => Type::Bool,
@@ -221,9 +543,8 @@ impl Type {
             Literal::String(_) => Type::String,
             Literal::Date(_, _, _) => Type::Date,
             Literal::Type(_) => todo!(),
-            Literal::Ref(lit, _) => Type::Ref(Box::new(Type::from_literal(lit))),
             Literal::Array(exprs) => Type::Array(
-                Box::new(exprs.first().map(|lit| Type::from_literal(lit)).unwrap_or(Type::Bool)),
+                Box::new(exprs.first().map(Type::from_literal).unwrap_or(Type::Bool)),
                 (1, exprs.len()),
                 None
             ), // TODO: allow starting index other than 1, and allow second dimension, and decide on empty array type
@@ -231,46 +552,47 @@ impl Type {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Proc {
     block: Stmt,
-    arg_list: Vec<(String, Type, bool)>
+    arg_list: Vec<(String, Type, bool)>,
+    line: usize
 }
 impl Proc {
-    pub fn new(block: Stmt, arg_list: Vec<(String, Type, bool)>) -> Self { Self { block, arg_list } }
+    pub fn new(block: Stmt, arg_list: Vec<(String, Type, bool)>, line: usize) -> Self { Self { block, arg_list, line } }
 
-    pub fn run(&self, env: &mut Environment) {
-        self.block.interpret(env);
+    pub fn run(&self, env: &mut Environment) -> Result<(), RuntimeError> {
+        self.block.interpret(env)?;
+        env.take_ret();
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Func {
     block: Stmt,
     arg_list: Vec<(String, Type, bool)>,
-    ret_type: Type
+    ret_type: Type,
+    line: usize
 }
 impl Func {
-    pub fn new(block: Stmt, arg_list: Vec<(String, Type, bool)>, ret_type: Type) -> Self
-    { Self { block, arg_list, ret_type } }
-
-    pub fn run(&self, env: &mut Environment) -> Literal {
-        self.block.interpret(env);
-        match &env.ret {
-            Some(ret) => if !(Type::from_literal(ret) == self.ret_type)
-                { panic!("mismatched type of return value") },
-            None => panic!("expected RETURN statement"),
+    pub fn new(block: Stmt, arg_list: Vec<(String, Type, bool)>, ret_type: Type, line: usize) -> Self
+    { Self { block, arg_list, ret_type, line } }
+
+    pub fn run(&self, env: &mut Environment) -> Result<Literal, RuntimeError> {
+        self.block.interpret(env)?;
+        match env.take_ret() {
+            Some(ret) => if Type::from_literal(&ret) == self.ret_type { Ok(ret) }
+                else { Err(RuntimeError::new("mismatched type of return value".into())) },
+            None => Err(RuntimeError::new("expected RETURN statement".into())),
         }
-        let tmp = env.ret.clone().unwrap();
-        env.reset_ret();
-        tmp
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Decl {
-    mutable: bool,
-    dtype: Type
+    pub mutable: bool,
+    pub dtype: Type
 }
 impl Decl {
     pub fn new(mutable: bool, dtype: Type) -> Self { Self { mutable, dtype } }
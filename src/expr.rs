@@ -6,9 +6,11 @@ pub enum Expr {
     Unary(Token, Box<Expr>),
     Binary(Box<Expr>, Token, Box<Expr>),
     Grouping(Box<Expr>),
-    IdentExpr(Token),
+    // the Option<usize> in IdentExpr and ArrIdx is how many scopes out the variable is, set by
+    // the resolver, or None for a global
+    IdentExpr(Token, Option<usize>),
     FnCall(Token, Vec<Expr>),
-    ArrIdx(Token, Box<Expr>, Option<Box<Expr>>),
+    ArrIdx(Token, Box<Expr>, #[allow(dead_code)] Option<Box<Expr>>, Option<usize>),
     ArrType((Box<Expr>, Box<Expr>), Option<(Box<Expr>, Box<Expr>)>, Box<Expr>),
     Literal(Literal),
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+use super::{Chart, Shape};
+
+// one graph with a cluster for each chart, so they're all drawn in the same picture
+pub fn write(charts: &[Chart]) -> String {
+    let mut out = String::from("digraph flowchart {\n    node [fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\"];\n");
+    for (i, chart) in charts.iter().enumerate() {
+        out.push_str(&format!("    subgraph cluster_{} {{\n        label=\"{}\";\n", i, escape(&chart.title)));
+        for (id, node) in chart.nodes.iter().enumerate() {
+            let shape = match node.shape {
+                Shape::Terminal => "shape=oval",
+                Shape::Process => "shape=box",
+                Shape::Decision => "shape=diamond",
+                Shape::Io => "shape=parallelogram",
+                // graphviz has no predefined process symbol, so a box with a double border stands in
+                Shape::Predefined => "shape=box, peripheries=2"
+            };
+            out.push_str(&format!("        c{}_{} [{}, label=\"{}\"];\n", i, id, shape, escape(&node.label)));
+        }
+        for edge in &chart.edges {
+            out.push_str(&format!("        c{}_{} -> c{}_{}", i, edge.from, i, edge.to));
+            if let Some(label) = edge.label { out.push_str(&format!(" [label=\"{}\"]", label)) }
+            out.push_str(";\n");
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,33 @@
+use super::{Chart, Shape};
+
+// one flowchart with a subgraph for each chart
+pub fn write(charts: &[Chart]) -> String {
+    let mut out = String::from("flowchart TD\n");
+    for (i, chart) in charts.iter().enumerate() {
+        out.push_str(&format!("    subgraph c{} [\"{}\"]\n", i, escape(&chart.title)));
+        for (id, node) in chart.nodes.iter().enumerate() {
+            let label = escape(&node.label);
+            let node = match node.shape {
+                Shape::Terminal => format!("([\"{}\"])", label),
+                Shape::Process => format!("[\"{}\"]", label),
+                Shape::Decision => format!("{{\"{}\"}}", label),
+                Shape::Io => format!("[/\"{}\"/]", label),
+                Shape::Predefined => format!("[[\"{}\"]]", label)
+            };
+            out.push_str(&format!("        c{}_{}{}\n", i, id, node));
+        }
+        for edge in &chart.edges {
+            match edge.label {
+                Some(label) => out.push_str(&format!("        c{}_{} -->|{}| c{}_{}\n", i, edge.from, label, i, edge.to)),
+                None => out.push_str(&format!("        c{}_{} --> c{}_{}\n", i, edge.from, i, edge.to))
+            }
+        }
+        out.push_str("    end\n");
+    }
+    out
+}
+
+// labels are quoted, so only quotes and what mermaid would take for HTML need escaping
+pub fn escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
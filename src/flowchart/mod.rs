@@ -0,0 +1,191 @@
+mod dot;
+mod mermaid;
+mod structure;
+
+pub use structure::structure_chart;
+
+use super::expr::Expr;
+use super::formatter::format_expr;
+use super::stmt::Stmt;
+use super::token::Token;
+
+/// A way of writing flowcharts down as text
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Dot,
+    Mermaid
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "dot" | "graphviz" => Some(Format::Dot),
+            "mermaid" => Some(Format::Mermaid),
+            _ => None
+        }
+    }
+}
+
+/// Draws a resolved program as flowcharts, one for the main program and one for each procedure
+/// and function, using the usual symbols: ovals to start and stop, diamonds for decisions,
+/// parallelograms for INPUT and OUTPUT and double-sided boxes for calls.
+pub fn flowchart(prog: &Stmt, format: Format) -> String {
+    let charts = charts(prog);
+    match format {
+        Format::Dot => dot::write(&charts),
+        Format::Mermaid => mermaid::write(&charts)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Terminal,
+    Process,
+    Decision,
+    Io,
+    Predefined // a call to a procedure, which has its own chart
+}
+
+struct Node {
+    shape: Shape,
+    label: String
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    label: Option<&'static str>
+}
+
+struct Chart {
+    title: String,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>
+}
+
+// the arrows still to be drawn into whatever comes next, from each node and with their label
+type Ends = Vec<(usize, Option<&'static str>)>;
+
+fn charts(prog: &Stmt) -> Vec<Chart> {
+    let mut charts = vec![chart("main program".into(), "START".into(), prog, "STOP")];
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Procedure(name, params, block) => charts.push(chart(
+                    format!("PROCEDURE {}", name.lexeme), format!("{}({})", name.lexeme, params_list(params)), block, "RETURN"
+                )),
+                Stmt::Function(name, params, _, block) => charts.push(chart(
+                    format!("FUNCTION {}", name.lexeme), format!("{}({})", name.lexeme, params_list(params)), block, "RETURN"
+                )),
+                _ => {}
+            }
+        }
+    }
+    charts
+}
+
+fn params_list(params: &[(Token, Expr, bool)]) -> String {
+    params.iter().map(|(name, _, _)| name.lexeme.clone()).collect::<Vec<_>>().join(", ")
+}
+
+fn chart(title: String, start: String, body: &Stmt, stop: &str) -> Chart {
+    let mut chart = Chart { title, nodes: Vec::new(), edges: Vec::new() };
+    let start = chart.node(Shape::Terminal, start, Vec::new());
+    let ends = chart.stmt(body, vec![(start, None)]);
+    // a function's RETURNs end it already, so there may be nothing left to stop
+    if !ends.is_empty() { chart.node(Shape::Terminal, stop.into(), ends); }
+    chart
+}
+
+impl Chart {
+    fn node(&mut self, shape: Shape, label: String, ends: Ends) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node { shape, label });
+        for (from, label) in ends { self.edges.push(Edge { from, to: id, label }) }
+        id
+    }
+
+    // adds a statement's nodes after `ends`, and returns the ends of the statement
+    fn stmt(&mut self, stmt: &Stmt, ends: Ends) -> Ends {
+        match stmt {
+            Stmt::Block(stmts) => stmts.iter().fold(ends, |ends, stmt| self.stmt(stmt, ends)),
+            // declarations and definitions aren't steps the program takes
+            Stmt::Declare(..) | Stmt::Procedure(..) | Stmt::Function(..) => ends,
+            Stmt::ExprStmt(expr) => vec![(self.node(Shape::Process, format_expr(expr), ends), None)],
+            Stmt::Constant(name, val) => vec![(self.node(Shape::Process, format!("{} ← {}", name.lexeme, format_expr(val)), ends), None)],
+            Stmt::Assign(target, val) => vec![(self.node(Shape::Process, format!("{} ← {}", format_expr(target), format_expr(val)), ends), None)],
+            Stmt::ProcCall(name, args) => {
+                let args = args.iter().map(format_expr).collect::<Vec<_>>().join(", ");
+                vec![(self.node(Shape::Predefined, format!("CALL {}({})", name.lexeme, args), ends), None)]
+            },
+            Stmt::Input(target) => vec![(self.node(Shape::Io, format!("INPUT {}", format_expr(target)), ends), None)],
+            Stmt::Output(_, exprs) => {
+                let exprs = exprs.iter().map(format_expr).collect::<Vec<_>>().join(", ");
+                vec![(self.node(Shape::Io, format!("OUTPUT {}", exprs), ends), None)]
+            },
+            Stmt::Ret(_, val) => {
+                self.node(Shape::Terminal, format!("RETURN {}", format_expr(val)), ends);
+                Vec::new()
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let decision = self.node(Shape::Decision, format!("{}?", format_expr(cond)), ends);
+                let mut ends = self.stmt(then_block, vec![(decision, Some("Yes"))]);
+                match else_block {
+                    Some(block) => ends.extend(self.stmt(block, vec![(decision, Some("No"))])),
+                    None => ends.push((decision, Some("No")))
+                }
+                ends
+            },
+            // each case is a decision, tried in order until one matches
+            Stmt::Case(_, selector, cases, otherwise) => {
+                let mut ends = ends;
+                let mut out = Vec::new();
+                let selector = format_expr(selector);
+                for (vals, body) in cases {
+                    let conds: Vec<String> = vals.iter().map(|(from, to)| match to {
+                        Some(to) => format!("{} ≤ {} ≤ {}", format_expr(from), selector, format_expr(to)),
+                        None => format!("{} = {}", selector, format_expr(from))
+                    }).collect();
+                    let decision = self.node(Shape::Decision, format!("{}?", conds.join(" OR ")), ends);
+                    out.extend(self.stmt(body, vec![(decision, Some("Yes"))]));
+                    ends = vec![(decision, Some("No"))];
+                }
+                match otherwise {
+                    Some(body) => out.extend(self.stmt(body, ends)),
+                    None => out.extend(ends)
+                }
+                out
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                let decision = self.node(Shape::Decision, format!("{}?", format_expr(cond)), ends);
+                let body = self.stmt(block, vec![(decision, Some("Yes"))]);
+                for (from, label) in body { self.edges.push(Edge { from, to: decision, label }) }
+                vec![(decision, Some("No"))]
+            },
+            Stmt::Repeat(_, cond, block) => {
+                // the body is drawn before the decision that loops back to its first node
+                let first = self.nodes.len();
+                let body = self.stmt(block, ends);
+                let decision = self.node(Shape::Decision, format!("{}?", format_expr(cond)), body);
+                self.edges.push(Edge { from: decision, to: first, label: Some("No") });
+                vec![(decision, Some("Yes"))]
+            },
+            Stmt::ForTo(counter, start, end, step, block) => {
+                let counter = &counter.lexeme;
+                let init = self.node(Shape::Process, format!("{} ← {}", counter, format_expr(start)), ends);
+                // a negative step counts down to the end instead of up
+                let step = step.as_ref().map_or("1".into(), format_expr);
+                let cmp = if step.starts_with('-') { "≥" } else { "≤" };
+                let decision = self.node(Shape::Decision, format!("{} {} {}?", counter, cmp, format_expr(end)), vec![(init, None)]);
+                let body = self.stmt(block, vec![(decision, Some("Yes"))]);
+                let next = match step.strip_prefix('-') {
+                    Some(step) => format!("{} ← {} - {}", counter, counter, step),
+                    None => format!("{} ← {} + {}", counter, counter, step)
+                };
+                let next = self.node(Shape::Process, next, body);
+                self.edges.push(Edge { from: next, to: decision, label: None });
+                vec![(decision, Some("No"))]
+            }
+        }
+    }
+}
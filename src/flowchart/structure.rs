@@ -0,0 +1,136 @@
+use super::Format;
+use super::super::expr::Expr;
+use super::super::formatter::format_expr;
+use super::super::stmt::Stmt;
+
+// the main program, or a procedure or function
+struct Module {
+    name: String,
+    params: Vec<(String, bool)>, // each parameter's name and whether it's BYREF
+    returns: Option<String> // a function's return type
+}
+
+/// Draws a resolved program's structure chart: a box for the main program and each procedure and
+/// function, with a line from each to the ones it calls. The lines are labelled with the data
+/// passed: `↓` for a BYVALUE parameter, `↕` for a BYREF one and `↑` for a function's return value.
+pub fn structure_chart(prog: &Stmt, format: Format) -> String {
+    let mut modules = vec![Module { name: "main program".into(), params: Vec::new(), returns: None }];
+    let mut bodies = vec![prog];
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts {
+            let (name, params, returns, block) = match stmt {
+                Stmt::Procedure(name, params, block) => (name, params, None, block),
+                Stmt::Function(name, params, ret_type, block) => (name, params, Some(format_expr(ret_type)), block),
+                _ => continue
+            };
+            let params = params.iter().map(|(param, _, byref)| (param.lexeme.clone(), *byref)).collect();
+            modules.push(Module { name: name.lexeme.clone(), params, returns });
+            bodies.push(block);
+        }
+    }
+
+    // each caller and callee once, in the order the calls are first made
+    let mut calls: Vec<(usize, usize)> = Vec::new();
+    for (caller, body) in bodies.into_iter().enumerate() {
+        let mut names = Vec::new();
+        stmt_calls(body, &mut names);
+        for name in names {
+            // calls to library functions like LENGTH have no box of their own
+            let Some(callee) = modules.iter().skip(1).position(|module| module.name == name) else { continue };
+            if !calls.contains(&(caller, callee + 1)) { calls.push((caller, callee + 1)) }
+        }
+    }
+
+    match format {
+        Format::Dot => {
+            let mut out = String::from("digraph structure {\n    node [shape=box, fontname=\"Helvetica\"];\n    edge [arrowhead=none, fontname=\"Helvetica\"];\n");
+            for (id, module) in modules.iter().enumerate() {
+                out.push_str(&format!("    m{} [label=\"{}\"];\n", id, super::dot::escape(&module.name)));
+            }
+            for (caller, callee) in calls {
+                out.push_str(&format!("    m{} -> m{}", caller, callee));
+                let data: Vec<String> = data(&modules[callee]).iter().map(|line| super::dot::escape(line)).collect();
+                if !data.is_empty() { out.push_str(&format!(" [label=\"{}\"]", data.join("\\n"))) }
+                out.push_str(";\n");
+            }
+            out.push_str("}\n");
+            out
+        },
+        Format::Mermaid => {
+            let mut out = String::from("flowchart TD\n");
+            for (id, module) in modules.iter().enumerate() {
+                out.push_str(&format!("    m{}[\"{}\"]\n", id, super::mermaid::escape(&module.name)));
+            }
+            for (caller, callee) in calls {
+                let data: Vec<String> = data(&modules[callee]).iter().map(|line| super::mermaid::escape(line)).collect();
+                if data.is_empty() { out.push_str(&format!("    m{} --- m{}\n", caller, callee)) }
+                else { out.push_str(&format!("    m{} ---|\"{}\"| m{}\n", caller, data.join("<br/>"), callee)) }
+            }
+            out
+        }
+    }
+}
+
+// the data passing between a module and whatever calls it, a line for each parameter then the
+// return value
+fn data(module: &Module) -> Vec<String> {
+    let mut lines: Vec<String> = module.params.iter()
+        .map(|(name, byref)| format!("{} {}", if *byref { "↕" } else { "↓" }, name))
+        .collect();
+    if let Some(dtype) = &module.returns { lines.push(format!("↑ {}", dtype)) }
+    lines
+}
+
+// the names of the procedures and functions a statement calls, leaving out the bodies of any it
+// defines
+fn stmt_calls(stmt: &Stmt, names: &mut Vec<String>) {
+    match stmt {
+        Stmt::Block(stmts) => for stmt in stmts { stmt_calls(stmt, names) },
+        Stmt::Procedure(..) | Stmt::Function(..) | Stmt::Declare(..) => {},
+        Stmt::ProcCall(name, args) => {
+            names.push(name.lexeme.clone());
+            for arg in args { expr_calls(arg, names) }
+        },
+        Stmt::ExprStmt(expr) | Stmt::Constant(_, expr) | Stmt::Input(expr) | Stmt::Ret(_, expr) => expr_calls(expr, names),
+        Stmt::Assign(target, val) => { expr_calls(target, names); expr_calls(val, names) },
+        Stmt::Output(_, exprs) => for expr in exprs { expr_calls(expr, names) },
+        Stmt::ForTo(_, start, end, step, block) => {
+            expr_calls(start, names);
+            expr_calls(end, names);
+            if let Some(step) = step { expr_calls(step, names) }
+            stmt_calls(block, names)
+        },
+        Stmt::IfThen(_, cond, then_block, else_block) => {
+            expr_calls(cond, names);
+            stmt_calls(then_block, names);
+            if let Some(block) = else_block { stmt_calls(block, names) }
+        },
+        Stmt::Case(_, selector, cases, otherwise) => {
+            expr_calls(selector, names);
+            for (vals, body) in cases {
+                for (from, to) in vals {
+                    expr_calls(from, names);
+                    if let Some(to) = to { expr_calls(to, names) }
+                }
+                stmt_calls(body, names)
+            }
+            if let Some(body) = otherwise { stmt_calls(body, names) }
+        },
+        Stmt::Repeat(_, cond, block) | Stmt::WhileDo(_, cond, block) => { stmt_calls(block, names); expr_calls(cond, names) }
+    }
+}
+fn expr_calls(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::FnCall(name, args) => {
+            for arg in args { expr_calls(arg, names) }
+            names.push(name.lexeme.clone())
+        },
+        Expr::Unary(_, inner) | Expr::Grouping(inner) => expr_calls(inner, names),
+        Expr::Binary(left, _, right) => { expr_calls(left, names); expr_calls(right, names) },
+        Expr::ArrIdx(_, idx1, idx2, _) => {
+            expr_calls(idx1, names);
+            if let Some(idx2) = idx2 { expr_calls(idx2, names) }
+        },
+        Expr::IdentExpr(..) | Expr::ArrType(..) | Expr::Literal(_) => {}
+    }
+}
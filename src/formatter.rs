@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use super::expr::Expr;
+use super::lines::stmt_line;
+use super::scanner::Comment;
+use super::stmt::Stmt;
+use super::token::{Literal, Token, TokenType};
+
+/// Writes a program back out in the standard CAIE layout: four spaces for each level of
+/// indentation, THEN and ELSE on their own lines two spaces in, keywords in capitals and one
+/// space around operators and `<-`. Comments go back before the statement that followed them, or
+/// at the end of the line they were on, and single blank lines between statements are kept.
+pub fn format(prog: &Stmt, tokens: &[Token], comments: &[Comment]) -> String {
+    let mut formatter = Formatter {
+        tokens,
+        comments: comments.iter().map(|comment| {
+            let trailing = tokens.iter().any(|tkn| tkn.line == comment.line && !matches!(tkn.ttype, TokenType::NL | TokenType::End));
+            (comment.clone(), trailing)
+        }).collect(),
+        lines: Vec::new(),
+        indent: 0,
+        prefix: None,
+        last_line: 0,
+        first_in_block: true
+    };
+    formatter.stmt(prog);
+    formatter.comments_before(usize::MAX);
+    formatter.lines.join("\n") + "\n"
+}
+
+// keywords that end a block
+const CLOSERS: &[TokenType] = &[
+    TokenType::ELSE, TokenType::ENDIF, TokenType::ENDFOR, TokenType::ENDWHILE, TokenType::UNTIL,
+    TokenType::ENDCASE, TokenType::ENDPROCEDURE, TokenType::ENDFUNCTION
+];
+
+struct Formatter<'a> {
+    tokens: &'a [Token],
+    comments: VecDeque<(Comment, bool)>, // comments still to be written, and whether each comes after code on its line
+    lines: Vec<String>,
+    indent: usize,
+    prefix: Option<String>, // written before the next line, for the label of a CASE branch
+    last_line: usize, // the last line of the source that's been written
+    first_in_block: bool
+}
+
+impl Formatter<'_> {
+    // writes a line of code that was at `line` in the source, with the comment at the end of it
+    fn line(&mut self, text: String, line: Option<usize>) {
+        let mut text = format!("{}{}{}", " ".repeat(self.indent), self.prefix.take().unwrap_or_default(), text);
+        if let Some(line) = line {
+            if let Some(idx) = self.comments.iter().position(|(comment, trailing)| *trailing && comment.line == line) {
+                let (comment, _) = self.comments.remove(idx).unwrap();
+                text = format!("{} {}", text, comment.text);
+            }
+            self.last_line = self.last_line.max(line);
+        }
+        self.lines.push(text);
+    }
+
+    // something from `line` in the source is about to be written: comments before it go first,
+    // then a blank line if there was one
+    fn start(&mut self, line: Option<usize>) {
+        let Some(line) = line else { return };
+        self.comments_before(line);
+        self.gap(line);
+        self.last_line = self.last_line.max(line);
+    }
+    fn comments_before(&mut self, line: usize) {
+        while self.comments.front().is_some_and(|(comment, _)| comment.line < line) {
+            let (comment, _) = self.comments.pop_front().unwrap();
+            self.gap(comment.line);
+            self.lines.push(format!("{}{}", " ".repeat(self.indent), comment.text));
+            self.last_line = comment.line;
+        }
+    }
+    fn gap(&mut self, line: usize) {
+        if !self.first_in_block && line > self.last_line + 1 { self.lines.push(String::new()) }
+        self.first_in_block = false;
+    }
+
+    // the line of the next of these keywords in the source
+    fn next(&self, ttypes: &[TokenType]) -> Option<usize> {
+        self.tokens.iter().find(|tkn| tkn.line > self.last_line && ttypes.contains(&tkn.ttype)).map(|tkn| tkn.line)
+    }
+
+    fn body(&mut self, stmt: &Stmt) {
+        self.indent += 4;
+        self.first_in_block = true;
+        self.stmt(stmt);
+        // comments before the keyword ending the block belong inside it
+        if let Some(line) = self.next(CLOSERS) { self.comments_before(line) }
+        self.first_in_block = false;
+        self.indent -= 4;
+    }
+    // a CASE clause's statements, the first after its label and the rest lined up under it
+    fn clause(&mut self, label: String, stmt: &Stmt) {
+        let stmts = match stmt {
+            Stmt::Block(stmts) => &stmts[..],
+            stmt => std::slice::from_ref(stmt)
+        };
+        let Some((first, rest)) = stmts.split_first() else { return };
+        self.start(stmt_line(first));
+        let width = label.chars().count();
+        self.prefix = Some(label);
+        self.stmt(first);
+        self.indent += width;
+        for stmt in rest { self.stmt(stmt) }
+        self.indent -= width;
+    }
+    fn close(&mut self, keyword: &str) {
+        let idx = self.tokens.iter().position(|tkn| tkn.line > self.last_line && CLOSERS.contains(&tkn.ttype));
+        let mut text = keyword.to_string();
+        // ENDFOR can be followed by the loop's counter
+        if let Some(tkn) = idx.and_then(|idx| self.tokens.get(idx + 1)) {
+            if keyword == "ENDFOR" && tkn.ttype == TokenType::Identifier { text = format!("ENDFOR {}", tkn.lexeme) }
+        }
+        self.line(text, idx.map(|idx| self.tokens[idx].line))
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Block(stmts) = stmt {
+            for stmt in stmts { self.stmt(stmt) }
+            return
+        }
+        let line = stmt_line(stmt);
+        self.start(line);
+        match stmt {
+            Stmt::Block(_) => unreachable!(),
+            Stmt::ExprStmt(expr) => self.line(format_expr(expr), line),
+            Stmt::Declare(name, dtype) => self.line(format!("DECLARE {} : {}", name.lexeme, format_expr(dtype)), line),
+            Stmt::Constant(name, val) => self.line(format!("CONSTANT {} = {}", name.lexeme, format_expr(val)), line),
+            Stmt::Assign(target, val) => self.line(format!("{} <- {}", format_expr(target), format_expr(val)), line),
+            Stmt::ProcCall(name, args) => match args.is_empty() {
+                true => self.line(format!("CALL {}", name.lexeme), line),
+                false => self.line(format!("CALL {}({})", name.lexeme, format_list(args)), line)
+            },
+            Stmt::Input(target) => self.line(format!("INPUT {}", format_expr(target)), line),
+            Stmt::Output(_, exprs) => self.line(format!("OUTPUT {}", format_list(exprs)), line),
+            Stmt::Ret(_, val) => self.line(format!("RETURN {}", format_expr(val)), line),
+            Stmt::Procedure(name, params, block) => {
+                self.line(format!("PROCEDURE {}{}", name.lexeme, format_params(params)), line);
+                self.body(block);
+                self.close("ENDPROCEDURE")
+            },
+            Stmt::Function(name, params, ret_type, block) => {
+                self.line(format!("FUNCTION {}{} RETURNS {}", name.lexeme, format_params(params), format_expr(ret_type)), line);
+                self.body(block);
+                self.close("ENDFUNCTION")
+            },
+            Stmt::ForTo(counter, start, end, step, block) => {
+                let mut header = format!("FOR {} <- {} TO {}", counter.lexeme, format_expr(start), format_expr(end));
+                if let Some(step) = step { header.push_str(&format!(" STEP {}", format_expr(step))) }
+                self.line(header, line);
+                self.body(block);
+                self.close("ENDFOR")
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                self.line(format!("IF {}", format_expr(cond)), line);
+                let then_line = self.next(&[TokenType::THEN]);
+                self.line("  THEN".into(), then_line);
+                self.body(then_block);
+                if let Some(block) = else_block {
+                    let else_line = self.next(CLOSERS);
+                    self.line("  ELSE".into(), else_line);
+                    self.body(block);
+                }
+                self.close("ENDIF")
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                self.line(format!("CASE OF {}", format_expr(val)), line);
+                self.indent += 4;
+                self.first_in_block = true;
+                for (vals, stmt) in cases {
+                    let vals: Vec<String> = vals.iter().map(|(from, to)| match to {
+                        Some(to) => format!("{} TO {}", format_expr(from), format_expr(to)),
+                        None => format_expr(from)
+                    }).collect();
+                    self.clause(format!("{} : ", vals.join(", ")), stmt);
+                }
+                if let Some(stmt) = otherwise { self.clause("OTHERWISE : ".into(), stmt) }
+                if let Some(line) = self.next(CLOSERS) { self.comments_before(line) }
+                self.indent -= 4;
+                self.close("ENDCASE")
+            },
+            Stmt::Repeat(_, cond, block) => {
+                self.line("REPEAT".into(), line);
+                self.body(block);
+                let until_line = self.next(CLOSERS);
+                self.line(format!("UNTIL {}", format_expr(cond)), until_line)
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                self.line(format!("WHILE {} DO", format_expr(cond)), line);
+                self.body(block);
+                self.close("ENDWHILE")
+            },
+        }
+    }
+}
+
+fn format_params(params: &[(Token, Expr, bool)]) -> String {
+    if params.is_empty() { return String::new() }
+    let params: Vec<String> = params.iter().map(|(name, dtype, byref)| {
+        format!("{}{} : {}", if *byref { "BYREF " } else { "" }, name.lexeme, format_expr(dtype))
+    }).collect();
+    format!("({})", params.join(", "))
+}
+fn format_list(exprs: &[Expr]) -> String {
+    exprs.iter().map(format_expr).collect::<Vec<_>>().join(", ")
+}
+
+/// An expression as it would be written in pseudocode
+pub fn format_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Unary(op, right) => match op.ttype {
+            TokenType::NOT => format!("NOT {}", format_expr(right)),
+            _ => format!("{}{}", op.lexeme, format_expr(right))
+        },
+        // keyword operators like MOD may have been written in lowercase
+        Expr::Binary(left, op, right) => format!("{} {} {}", format_expr(left), op.lexeme.to_uppercase(), format_expr(right)),
+        Expr::Grouping(inner) => format!("({})", format_expr(inner)),
+        Expr::IdentExpr(name, _) => name.lexeme.clone(),
+        Expr::FnCall(name, args) => format!("{}({})", name.lexeme, format_list(args)),
+        Expr::ArrIdx(name, idx1, idx2, _) => match idx2 {
+            Some(idx2) => format!("{}[{}, {}]", name.lexeme, format_expr(idx1), format_expr(idx2)),
+            None => format!("{}[{}]", name.lexeme, format_expr(idx1))
+        },
+        Expr::ArrType((start1, end1), idx2, dtype) => match idx2 {
+            Some((start2, end2)) => format!("ARRAY[{}:{}, {}:{}] OF {}", format_expr(start1), format_expr(end1),
+                format_expr(start2), format_expr(end2), format_expr(dtype)),
+            None => format!("ARRAY[{}:{}] OF {}", format_expr(start1), format_expr(end1), format_expr(dtype))
+        },
+        Expr::Literal(lit) => match lit {
+            Literal::Float(val) if val.fract() == 0.0 => format!("{:.1}", val),
+            Literal::Type(dtype) => dtype.keyword().into(),
+            lit => lit.to_string()
+        }
+    }
+}
@@ -1,8 +1,35 @@
 mod stmt_interpreter;
 mod expr_interpreter;
+pub mod ops;
 
 use super::env::Environment;
 
 pub trait Interpreter<Return> {
-    fn interpret(&self, env: &mut Environment) -> Return;
-}
\ No newline at end of file
+    fn interpret(&self, env: &mut Environment) -> Result<Return, RuntimeError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub msg: String,
+    trace: Option<String> // the procedure/function calls running when the error happened
+}
+
+impl RuntimeError {
+    pub fn new(msg: String) -> Self { Self { msg, trace: None } }
+    pub fn has_trace(&self) -> bool {
+        self.trace.is_some()
+    }
+    pub fn with_trace(self, trace: String) -> Self {
+        Self { trace: Some(trace), ..self }
+    }
+    pub fn print(&self) {
+        eprint!("{}", self.report());
+    }
+    /// The error as it's printed, followed by the calls that led to it
+    pub fn report(&self) -> String {
+        match &self.trace {
+            Some(trace) if !trace.is_empty() => format!("runtime error: {}\n{}\n", self.msg, trace),
+            _ => format!("runtime error: {}\n", self.msg)
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::cmp::Ordering;
+use crate::env::Type;
+use crate::token::{Literal, TokenType};
+use super::RuntimeError;
+
+// The operators, shared by the tree-walking interpreter and the VM so both give the same
+// results and errors. Operands are evaluated by the caller.
+
+pub fn unary(op: &TokenType, right: Literal) -> Result<Literal, RuntimeError> {
+    Ok(match op {
+        TokenType::NOT => match right {
+            Literal::TRUE => Literal::FALSE,
+            Literal::FALSE => Literal::TRUE,
+            _ => return Err(RuntimeError::new("expected boolean expression".into()))
+        },
+        TokenType::Minus => match right {
+            Literal::Int(val) => Literal::Int(-val),
+            Literal::Float(val) => Literal::Float(-val),
+            _ => return Err(RuntimeError::new("expected boolean expression".into()))
+        },
+        _ => return Err(RuntimeError::new("invalid syntax tree (unary operator)".into()))
+    })
+}
+
+pub fn binary(op: &TokenType, left: Literal, right: Literal) -> Result<Literal, RuntimeError> {
+    Ok(match op {
+        TokenType::Equal => if left == right { Literal::TRUE }
+            else { Literal::FALSE },
+        TokenType::NotEqual => if left == right { Literal::FALSE }
+            else { Literal::TRUE },
+        // TokenType::Period => todo!(),
+        TokenType::Star => match left {
+            // Literal::Char(_) => todo!(),
+            // Literal::String(_) => todo!(),
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Int(left_i * right_i),
+                Literal::Float(right_f) => Literal::Float(left_i as f32 * right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Float(left_f * right_i as f32),
+                Literal::Float(right_f) => Literal::Float(left_f * right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::Slash => {
+            let left = match left {
+                Literal::Int(i) => i as f32,
+                Literal::Float(f) => f,
+                _ =>return Err(RuntimeError::new("expected numerical value".into()))
+            };
+            let right = match right {
+                Literal::Int(i) => i as f32,
+                Literal::Float(f) => f,
+                _ =>return Err(RuntimeError::new("expected numerical value".into()))
+            };
+            
+            Literal::Float(left / right)
+        },
+        TokenType::Plus => match left {
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Int(left_i + right_i),
+                Literal::Float(right_f) => Literal::Float(left_i as f32 + right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Float(left_f + right_i as f32),
+                Literal::Float(right_f) => Literal::Float(left_f + right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::Minus => match left {
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Int(left_i - right_i),
+                Literal::Float(right_f) => Literal::Float(left_i as f32 - right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => Literal::Float(left_f - right_i as f32),
+                Literal::Float(right_f) => Literal::Float(left_f - right_f),
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::Less => match left {
+            // Literal::Char(_) => todo!(), Alphabetical order
+            // Literal::String(_) => todo!(), for char and string
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_i < right_i { Literal::TRUE }
+                    else { Literal::FALSE },
+                Literal::Float(right_f) => if (left_i as f32) < right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_f < right_i as f32 { Literal::TRUE }
+                else { Literal::FALSE },
+                Literal::Float(right_f) => if left_f < right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::Greater => match left {
+            // Literal::Char(_) => todo!(),
+            // Literal::String(_) => todo!(),
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_i > right_i { Literal::TRUE }
+                    else { Literal::FALSE },
+                Literal::Float(right_f) => if (left_i as f32) > right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_f > right_i as f32 { Literal::TRUE }
+                else { Literal::FALSE },
+                Literal::Float(right_f) => if left_f > right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::LessEqual => match left {
+            // Literal::Char(_) => todo!(), just use normal equal
+            // Literal::String(_) => todo!(), plus Less operation
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_i <= right_i { Literal::TRUE }
+                    else { Literal::FALSE },
+                Literal::Float(right_f) => if (left_i as f32) <= right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_f <= right_i as f32 { Literal::TRUE }
+                else { Literal::FALSE },
+                Literal::Float(right_f) => if left_f <= right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        TokenType::GreaterEqual => match left {
+            // Literal::Char(_) => todo!(),
+            // Literal::String(_) => todo!(),
+            // Literal::Date(_, _, _) => todo!(),
+            Literal::Int(left_i) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_i >= right_i { Literal::TRUE }
+                    else { Literal::FALSE },
+                Literal::Float(right_f) => if (left_i as f32) >= right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            Literal::Float(left_f) => match right {
+                // Literal::Char(_) => todo!(),
+                // Literal::String(_) => todo!(),
+                // Literal::Date(_, _, _) => todo!(),
+                Literal::Int(right_i) => if left_f >= right_i as f32 { Literal::TRUE }
+                else { Literal::FALSE },
+                Literal::Float(right_f) => if left_f >= right_f { Literal::TRUE }
+                else { Literal::FALSE },
+                _ => return Err(RuntimeError::new("expected numerical value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected numerical value".into()))
+        },
+        // TokenType::MOD => { TODO: what do these do anyways? remainder and integer division? are they even operators and not functions?
+        //     let left = left;
+        //     let right = right;
+        //     match left {
+        //         Literal::Int(left_i) => match right {
+        //             Literal::Int(right_i) => Literal::Int(left_i - right_i),
+        //             Literal::Float(right_f) => Literal::Float(left_i as f32 - right_f),
+        //             _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //         },
+        //         Literal::Float(left_f) => match right {
+        //             Literal::Int(right_i) => Literal::Float(left_f - right_i as f32),
+        //             Literal::Float(right_f) => Literal::Float(left_f - right_f),
+        //             _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //         },
+        //         _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //     }
+        // },
+        // TokenType::DIV => {
+        //     let left = left;
+        //     let right = right;
+        //     match left {
+        //         Literal::Int(left_i) => match right {
+        //             Literal::Int(right_i) => Literal::Int(left_i - right_i),
+        //             Literal::Float(right_f) => Literal::Float(left_i as f32 - right_f),
+        //             _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //         },
+        //         Literal::Float(left_f) => match right {
+        //             Literal::Int(right_i) => Literal::Float(left_f - right_i as f32),
+        //             Literal::Float(right_f) => Literal::Float(left_f - right_f),
+        //             _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //         },
+        //         _ => return Err(RuntimeError::new("expected numerical value".into()))
+        //     }
+        // },
+        TokenType::AND => match left {
+            Literal::TRUE => match right {
+                Literal::TRUE => Literal::TRUE,
+                Literal::FALSE => Literal::FALSE,
+                _ => return Err(RuntimeError::new("expected boolean value".into()))
+            },
+            Literal::FALSE => match right {
+                Literal::TRUE | Literal::FALSE => Literal::FALSE,
+                _ => return Err(RuntimeError::new("expected boolean value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected boolean value".into()))
+        },
+        TokenType::OR => match left {
+            Literal::TRUE => match right {
+                Literal::TRUE | Literal::FALSE => Literal::TRUE,
+                _ => return Err(RuntimeError::new("expected boolean value".into()))
+            },
+            Literal::FALSE => match right {
+                Literal::TRUE => Literal::TRUE,
+                Literal::FALSE => Literal::FALSE,
+                _ => return Err(RuntimeError::new("expected boolean value".into()))
+            },
+            _ => return Err(RuntimeError::new("expected boolean value".into()))
+        },
+        _ => todo!()
+    })
+}
+
+/// Checks a CASE clause's value, or with `to` the range from `from` to `to`, can be compared with the
+/// selector. A value has to be the same type as the selector, apart from INTEGER and REAL which can
+/// be mixed, and a range needs a type with an order.
+pub fn check_case_value(selector: &Literal, from: &Literal, to: Option<&Literal>) -> Result<(), RuntimeError> {
+    for val in std::iter::once(from).chain(to) {
+        let numbers = matches!(selector, Literal::Int(_) | Literal::Float(_)) && matches!(val, Literal::Int(_) | Literal::Float(_));
+        if !numbers && type_name(selector) != type_name(val) {
+            return Err(RuntimeError::new(format!(
+                "CASE value {} is {} but the value being checked is {}", val.to_string(), a(type_name(val)), a(type_name(selector))
+            )))
+        }
+    }
+    match to {
+        Some(to) if order(selector, from).is_none() || order(selector, to).is_none() =>
+            Err(RuntimeError::new(format!("a CASE range can't be made of {} values", type_name(from)))),
+        _ => Ok(())
+    }
+}
+
+/// Whether a CASE selector matches one of a clause's values, or with `to` is in the range from `from`
+/// to `to`, including both ends. The value should already have passed `check_case_value`.
+pub fn case_matches(selector: &Literal, from: &Literal, to: Option<&Literal>) -> bool {
+    match to {
+        None => order(selector, from).map_or(selector == from, Ordering::is_eq),
+        Some(to) => order(selector, from).is_some_and(Ordering::is_ge) && order(selector, to).is_some_and(Ordering::is_le)
+    }
+}
+
+// how two values compare, for the types that have an order
+fn order(left: &Literal, right: &Literal) -> Option<Ordering> {
+    match (left, right) {
+        (Literal::Int(left), Literal::Int(right)) => Some(left.cmp(right)),
+        (Literal::Int(left), Literal::Float(right)) => (*left as f32).partial_cmp(right),
+        (Literal::Float(left), Literal::Int(right)) => left.partial_cmp(&(*right as f32)),
+        (Literal::Float(left), Literal::Float(right)) => left.partial_cmp(right),
+        (Literal::Char(left), Literal::Char(right)) => Some(left.cmp(right)),
+        (Literal::String(left), Literal::String(right)) => Some(left.cmp(right)),
+        _ => None
+    }
+}
+
+fn type_name(val: &Literal) -> &'static str {
+    match val {
+        Literal::Array(_) => "ARRAY",
+        Literal::READ | Literal::WRITE | Literal::APPEND | Literal::RANDOM | Literal::Type(_) => "TYPE",
+        val => Type::from_literal(val).keyword()
+    }
+}
+fn a(name: &str) -> String {
+    if name.starts_with(['A', 'E', 'I', 'O', 'U']) { format!("an {}", name) } else { format!("a {}", name) }
+}
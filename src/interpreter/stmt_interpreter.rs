@@ -1,173 +1,183 @@
 use crate::{stmt::Stmt, env::{Environment, Decl, Type}, token::Literal, expr::Expr};
+use super::{ops, RuntimeError};
 
 impl super::Interpreter<()> for Stmt {
-    fn interpret(&self, env: &mut Environment) {
+    fn interpret(&self, env: &mut Environment) -> Result<(), RuntimeError> {
+        if !matches!(self, Stmt::Block(_)) {
+            env.step();
+            env.observe(self)?;
+        }
         match self {
             Stmt::Block(stmts) => for stmt in stmts {
-                stmt.interpret(env);
+                stmt.interpret(env)?;
+                if env.returning() { break }
             },
-            Stmt::ExprStmt(expr) => {expr.interpret(env);},
+            Stmt::ExprStmt(expr) => {expr.interpret(env)?;},
             Stmt::Declare(name, dtype) => {
-                let dtype = match dtype.interpret(env) {
+                let dtype = match dtype.interpret(env)? {
                     Literal::Type(inner) => inner,
-                    _ => panic!("expected type")
+                    _ => return Err(RuntimeError::new("expected type".into()))
                 };
                 env.declare(name.lexeme.clone(), Decl::new(true, dtype.clone()));
-                if let Type::Array(inner_type, (_, idx1len), _) = dtype {
-                    let mut default_val = Vec::new();
-                    let lit = match *inner_type {
-                        Type::Bool => Literal::FALSE,
-                        Type::Int => Literal::Int(0),
-                        Type::Char => Literal::Char(0 as char),
-                        Type::String => Literal::String("".into()),
-                        _ => panic!("{:?}", inner_type)
-                    };
-                    default_val.resize(idx1len, lit);
-                    env.assign(name.lexeme.clone(), Literal::Array(default_val));
+                if let Some(val) = dtype.default_value()? {
+                    env.assign(name.lexeme.clone(), Some(0), val)?;
                 }
             },
             Stmt::Constant(name, val) => {
-                let val = val.interpret(env);
-                env.declare(name.lexeme.clone(), Decl::new(false, Type::from_literal(&val)));
-                env.assign(name.lexeme.clone(), val);
+                let val = val.interpret(env)?;
+                env.def_const(&name.lexeme, val);
             },
             Stmt::Assign(name, val) => {
-                let val = val.interpret(env);
-                let (name, idx1) = match name {
-                    Expr::IdentExpr(tkn) => (tkn.lexeme.clone(), None),
-                    Expr::ArrIdx(name, idx1, _) => (name.lexeme.clone(), Some(idx1)),
-                    _ => panic!("expected identifier")
+                let val = val.interpret(env)?;
+                let (name, depth, idx1) = match name {
+                    Expr::IdentExpr(tkn, depth) => (tkn.lexeme.clone(), *depth, None),
+                    Expr::ArrIdx(name, idx1, _, depth) => (name.lexeme.clone(), *depth, Some(idx1)),
+                    _ => return Err(RuntimeError::new("expected identifier".into()))
                 };
                 if let Some(idx1) = idx1 {
-                    let idx1 = if let Literal::Int(n) = idx1.interpret(env) { n as usize }
-                        else { panic!("expected integer") };
-                    env.assign_idx(name, idx1, val);
-                } else { env.assign(name.clone(), val); }
+                    let idx1 = if let Literal::Int(n) = idx1.interpret(env)? { n as usize }
+                        else { return Err(RuntimeError::new("expected integer".into())) };
+                    env.assign_idx(name, depth, idx1, val)?;
+                } else { env.assign(name, depth, val)?; }
             },
             Stmt::ProcCall(name, args) => {
-                let mut arg_list = Vec::new();
-                for arg in args {
-                    let arg_name = if let Expr::IdentExpr(name) = arg {
-                        name.lexeme.clone()
-                    } else { "".into() };
-                    arg_list.push((arg_name, arg.interpret(env)));
-                }
-                env.call_proc(&name.lexeme, arg_list);
+                env.call_proc(&name.lexeme, name.line, args)?;
             },
-            Stmt::Input(expr) => if let Expr::IdentExpr(name) = expr.clone() {
-                let mut val = String::new();
-                std::io::stdin().read_line(&mut val).unwrap();
-                env.assign(name.lexeme.clone(), Literal::String(val.trim().to_string())); // Cut off newline
-            } else { panic!("expected identifier") },
-            Stmt::Output(exprs) => { for expr in exprs {
-                let val = expr.interpret(env);
+            Stmt::Input(expr) => if let Expr::IdentExpr(name, depth) = expr {
+                let val = env.read_line()?;
+                env.assign(name.lexeme.clone(), *depth, Literal::String(val))?;
+            } else { return Err(RuntimeError::new("expected identifier".into())) },
+            Stmt::Output(_, exprs) => { for expr in exprs {
+                let val = expr.interpret(env)?;
                 match val {
-                    Literal::String(val) => print!("{}", val),
-                    Literal::Char(val) => print!("{}", val),
-                    _ => print!("{}", val.to_string())
+                    Literal::String(val) => env.write(&val),
+                    Literal::Char(val) => env.write(&val.to_string()),
+                    _ => env.write(&val.to_string())
                 }
-            } println!("")}, // print newline at the end
-            Stmt::Ret(val) => {
-                let val = val.interpret(env);
-                env.set_ret(val);
+            } env.write("\n")}, // print newline at the end
+            Stmt::Ret(_, val) => {
+                let val = val.interpret(env)?;
+                env.set_ret(val)?;
             },
             Stmt::Procedure(name, args, block) => {
                 let mut arg_list = Vec::new();
                 for arg in args {
-                    arg_list.push((arg.0.lexeme.clone(), match arg.1.interpret(env) {
+                    arg_list.push((arg.0.lexeme.clone(), match arg.1.interpret(env)? {
                         Literal::Type(val) => val,
-                        _ => panic!("expected type")
+                        _ => return Err(RuntimeError::new("expected type".into()))
                     }, arg.2))
                 }
-                env.def_proc(&name.lexeme, arg_list, *block.clone())
+                env.def_proc(&name.lexeme, name.line, arg_list, *block.clone())
             },
             Stmt::Function(name, args, ret_type, block) => {
                 let mut arg_list = Vec::new();
                 for arg in args {
-                    arg_list.push((arg.0.lexeme.clone(), match arg.1.interpret(env) {
+                    arg_list.push((arg.0.lexeme.clone(), match arg.1.interpret(env)? {
                         Literal::Type(val) => val,
-                        _ => panic!("expected type")
+                        _ => return Err(RuntimeError::new("expected type".into()))
                     }, arg.2))
                 }
-                let ret_type = match ret_type.interpret(env) {
+                let ret_type = match ret_type.interpret(env)? {
                     Literal::Type(inner) => inner,
-                    _ => panic!("expected type")
+                    _ => return Err(RuntimeError::new("expected type".into()))
                 };
-                env.def_func(&name.lexeme, arg_list, ret_type, *block.clone())
+                env.def_func(&name.lexeme, name.line, arg_list, ret_type, *block.clone())
             },
             Stmt::ForTo(name, val1, val2, step_opt, block) => {
-                let val1 = val1.interpret(env);
-                let val2 = if let Literal::Int(val) = val2.interpret(env) { val }
-                else { panic!("expected integer expression1") };
+                let val1 = val1.interpret(env)?;
+                let val2 = if let Literal::Int(val) = val2.interpret(env)? { val }
+                else { return Err(RuntimeError::new("expected integer expression1".into())) };
                 let mut step = 1;
                 if let Some(val) = step_opt {
-                    step = match val.interpret(env) {
+                    step = match val.interpret(env)? {
                         Literal::Int(val) => val,
-                        _ => panic!("expected integer expression2")
+                        _ => return Err(RuntimeError::new("expected integer expression2".into()))
                     }
                 }
 
-                let mut inner_env = Environment::new(Some(Box::new(env.clone())));
-                inner_env.declare(name.lexeme.clone(), Decl::new(true, Type::Int));
-                inner_env.assign(name.lexeme.clone(), val1);
-                
-                loop {
-                    block.interpret(&mut inner_env);
-                    let prev = if let Literal::Int(val) =
-                        inner_env.get_stack(&name.lexeme) { val.clone() }
-                    else { panic!("expected integer expression3") };
-                    let next = prev + step;
-                    if next > val2 { break }
-                    inner_env.assign(name.lexeme.clone(), Literal::Int(next));
-                }
+                env.in_scope(|env| {
+                    env.declare(name.lexeme.clone(), Decl::new(true, Type::Int));
+                    env.assign(name.lexeme.clone(), Some(0), val1)?;
 
-                inner_env.update_parent(env);
+                    loop {
+                        block.interpret(env)?;
+                        if env.returning() { break }
+                        env.step();
+                        env.check_limits("FOR", name.line)?;
+                        env.looping(self);
+                        let prev = if let Literal::Int(val) =
+                            env.get_stack(&name.lexeme, Some(0))? { *val }
+                        else { return Err(RuntimeError::new("expected integer expression3".into())) };
+                        let next = prev + step;
+                        if next > val2 { break }
+                        env.assign(name.lexeme.clone(), Some(0), Literal::Int(next))?;
+                    }
+                    Ok(())
+                })?
             },
-            Stmt::IfThen(cond, then_block, else_block) => {
-                let mut inner_env = Environment::new(Some(Box::new(env.clone())));
-                match cond.interpret(env) {
-                    Literal::TRUE => then_block.interpret(&mut inner_env),
-                    Literal::FALSE => match else_block {
-                        Some(block) => block.interpret(&mut inner_env),
-                        None => {}
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                match cond.interpret(env)? {
+                    Literal::TRUE => env.in_scope(|env| then_block.interpret(env))?,
+                    Literal::FALSE => if let Some(block) = else_block {
+                        env.in_scope(|env| block.interpret(env))?
                     },
-                    _ => panic!("expected boolean expression")
+                    _ => return Err(RuntimeError::new("expected boolean expression".into()))
                 };
-                inner_env.update_parent(env);
             },
-            Stmt::Case(val, cases, otherwise) => {
-                let val = val.interpret(env);
-                for case in cases.into_iter() {
-                    if val == case.0.interpret(env) { case.1.interpret(env); return }
+            Stmt::Case(_, val, cases, otherwise) => {
+                let val = val.interpret(env)?;
+                // every clause's values are worked out and checked first, so a clause of the wrong type is
+                // an error even when an earlier one matches
+                let mut clauses = Vec::with_capacity(cases.len());
+                for (vals, _) in cases.iter() {
+                    let mut clause = Vec::with_capacity(vals.len());
+                    for (from, to) in vals {
+                        let from = from.interpret(env)?;
+                        let to = match to {
+                            Some(to) => Some(to.interpret(env)?),
+                            None => None
+                        };
+                        ops::check_case_value(&val, &from, to.as_ref())?;
+                        clause.push((from, to));
+                    }
+                    clauses.push(clause);
+                }
+                for (clause, (_, stmt)) in clauses.iter().zip(cases) {
+                    if clause.iter().any(|(from, to)| ops::case_matches(&val, from, to.as_ref())) { return stmt.interpret(env) }
                 }
                 if let Some(stmt) = otherwise {
-                    stmt.interpret(env)
+                    stmt.interpret(env)?
                 }
             },
-            Stmt::Repeat(cond, block) => {
-                let mut inner_env = Environment::new(Some(Box::new(env.clone())));
-                loop {
-                    block.interpret(&mut inner_env);
-                    match cond.interpret(&mut inner_env) {
-                        Literal::TRUE => {},
-                        Literal::FALSE => break,
-                        _ => panic!("Expected boolean expression")
+            Stmt::Repeat(keyword, cond, block) => {
+                env.in_scope(|env| loop {
+                    block.interpret(env)?;
+                    if env.returning() { return Ok(()) }
+                    env.step();
+                    env.check_limits("REPEAT", keyword.line)?;
+                    env.looping(self);
+                    match cond.interpret(env)? {
+                        Literal::TRUE => return Ok(()),
+                        Literal::FALSE => {},
+                        _ => return Err(RuntimeError::new("Expected boolean expression".into()))
                     }
-                }
-                inner_env.update_parent(env);
+                })?
             },
-            Stmt::WhileDo(cond, block) => {
-                let mut inner_env = Environment::new(Some(Box::new(env.clone())));
-                loop {
-                    match cond.interpret(&mut inner_env) {
+            Stmt::WhileDo(keyword, cond, block) => {
+                env.in_scope(|env| loop {
+                    env.step();
+                    env.check_limits("WHILE", keyword.line)?;
+                    env.looping(self);
+                    match cond.interpret(env)? {
                         Literal::TRUE => {},
-                        Literal::FALSE => break,
-                        _ => panic!("Expected boolean expression")
+                        Literal::FALSE => return Ok(()),
+                        _ => return Err(RuntimeError::new("Expected boolean expression".into()))
                     }
-                    block.interpret(&mut inner_env);
-                }
-                inner_env.update_parent(env);
+                    block.interpret(env)?;
+                    if env.returning() { return Ok(()) }
+                })?
             },
         }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+// converts tokens and syntax trees to and from JSON. The layout is versioned so tools reading it can
+// tell when it changes: bump VERSION whenever a node gains, loses or renames a field.
+use super::{JsonError, Value};
+use crate::env::Type;
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::{Literal, Token, TokenType};
+
+const VERSION: u64 = 2;
+
+// every token type other than literals, which are written by their name
+const TTYPES: &[TokenType] = &[
+    TokenType::Equal, TokenType::LeftBracket, TokenType::RightBracket, TokenType::LeftParen, TokenType::RightParen,
+    TokenType::Colon, TokenType::Comma, TokenType::Period, TokenType::Star, TokenType::Slash, TokenType::Plus,
+    TokenType::Minus, TokenType::Less, TokenType::Greater, TokenType::Caret, TokenType::LessEqual, TokenType::GreaterEqual,
+    TokenType::NotEqual, TokenType::Arrow, TokenType::DATE, TokenType::ARRAY, TokenType::DECLARE, TokenType::CONSTANT,
+    TokenType::CALL, TokenType::INPUT, TokenType::OUTPUT, TokenType::RETURN, TokenType::OPENFILE, TokenType::CLOSEFILE,
+    TokenType::READFILE, TokenType::WRITEFILE, TokenType::GETRECORD, TokenType::PUTRECORD, TokenType::SEEK,
+    TokenType::PROCEDURE, TokenType::ENDPROCEDURE, TokenType::BYREF, TokenType::BYVALUE, TokenType::FUNCTION,
+    TokenType::RETURNS, TokenType::ENDFUNCTION, TokenType::FOR, TokenType::TO, TokenType::STEP, TokenType::ENDFOR,
+    TokenType::IF, TokenType::THEN, TokenType::ELSE, TokenType::ENDIF, TokenType::CASE, TokenType::OF,
+    TokenType::OTHERWISE, TokenType::ENDCASE, TokenType::REPEAT, TokenType::UNTIL, TokenType::WHILE, TokenType::DO,
+    TokenType::ENDWHILE, TokenType::TYPE, TokenType::ENDTYPE, TokenType::MOD, TokenType::DIV, TokenType::AND,
+    TokenType::OR, TokenType::NOT, TokenType::Identifier, TokenType::NL, TokenType::End
+];
+const TYPES: &[Type] = &[Type::Int, Type::Float, Type::Bool, Type::Char, Type::String, Type::Date, Type::Type];
+
+/// The whole document for a program: its tokens, and the syntax tree parsed from them
+pub fn export(tokens: &[Token], prog: &Stmt) -> Value {
+    Value::object(vec![
+        ("format", Value::String("camps-ast".into())),
+        ("version", Value::Number(VERSION as f64)),
+        ("tokens", Value::Array(tokens.iter().map(token).collect())),
+        ("ast", stmt(prog))
+    ])
+}
+
+/// Rebuilds the syntax tree from a document written by `export`
+pub fn import(doc: &Value) -> Result<Stmt, JsonError> {
+    match doc.get("version") {
+        Some(Value::Number(version)) if *version == VERSION as f64 => {},
+        Some(Value::Number(version)) => return Err(JsonError::new(format!("version {} isn't supported, expected {}", version, VERSION))),
+        _ => return Err(JsonError::new("missing \"version\"".into()))
+    }
+    read_stmt(field(doc, "ast")?)
+}
+
+fn token(tkn: &Token) -> Value {
+    let mut entries = match &tkn.ttype {
+        TokenType::Literal(lit) => vec![("type", Value::String("Literal".into())), ("literal", literal(lit))],
+        ttype => vec![("type", Value::String(format!("{:?}", ttype)))]
+    };
+    entries.push(("lexeme", Value::String(tkn.lexeme.clone())));
+    entries.push(("span", Value::object(vec![
+        ("line", Value::Number(tkn.line as f64)),
+        ("col", Value::Number(tkn.col as f64)),
+        ("len", Value::Number(tkn.lexeme.chars().count() as f64))
+    ])));
+    Value::object(entries)
+}
+
+fn literal(lit: &Literal) -> Value {
+    let (dtype, val) = match lit {
+        Literal::TRUE => ("BOOLEAN", Value::Bool(true)),
+        Literal::FALSE => ("BOOLEAN", Value::Bool(false)),
+        Literal::READ | Literal::WRITE | Literal::APPEND | Literal::RANDOM => ("FILEMODE", Value::String(lit.to_string())),
+        Literal::Int(val) => ("INTEGER", Value::Number(*val as f64)),
+        Literal::Float(val) => ("REAL", Value::Number(*val as f64)),
+        Literal::Char(val) => ("CHAR", Value::String(val.to_string())),
+        Literal::String(val) => ("STRING", Value::String(val.clone())),
+        Literal::Date(d, m, y) => ("DATE", Value::Array(vec![Value::Number(*d as f64), Value::Number(*m as f64), Value::Number(*y as f64)])),
+        Literal::Array(vals) => ("ARRAY", Value::Array(vals.iter().map(literal).collect())),
+        Literal::Type(dtype) => ("TYPE", type_value(dtype))
+    };
+    Value::object(vec![("type", Value::String(dtype.into())), ("value", val)])
+}
+
+fn type_value(dtype: &Type) -> Value {
+    match dtype {
+        Type::Array(inner, (start1, len1), idx2) => {
+            let mut bounds = vec![Value::Array(vec![Value::Number(*start1 as f64), Value::Number(*len1 as f64)])];
+            if let Some((start2, len2)) = idx2 { bounds.push(Value::Array(vec![Value::Number(*start2 as f64), Value::Number(*len2 as f64)])) }
+            Value::object(vec![("of", type_value(inner)), ("bounds", Value::Array(bounds))])
+        },
+        dtype => Value::String(dtype.keyword().into())
+    }
+}
+
+fn exprs(exprs: &[Expr]) -> Value {
+    Value::Array(exprs.iter().map(expr).collect())
+}
+fn depth(depth: &Option<usize>) -> Value {
+    depth.map_or(Value::Null, |depth| Value::Number(depth as f64))
+}
+fn node(kind: &str, mut fields: Vec<(&str, Value)>) -> Value {
+    fields.insert(0, ("kind", Value::String(kind.into())));
+    Value::object(fields)
+}
+
+fn expr(expr: &Expr) -> Value {
+    match expr {
+        Expr::Unary(op, right) => node("Unary", vec![("op", token(op)), ("right", self::expr(right))]),
+        Expr::Binary(left, op, right) => node("Binary", vec![("left", self::expr(left)), ("op", token(op)), ("right", self::expr(right))]),
+        Expr::Grouping(inner) => node("Grouping", vec![("inner", self::expr(inner))]),
+        Expr::IdentExpr(name, scope) => node("Ident", vec![("name", token(name)), ("depth", depth(scope))]),
+        Expr::FnCall(name, args) => node("FnCall", vec![("name", token(name)), ("args", exprs(args))]),
+        Expr::ArrIdx(name, idx1, idx2, scope) => {
+            let mut idx = vec![self::expr(idx1)];
+            if let Some(idx2) = idx2 { idx.push(self::expr(idx2)) }
+            node("ArrIdx", vec![("name", token(name)), ("index", Value::Array(idx)), ("depth", depth(scope))])
+        },
+        Expr::ArrType((start1, end1), idx2, dtype) => {
+            let mut bounds = vec![Value::Array(vec![self::expr(start1), self::expr(end1)])];
+            if let Some((start2, end2)) = idx2 { bounds.push(Value::Array(vec![self::expr(start2), self::expr(end2)])) }
+            node("ArrType", vec![("bounds", Value::Array(bounds)), ("of", self::expr(dtype))])
+        },
+        Expr::Literal(lit) => node("Literal", vec![("value", literal(lit))])
+    }
+}
+
+fn params(params: &[(Token, Expr, bool)]) -> Value {
+    Value::Array(params.iter().map(|(name, dtype, byref)| {
+        Value::object(vec![("name", token(name)), ("type", expr(dtype)), ("byref", Value::Bool(*byref))])
+    }).collect())
+}
+fn optional(stmt: &Option<Box<Stmt>>) -> Value {
+    stmt.as_ref().map_or(Value::Null, |stmt| self::stmt(stmt))
+}
+
+fn stmt(stmt: &Stmt) -> Value {
+    match stmt {
+        Stmt::Block(stmts) => node("Block", vec![("body", Value::Array(stmts.iter().map(self::stmt).collect()))]),
+        Stmt::ExprStmt(val) => node("ExprStmt", vec![("expr", expr(val))]),
+        Stmt::Declare(name, dtype) => node("Declare", vec![("name", token(name)), ("type", expr(dtype))]),
+        Stmt::Constant(name, val) => node("Constant", vec![("name", token(name)), ("value", expr(val))]),
+        Stmt::Assign(target, val) => node("Assign", vec![("target", expr(target)), ("value", expr(val))]),
+        Stmt::ProcCall(name, args) => node("Call", vec![("name", token(name)), ("args", exprs(args))]),
+        Stmt::Input(target) => node("Input", vec![("target", expr(target))]),
+        Stmt::Output(keyword, vals) => node("Output", vec![("keyword", token(keyword)), ("values", exprs(vals))]),
+        Stmt::Ret(keyword, val) => node("Return", vec![("keyword", token(keyword)), ("value", expr(val))]),
+        Stmt::Procedure(name, args, block) => node("Procedure", vec![("name", token(name)), ("params", params(args)), ("body", self::stmt(block))]),
+        Stmt::Function(name, args, ret_type, block) => node("Function", vec![("name", token(name)), ("params", params(args)),
+            ("returns", expr(ret_type)), ("body", self::stmt(block))]),
+        Stmt::ForTo(counter, start, end, step, block) => node("For", vec![("counter", token(counter)), ("start", expr(start)),
+            ("end", expr(end)), ("step", step.as_ref().map_or(Value::Null, expr)), ("body", self::stmt(block))]),
+        Stmt::IfThen(keyword, cond, then_block, else_block) => node("If", vec![("keyword", token(keyword)), ("condition", expr(cond)),
+            ("then", self::stmt(then_block)), ("else", optional(else_block))]),
+        Stmt::Case(keyword, val, cases, otherwise) => node("Case", vec![("keyword", token(keyword)), ("value", expr(val)),
+            ("branches", Value::Array(cases.iter().map(|(vals, stmt)| Value::object(vec![
+                ("values", Value::Array(vals.iter().map(|(from, to)| Value::object(vec![
+                    ("from", expr(from)), ("to", to.as_ref().map_or(Value::Null, expr))
+                ])).collect())),
+                ("body", self::stmt(stmt))
+            ])).collect())),
+            ("otherwise", optional(otherwise))]),
+        Stmt::Repeat(keyword, cond, block) => node("Repeat", vec![("keyword", token(keyword)), ("body", self::stmt(block)), ("until", expr(cond))]),
+        Stmt::WhileDo(keyword, cond, block) => node("While", vec![("keyword", token(keyword)), ("condition", expr(cond)), ("body", self::stmt(block))]),
+    }
+}
+
+fn field<'a>(val: &'a Value, key: &str) -> Result<&'a Value, JsonError> {
+    val.get(key).ok_or_else(|| JsonError::new(format!("missing \"{}\" in {}", key, describe(val))))
+}
+// a short description of a node for error messages
+fn describe(val: &Value) -> String {
+    match val.get("kind").or_else(|| val.get("type")) {
+        Some(Value::String(kind)) => kind.clone(),
+        _ => val.to_string().chars().take(40).collect()
+    }
+}
+fn str_field<'a>(val: &'a Value, key: &str) -> Result<&'a str, JsonError> {
+    match field(val, key)? {
+        Value::String(text) => Ok(text),
+        other => Err(JsonError::new(format!("expected \"{}\" to be a string, found {}", key, other.to_string())))
+    }
+}
+fn array_field<'a>(val: &'a Value, key: &str) -> Result<&'a [Value], JsonError> {
+    match field(val, key)? {
+        Value::Array(vals) => Ok(vals),
+        other => Err(JsonError::new(format!("expected \"{}\" to be an array, found {}", key, other.to_string())))
+    }
+}
+fn number(val: &Value) -> Result<f64, JsonError> {
+    match val {
+        Value::Number(num) => Ok(*num),
+        other => Err(JsonError::new(format!("expected a number, found {}", other.to_string())))
+    }
+}
+fn whole(val: &Value) -> Result<usize, JsonError> {
+    let num = number(val)?;
+    if num >= 0.0 && num.fract() == 0.0 { Ok(num as usize) }
+    else { Err(JsonError::new(format!("expected a whole number, found {}", num))) }
+}
+fn pair(val: &Value) -> Result<(&Value, &Value), JsonError> {
+    match val {
+        Value::Array(vals) if vals.len() == 2 => Ok((&vals[0], &vals[1])),
+        other => Err(JsonError::new(format!("expected a pair, found {}", other.to_string())))
+    }
+}
+
+fn read_token(val: &Value) -> Result<Token, JsonError> {
+    let ttype = match str_field(val, "type")? {
+        "Literal" => TokenType::Literal(read_literal(field(val, "literal")?)?),
+        name => TTYPES.iter().find(|ttype| format!("{:?}", ttype) == name).cloned()
+            .ok_or_else(|| JsonError::new(format!("unknown token type {}", name)))?
+    };
+    let lexeme = str_field(val, "lexeme")?.to_string();
+    // the span is optional for hand-written trees
+    let (line, col) = match val.get("span") {
+        Some(span) => (whole(field(span, "line")?)?, whole(field(span, "col")?)?),
+        None => (0, 0)
+    };
+    Ok(Token::new(ttype, lexeme, line).at_col(col))
+}
+
+fn read_literal(val: &Value) -> Result<Literal, JsonError> {
+    let inner = field(val, "value")?;
+    Ok(match (str_field(val, "type")?, inner) {
+        ("BOOLEAN", Value::Bool(true)) => Literal::TRUE,
+        ("BOOLEAN", Value::Bool(false)) => Literal::FALSE,
+        ("FILEMODE", Value::String(mode)) => match mode.as_str() {
+            "READ" => Literal::READ,
+            "WRITE" => Literal::WRITE,
+            "APPEND" => Literal::APPEND,
+            "RANDOM" => Literal::RANDOM,
+            _ => return Err(JsonError::new(format!("unknown file mode {}", mode)))
+        },
+        ("INTEGER", Value::Number(num)) if num.fract() == 0.0 && *num >= i32::MIN as f64 && *num <= i32::MAX as f64 => Literal::Int(*num as i32),
+        ("REAL", Value::Number(num)) => Literal::Float(*num as f32),
+        ("CHAR", Value::String(text)) if text.chars().count() == 1 => Literal::Char(text.chars().next().unwrap()),
+        ("STRING", Value::String(text)) => Literal::String(text.clone()),
+        ("DATE", Value::Array(parts)) if parts.len() == 3 => Literal::Date(number(&parts[0])? as i8, number(&parts[1])? as i8, number(&parts[2])? as i16),
+        ("ARRAY", Value::Array(vals)) => Literal::Array(vals.iter().map(read_literal).collect::<Result<_, _>>()?),
+        ("TYPE", dtype) => Literal::Type(read_type(dtype)?),
+        (dtype, inner) => return Err(JsonError::new(format!("{} isn't a valid {} literal", inner.to_string(), dtype)))
+    })
+}
+
+fn read_type(val: &Value) -> Result<Type, JsonError> {
+    if let Value::String(name) = val {
+        return TYPES.iter().find(|dtype| dtype.keyword() == name).cloned()
+            .ok_or_else(|| JsonError::new(format!("unknown type {}", name)))
+    }
+    let bounds = array_field(val, "bounds")?;
+    let bound = |val: &Value| -> Result<(usize, usize), JsonError> { let (start, len) = pair(val)?; Ok((whole(start)?, whole(len)?)) };
+    let idx1 = bound(bounds.first().ok_or_else(|| JsonError::new("an array type needs bounds".into()))?)?;
+    let idx2 = bounds.get(1).map(bound).transpose()?;
+    Ok(Type::Array(Box::new(read_type(field(val, "of")?)?), idx1, idx2))
+}
+
+fn read_exprs(val: &Value, key: &str) -> Result<Vec<Expr>, JsonError> {
+    array_field(val, key)?.iter().map(read_expr).collect()
+}
+fn read_depth(val: &Value) -> Result<Option<usize>, JsonError> {
+    match val.get("depth") {
+        None | Some(Value::Null) => Ok(None),
+        Some(depth) => whole(depth).map(Some)
+    }
+}
+fn boxed(val: &Value, key: &str) -> Result<Box<Expr>, JsonError> {
+    read_expr(field(val, key)?).map(Box::new)
+}
+
+fn read_expr(val: &Value) -> Result<Expr, JsonError> {
+    Ok(match str_field(val, "kind")? {
+        "Unary" => Expr::Unary(read_token(field(val, "op")?)?, boxed(val, "right")?),
+        "Binary" => Expr::Binary(boxed(val, "left")?, read_token(field(val, "op")?)?, boxed(val, "right")?),
+        "Grouping" => Expr::Grouping(boxed(val, "inner")?),
+        "Ident" => Expr::IdentExpr(read_token(field(val, "name")?)?, read_depth(val)?),
+        "FnCall" => Expr::FnCall(read_token(field(val, "name")?)?, read_exprs(val, "args")?),
+        "ArrIdx" => {
+            let mut idx = read_exprs(val, "index")?.into_iter().map(Box::new);
+            let idx1 = idx.next().ok_or_else(|| JsonError::new("an array index needs at least one index".into()))?;
+            Expr::ArrIdx(read_token(field(val, "name")?)?, idx1, idx.next(), read_depth(val)?)
+        },
+        "ArrType" => {
+            let bounds = array_field(val, "bounds")?;
+            let bound = |val: &Value| -> Result<(Box<Expr>, Box<Expr>), JsonError> {
+                let (start, end) = pair(val)?;
+                Ok((Box::new(read_expr(start)?), Box::new(read_expr(end)?)))
+            };
+            let idx1 = bound(bounds.first().ok_or_else(|| JsonError::new("an array type needs bounds".into()))?)?;
+            Expr::ArrType(idx1, bounds.get(1).map(bound).transpose()?, boxed(val, "of")?)
+        },
+        "Literal" => Expr::Literal(read_literal(field(val, "value")?)?),
+        kind => return Err(JsonError::new(format!("unknown expression kind {}", kind)))
+    })
+}
+
+fn read_params(val: &Value) -> Result<Vec<(Token, Expr, bool)>, JsonError> {
+    array_field(val, "params")?.iter().map(|param| {
+        let byref = matches!(param.get("byref"), Some(Value::Bool(true)));
+        Ok((read_token(field(param, "name")?)?, read_expr(field(param, "type")?)?, byref))
+    }).collect()
+}
+fn read_body(val: &Value, key: &str) -> Result<Box<Stmt>, JsonError> {
+    read_stmt(field(val, key)?).map(Box::new)
+}
+fn read_optional(val: &Value, key: &str) -> Result<Option<Box<Stmt>>, JsonError> {
+    match val.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(stmt) => read_stmt(stmt).map(|stmt| Some(Box::new(stmt)))
+    }
+}
+fn keyword(val: &Value) -> Result<Token, JsonError> {
+    read_token(field(val, "keyword")?)
+}
+
+fn read_stmt(val: &Value) -> Result<Stmt, JsonError> {
+    let expr_field = |key: &str| read_expr(field(val, key)?);
+    let name = || read_token(field(val, "name")?);
+    Ok(match str_field(val, "kind")? {
+        "Block" => Stmt::Block(array_field(val, "body")?.iter().map(read_stmt).collect::<Result<_, _>>()?),
+        "ExprStmt" => Stmt::ExprStmt(expr_field("expr")?),
+        "Declare" => Stmt::Declare(name()?, expr_field("type")?),
+        "Constant" => Stmt::Constant(name()?, expr_field("value")?),
+        "Assign" => Stmt::Assign(expr_field("target")?, expr_field("value")?),
+        "Call" => Stmt::ProcCall(name()?, read_exprs(val, "args")?),
+        "Input" => Stmt::Input(expr_field("target")?),
+        "Output" => Stmt::Output(keyword(val)?, read_exprs(val, "values")?),
+        "Return" => Stmt::Ret(keyword(val)?, expr_field("value")?),
+        "Procedure" => Stmt::Procedure(name()?, read_params(val)?, read_body(val, "body")?),
+        "Function" => Stmt::Function(name()?, read_params(val)?, expr_field("returns")?, read_body(val, "body")?),
+        "For" => {
+            let step = match val.get("step") {
+                None | Some(Value::Null) => None,
+                Some(step) => Some(read_expr(step)?)
+            };
+            Stmt::ForTo(read_token(field(val, "counter")?)?, expr_field("start")?, expr_field("end")?, step, read_body(val, "body")?)
+        },
+        "If" => Stmt::IfThen(keyword(val)?, expr_field("condition")?, read_body(val, "then")?, read_optional(val, "else")?),
+        "Case" => {
+            let branches = array_field(val, "branches")?.iter().map(|branch| {
+                let vals = array_field(branch, "values")?.iter().map(|case| {
+                    let to = match case.get("to") {
+                        None | Some(Value::Null) => None,
+                        Some(to) => Some(read_expr(to)?)
+                    };
+                    Ok((read_expr(field(case, "from")?)?, to))
+                }).collect::<Result<_, JsonError>>()?;
+                Ok((vals, read_stmt(field(branch, "body")?)?))
+            }).collect::<Result<_, JsonError>>()?;
+            Stmt::Case(keyword(val)?, expr_field("value")?, branches, read_optional(val, "otherwise")?)
+        },
+        "Repeat" => Stmt::Repeat(keyword(val)?, expr_field("until")?, read_body(val, "body")?),
+        "While" => Stmt::WhileDo(keyword(val)?, expr_field("condition")?, read_body(val, "body")?),
+        kind => return Err(JsonError::new(format!("unknown statement kind {}", kind)))
+    })
+}
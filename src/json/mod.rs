@@ -0,0 +1,220 @@
+// a small JSON reader and writer, just enough to save and load programs without any dependencies
+mod ast;
+
+pub use ast::{export, import};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>) // keeps the order the keys were written in
+}
+
+pub struct JsonError {
+    pub msg: String
+}
+
+impl JsonError {
+    pub fn new(msg: String) -> Self { Self { msg } }
+    pub fn print(&self) {
+        println!("json err: {}", self.msg)
+    }
+}
+
+impl Value {
+    pub fn object(entries: Vec<(&str, Value)>) -> Self {
+        Value::Object(entries.into_iter().map(|(key, val)| (key.to_string(), val)).collect())
+    }
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, val)| val),
+            _ => None
+        }
+    }
+
+    // written over several lines with two spaces of indentation, but anything short enough stays on one
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let compact = self.to_string();
+        let items: Vec<(Option<&String>, &Value)> = match self {
+            Value::Array(vals) => vals.iter().map(|val| (None, val)).collect(),
+            Value::Object(entries) => entries.iter().map(|(key, val)| (Some(key), val)).collect(),
+            _ => Vec::new()
+        };
+        if items.is_empty() || indent + compact.len() <= 100 {
+            out.push_str(&compact);
+            return
+        }
+        let (open, close) = if let Value::Array(_) = self { ('[', ']') } else { ('{', '}') };
+        out.push(open);
+        for (i, (key, val)) in items.iter().enumerate() {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent + 2));
+            if let Some(key) = key { out.push_str(&format!("{}: ", string(key))) }
+            val.write_pretty(out, indent + 2);
+            if i + 1 < items.len() { out.push(',') }
+        }
+        out.push('\n');
+        out.push_str(&" ".repeat(indent));
+        out.push(close);
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            Value::Null => "null".into(),
+            Value::Bool(val) => val.to_string(),
+            Value::Number(val) => val.to_string(),
+            Value::String(val) => string(val),
+            Value::Array(vals) => format!("[{}]", vals.iter().map(Value::to_string).collect::<Vec<_>>().join(", ")),
+            Value::Object(entries) => {
+                let entries: Vec<String> = entries.iter().map(|(key, val)| format!("{}: {}", string(key), val.to_string())).collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+        }
+    }
+}
+
+fn string(val: &str) -> String {
+    let mut out = String::from('"');
+    for c in val.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn parse(source: &str) -> Result<Value, JsonError> {
+    let mut reader = Reader { chars: source.chars().collect(), current: 0, line: 1 };
+    let val = reader.value()?;
+    reader.whitespace();
+    if reader.current < reader.chars.len() { return Err(reader.error("unexpected text after the end of the document")) }
+    Ok(val)
+}
+
+struct Reader {
+    chars: Vec<char>,
+    current: usize,
+    line: usize
+}
+
+impl Reader {
+    fn error(&self, msg: &str) -> JsonError {
+        JsonError::new(format!("{} at line {}", msg, self.line))
+    }
+    fn peak(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peak()?;
+        self.current += 1;
+        if c == '\n' { self.line += 1 }
+        Some(c)
+    }
+    fn whitespace(&mut self) {
+        while self.peak().is_some_and(|c| c.is_ascii_whitespace()) { self.advance(); }
+    }
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.whitespace();
+        if self.advance() == Some(expected) { Ok(()) }
+        else { Err(self.error(&format!("expected '{}'", expected))) }
+    }
+    fn keyword(&mut self, word: &str, val: Value) -> Result<Value, JsonError> {
+        for expected in word.chars() {
+            if self.advance() != Some(expected) { return Err(self.error(&format!("expected {}", word))) }
+        }
+        Ok(val)
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.whitespace();
+        match self.peak() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('[') => {
+                self.advance();
+                let mut vals = Vec::new();
+                self.whitespace();
+                if self.peak() == Some(']') { self.advance(); return Ok(Value::Array(vals)) }
+                loop {
+                    vals.push(self.value()?);
+                    self.whitespace();
+                    match self.advance() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Value::Array(vals)),
+                        _ => return Err(self.error("expected ',' or ']'"))
+                    }
+                }
+            },
+            Some('{') => {
+                self.advance();
+                let mut entries = Vec::new();
+                self.whitespace();
+                if self.peak() == Some('}') { self.advance(); return Ok(Value::Object(entries)) }
+                loop {
+                    self.whitespace();
+                    if self.peak() != Some('"') { return Err(self.error("expected a key")) }
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    entries.push((key, self.value()?));
+                    self.whitespace();
+                    match self.advance() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Value::Object(entries)),
+                        _ => return Err(self.error("expected ',' or '}'"))
+                    }
+                }
+            },
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.current;
+                while self.peak().is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c)) { self.advance(); }
+                let text: String = self.chars[start..self.current].iter().collect();
+                text.parse().map(Value::Number).map_err(|_| self.error(&format!("{} isn't a number", text)))
+            },
+            Some(c) => Err(self.error(&format!("unexpected character {}", c))),
+            None => Err(self.error("unexpected end of the document"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.advance();
+        let mut out = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.advance() {
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.advance()).collect();
+                        let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                        out.push(c.ok_or_else(|| self.error(&format!("\\u{} isn't a character", hex)))?)
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err(self.error("unterminated string"))
+                },
+                Some(c) => out.push(c),
+                None => return Err(self.error("unterminated string"))
+            }
+        }
+    }
+}
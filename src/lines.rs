@@ -0,0 +1,52 @@
+// where statements and expressions are in the source, for tools that point back at it
+use std::collections::BTreeSet;
+use super::expr::Expr;
+use super::stmt::Stmt;
+
+// the line a statement starts on, if any of its tokens say
+pub fn stmt_line(stmt: &Stmt) -> Option<usize> {
+    match stmt {
+        Stmt::Block(stmts) => stmts.first().and_then(stmt_line),
+        Stmt::ExprStmt(expr) | Stmt::Assign(expr, _) | Stmt::Input(expr) => expr_line(expr),
+        Stmt::Declare(tkn, _) | Stmt::Constant(tkn, _) | Stmt::ProcCall(tkn, _) | Stmt::Output(tkn, _)
+            | Stmt::Ret(tkn, _) | Stmt::Procedure(tkn, _, _) | Stmt::Function(tkn, _, _, _)
+            | Stmt::ForTo(tkn, _, _, _, _) | Stmt::IfThen(tkn, _, _, _) | Stmt::Case(tkn, _, _, _)
+            | Stmt::Repeat(tkn, _, _) | Stmt::WhileDo(tkn, _, _) => Some(tkn.line)
+    }
+}
+pub fn expr_line(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Unary(tkn, _) | Expr::IdentExpr(tkn, _) | Expr::FnCall(tkn, _) | Expr::ArrIdx(tkn, _, _, _) => Some(tkn.line),
+        Expr::Binary(left, _, _) => expr_line(left),
+        Expr::Grouping(inner) | Expr::ArrType((inner, _), _, _) => expr_line(inner),
+        Expr::Literal(_) => None
+    }
+}
+
+// every line a statement that runs starts on, leaving out the headers of procedures and functions
+pub fn stmt_lines(stmt: &Stmt) -> BTreeSet<usize> {
+    let mut lines = BTreeSet::new();
+    collect_lines(stmt, &mut lines);
+    lines
+}
+fn collect_lines(stmt: &Stmt, lines: &mut BTreeSet<usize>) {
+    match stmt {
+        Stmt::Block(stmts) => for stmt in stmts { collect_lines(stmt, lines) },
+        Stmt::Procedure(_, _, block) | Stmt::Function(_, _, _, block) => collect_lines(block, lines),
+        _ => {
+            if let Some(line) = stmt_line(stmt) { lines.insert(line); }
+            match stmt {
+                Stmt::IfThen(_, _, then_block, else_block) => {
+                    collect_lines(then_block, lines);
+                    if let Some(block) = else_block { collect_lines(block, lines) }
+                },
+                Stmt::Case(_, _, cases, otherwise) => {
+                    for (_, stmt) in cases { collect_lines(stmt, lines) }
+                    if let Some(stmt) = otherwise { collect_lines(stmt, lines) }
+                },
+                Stmt::ForTo(_, _, _, _, block) | Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => collect_lines(block, lines),
+                _ => {}
+            }
+        }
+    }
+}
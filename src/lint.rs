@@ -0,0 +1,333 @@
+// warnings about programs that run but probably don't do what their writer meant, pointing out the
+// mistakes students most often make
+use std::collections::{HashMap, HashSet};
+use super::env::Type;
+use super::expr::Expr;
+use super::lines::stmt_line;
+use super::stmt::Stmt;
+use super::token::{Literal, Token, TokenType};
+
+pub struct Warning {
+    pub code: &'static str,
+    pub line: usize,
+    pub msg: String
+}
+
+impl Warning {
+    pub fn print(&self) {
+        println!("warning {} at line {}: {}", self.code, self.line, self.msg)
+    }
+}
+
+pub const USED_BEFORE_ASSIGNED: &str = "W01";
+pub const NEVER_USED: &str = "W02";
+pub const MISSING_RETURN: &str = "W03";
+pub const RETURN_IN_PROCEDURE: &str = "W04";
+pub const COUNTER_ASSIGNED: &str = "W05";
+pub const UNREACHABLE_OTHERWISE: &str = "W06";
+pub const REAL_EQUALITY: &str = "W07";
+pub const CASE_DIFFERENCE: &str = "W08";
+
+/// Checks a resolved program, returning its warnings in the order of the lines they're on
+pub fn lint(prog: &Stmt) -> Vec<Warning> {
+    let mut linter = Linter {
+        warnings: Vec::new(),
+        unit: Unit::default(),
+        globals: HashSet::new(),
+        global_reals: HashSet::new(),
+        real_funcs: HashSet::new(),
+        byref: HashMap::new(),
+        global_refs: HashSet::new(),
+        spellings: HashMap::new()
+    };
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Declare(name, dtype) => {
+                    linter.globals.insert(name.lexeme.clone());
+                    if is_real_type(dtype) { linter.global_reals.insert(name.lexeme.clone()); }
+                },
+                Stmt::Constant(name, _) => { linter.globals.insert(name.lexeme.clone()); },
+                Stmt::Assign(Expr::IdentExpr(name, _) | Expr::ArrIdx(name, _, _, _), _) | Stmt::Input(Expr::IdentExpr(name, _)) => {
+                    linter.globals.insert(name.lexeme.clone());
+                },
+                Stmt::Procedure(name, params, _) | Stmt::Function(name, params, _, _) => {
+                    linter.byref.insert(name.lexeme.clone(), params.iter().map(|(_, _, byref)| *byref).collect());
+                    if let Stmt::Function(_, _, ret_type, _) = stmt {
+                        if is_real_type(ret_type) { linter.real_funcs.insert(name.lexeme.clone()); }
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+    linter.unit.reals = linter.global_reals.clone();
+    linter.stmt(prog);
+
+    // a global only needs to be used somewhere, which may be in a procedure or function
+    let unit = std::mem::take(&mut linter.unit);
+    for (name, line) in &unit.declared {
+        if !unit.refs.contains(name) && !linter.global_refs.contains(name) {
+            linter.warn(NEVER_USED, *line, format!("{} is declared but never used", name))
+        }
+    }
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.line);
+    warnings
+}
+
+// what's known about the main program, or the procedure or function being checked
+#[derive(Default)]
+struct Unit {
+    module: Option<(String, bool)>, // the procedure's or function's name and whether it's a function
+    locals: HashSet<String>, // parameters and variables declared in a procedure or function
+    assigned: HashSet<String>, // variables given a value so far
+    refs: HashSet<String>, // variables used anywhere apart from their declaration
+    declared: Vec<(String, usize)>, // DECLAREd variables and the lines they're declared on
+    warned: HashSet<String>, // variables already warned about being used before they have a value
+    reals: HashSet<String>, // REAL variables, and arrays of them
+    counters: Vec<String> // the counters of the FOR loops the statement being checked is in
+}
+
+struct Linter {
+    warnings: Vec<Warning>,
+    unit: Unit,
+    globals: HashSet<String>,
+    global_reals: HashSet<String>,
+    real_funcs: HashSet<String>, // functions that return a REAL
+    byref: HashMap<String, Vec<bool>>, // which of each procedure's and function's parameters are BYREF
+    global_refs: HashSet<String>, // globals used in a procedure or function
+    spellings: HashMap<String, String> // the first spelling of each name, by its lowercase
+}
+
+fn is_real_type(dtype: &Expr) -> bool {
+    match dtype {
+        Expr::Literal(Literal::Type(Type::Float)) => true,
+        Expr::ArrType(_, _, dtype) => is_real_type(dtype),
+        _ => false
+    }
+}
+
+// whether a statement always ends in a RETURN, however it runs
+fn always_returns(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Ret(..) => true,
+        Stmt::Block(stmts) => stmts.iter().any(always_returns),
+        Stmt::IfThen(_, _, then_block, Some(else_block)) => always_returns(then_block) && always_returns(else_block),
+        Stmt::Case(_, _, cases, Some(otherwise)) => cases.iter().all(|(_, stmt)| always_returns(stmt)) && always_returns(otherwise),
+        // a REPEAT loop's body always runs at least once
+        Stmt::Repeat(_, _, block) => always_returns(block),
+        _ => false
+    }
+}
+
+impl Linter {
+    fn warn(&mut self, code: &'static str, line: usize, msg: String) {
+        self.warnings.push(Warning { code, line, msg })
+    }
+
+    fn spelling(&mut self, name: &Token) {
+        match self.spellings.get(&name.lexeme.to_lowercase()) {
+            Some(first) if *first != name.lexeme => {
+                let msg = format!("{} and {} are different names that differ only in case", first, name.lexeme);
+                // each new spelling is only pointed out once
+                self.spellings.insert(name.lexeme.to_lowercase(), name.lexeme.clone());
+                self.warn(CASE_DIFFERENCE, name.line, msg)
+            },
+            Some(_) => {},
+            None => { self.spellings.insert(name.lexeme.to_lowercase(), name.lexeme.clone()); }
+        }
+    }
+
+    // whether a name belongs to the unit being checked rather than being a global used in it
+    fn is_local(&self, name: &str) -> bool {
+        self.unit.module.is_none() || self.unit.locals.contains(name) || !self.globals.contains(name)
+    }
+
+    fn read(&mut self, name: &Token) {
+        self.spelling(name);
+        let local = self.is_local(&name.lexeme);
+        if !local { self.global_refs.insert(name.lexeme.clone()); }
+        self.unit.refs.insert(name.lexeme.clone());
+        if local && !self.unit.assigned.contains(&name.lexeme) && self.unit.warned.insert(name.lexeme.clone()) {
+            self.warn(USED_BEFORE_ASSIGNED, name.line, format!("{} is used before it's given a value", name.lexeme))
+        }
+    }
+    fn write(&mut self, name: &Token) {
+        self.spelling(name);
+        if !self.is_local(&name.lexeme) { self.global_refs.insert(name.lexeme.clone()); }
+        self.unit.refs.insert(name.lexeme.clone());
+        self.unit.assigned.insert(name.lexeme.clone());
+        if self.unit.counters.contains(&name.lexeme) {
+            self.warn(COUNTER_ASSIGNED, name.line, format!("{} is changed inside the FOR loop it counts, which upsets how many times it runs", name.lexeme))
+        }
+    }
+    fn target(&mut self, target: &Expr) {
+        match target {
+            Expr::IdentExpr(name, _) => self.write(name),
+            Expr::ArrIdx(name, idx1, idx2, _) => {
+                self.expr(idx1);
+                if let Some(idx2) = idx2 { self.expr(idx2) }
+                self.write(name)
+            },
+            _ => self.expr(target)
+        }
+    }
+    fn args(&mut self, name: &Token, args: &[Expr]) {
+        let byref = self.byref.get(&name.lexeme).cloned().unwrap_or_default();
+        for (i, arg) in args.iter().enumerate() {
+            // a BYREF argument may be there to be given a value
+            if byref.get(i) == Some(&true) { self.target(arg) } else { self.expr(arg) }
+        }
+    }
+
+    fn is_real(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Literal(Literal::Float(_)) => true,
+            Expr::IdentExpr(name, _) | Expr::ArrIdx(name, _, _, _) => self.unit.reals.contains(&name.lexeme),
+            Expr::FnCall(name, _) => self.real_funcs.contains(&name.lexeme),
+            Expr::Binary(_, op, _) if op.ttype == TokenType::Slash => true,
+            Expr::Binary(left, op, right) if matches!(op.ttype, TokenType::Plus | TokenType::Minus | TokenType::Star) =>
+                self.is_real(left) || self.is_real(right),
+            Expr::Unary(op, inner) if op.ttype == TokenType::Minus => self.is_real(inner),
+            Expr::Grouping(inner) => self.is_real(inner),
+            _ => false
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary(left, op, right) => {
+                if matches!(op.ttype, TokenType::Equal | TokenType::NotEqual) && (self.is_real(left) || self.is_real(right)) {
+                    self.warn(REAL_EQUALITY, op.line, format!(
+                        "REAL values are compared with {}, which can go wrong because of rounding; check how far apart they are instead", op.lexeme
+                    ))
+                }
+                self.expr(left);
+                self.expr(right)
+            },
+            Expr::Unary(_, inner) | Expr::Grouping(inner) => self.expr(inner),
+            Expr::IdentExpr(name, _) => self.read(name),
+            Expr::ArrIdx(name, idx1, idx2, _) => {
+                self.expr(idx1);
+                if let Some(idx2) = idx2 { self.expr(idx2) }
+                self.read(name)
+            },
+            Expr::FnCall(name, args) => {
+                self.spelling(name);
+                self.args(name, args)
+            },
+            Expr::ArrType(..) | Expr::Literal(_) => {}
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts { self.stmt(stmt) },
+            Stmt::ExprStmt(expr) => self.expr(expr),
+            Stmt::Declare(name, dtype) => {
+                self.spelling(name);
+                self.unit.declared.push((name.lexeme.clone(), name.line));
+                if self.unit.module.is_some() { self.unit.locals.insert(name.lexeme.clone()); }
+                if is_real_type(dtype) { self.unit.reals.insert(name.lexeme.clone()); }
+                else { self.unit.reals.remove(&name.lexeme); }
+                // arrays start with every element set
+                if let Expr::ArrType(..) = dtype { self.unit.assigned.insert(name.lexeme.clone()); }
+            },
+            Stmt::Constant(name, val) => {
+                self.expr(val);
+                self.spelling(name);
+                if self.is_real(val) { self.unit.reals.insert(name.lexeme.clone()); }
+                self.unit.assigned.insert(name.lexeme.clone());
+            },
+            Stmt::Assign(target, val) => {
+                self.expr(val);
+                // a variable that's never declared takes the type of its first value
+                if let Expr::IdentExpr(name, _) = target {
+                    if !self.unit.declared.iter().any(|(declared, _)| *declared == name.lexeme) && self.is_real(val) {
+                        self.unit.reals.insert(name.lexeme.clone());
+                    }
+                }
+                self.target(target)
+            },
+            Stmt::Input(target) => self.target(target),
+            Stmt::Output(_, exprs) => for expr in exprs { self.expr(expr) },
+            Stmt::ProcCall(name, args) => {
+                self.spelling(name);
+                self.args(name, args)
+            },
+            Stmt::Ret(tkn, val) => {
+                if let Some((name, false)) = &self.unit.module {
+                    let msg = format!("RETURN in PROCEDURE {}, but only a FUNCTION can return a value", name);
+                    self.warn(RETURN_IN_PROCEDURE, tkn.line, msg)
+                }
+                self.expr(val)
+            },
+            Stmt::Procedure(name, params, block) | Stmt::Function(name, params, _, block) => {
+                let is_function = matches!(stmt, Stmt::Function(..));
+                self.spelling(name);
+                if is_function && !always_returns(block) {
+                    self.warn(MISSING_RETURN, name.line, format!("FUNCTION {} can reach its end without a RETURN", name.lexeme))
+                }
+                let mut unit = Unit { module: Some((name.lexeme.clone(), is_function)), ..Unit::default() };
+                unit.reals = self.global_reals.clone();
+                for (param, dtype, _) in params {
+                    self.spelling(param);
+                    unit.locals.insert(param.lexeme.clone());
+                    unit.assigned.insert(param.lexeme.clone());
+                    unit.refs.insert(param.lexeme.clone());
+                    if is_real_type(dtype) { unit.reals.insert(param.lexeme.clone()); }
+                    else { unit.reals.remove(&param.lexeme); }
+                }
+                let outer = std::mem::replace(&mut self.unit, unit);
+                self.stmt(block);
+                let unit = std::mem::replace(&mut self.unit, outer);
+                for (name, line) in &unit.declared {
+                    if !unit.refs.contains(name) { self.warn(NEVER_USED, *line, format!("{} is declared but never used", name)) }
+                }
+            },
+            Stmt::ForTo(counter, start, end, step, block) => {
+                self.expr(start);
+                self.expr(end);
+                if let Some(step) = step { self.expr(step) }
+                self.write(counter);
+                self.unit.counters.push(counter.lexeme.clone());
+                self.stmt(block);
+                self.unit.counters.pop();
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                self.expr(cond);
+                self.stmt(then_block);
+                if let Some(block) = else_block { self.stmt(block) }
+            },
+            Stmt::Case(tkn, selector, cases, otherwise) => {
+                self.expr(selector);
+                for (vals, stmt) in cases {
+                    for (from, to) in vals {
+                        self.expr(from);
+                        if let Some(to) = to { self.expr(to) }
+                    }
+                    self.stmt(stmt);
+                }
+                if let Some(otherwise) = otherwise {
+                    let covers = |lit: Literal| cases.iter().flat_map(|(vals, _)| vals)
+                        .any(|(val, to)| to.is_none() && matches!(val, Expr::Literal(val) if *val == lit));
+                    if covers(Literal::TRUE) && covers(Literal::FALSE) {
+                        let line = stmt_line(otherwise).unwrap_or(tkn.line);
+                        self.warn(UNREACHABLE_OTHERWISE, line, "OTHERWISE can never be reached, because the cases cover both TRUE and FALSE".into())
+                    }
+                    self.stmt(otherwise)
+                }
+            },
+            Stmt::Repeat(_, cond, block) => {
+                self.stmt(block);
+                self.expr(cond)
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                self.expr(cond);
+                self.stmt(block)
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// what the language server knows about one open document: its errors, and where each name is defined
+use crate::expr::Expr;
+use crate::formatter::format_expr;
+use crate::parser::Parser;
+use crate::resolver;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use crate::token::{Token, TokenType};
+
+// LSP symbol kinds
+pub const FUNCTION: u8 = 12;
+pub const VARIABLE: u8 = 13;
+pub const CONSTANT: u8 = 14;
+
+pub struct Diagnostic {
+    pub line: usize, // from 1, like tokens
+    pub col: usize, // from 1, or 0 for the whole line
+    pub len: usize,
+    pub msg: String
+}
+
+pub struct Def {
+    pub name: Token,
+    pub kind: u8,
+    pub detail: String, // the line that defines it, shown on hover
+    pub lines: (usize, usize), // the lines a procedure or function covers, or just the definition's line
+    pub children: Vec<Def> // a procedure's or function's parameters and local variables
+}
+
+pub struct Analysis {
+    pub tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub defs: Vec<Def>
+}
+
+pub fn analyse(source: &str) -> Analysis {
+    let mut analysis = Analysis { tokens: Vec::new(), diagnostics: Vec::new(), defs: Vec::new() };
+    let tokens = match Scanner::new(source.to_string()).scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            analysis.diagnostics.push(Diagnostic { line: err.line, col: 0, len: 0, msg: err.msg });
+            return analysis
+        }
+    };
+    analysis.tokens = tokens.clone();
+    let mut prog = match Parser::new(tokens).try_parse() {
+        Ok(prog) => prog,
+        Err(err) => {
+            let (line, col, len) = match err.token.ttype {
+                TokenType::End | TokenType::NL => (err.token.line, 0, 0),
+                _ => (err.token.line, err.token.col, err.token.lexeme.chars().count())
+            };
+            analysis.diagnostics.push(Diagnostic { line, col, len, msg: err.msg });
+            return analysis
+        }
+    };
+    if let Err(err) = resolver::resolve(&mut prog) {
+        analysis.diagnostics.push(Diagnostic { line: err.line, col: 0, len: 0, msg: err.msg });
+    }
+    if let Stmt::Block(stmts) = &prog {
+        for stmt in stmts { collect(stmt, &mut analysis.defs, &analysis.tokens) }
+    }
+    analysis
+}
+
+impl Analysis {
+    // the identifier at a position, which counts as on it when just after its last character
+    pub fn ident_at(&self, line: usize, col: usize) -> Option<&Token> {
+        self.tokens.iter().find(|tkn| {
+            tkn.ttype == TokenType::Identifier && tkn.line == line && tkn.col <= col && col <= tkn.col + tkn.lexeme.chars().count()
+        })
+    }
+
+    // the definition a name on `line` refers to, looking in the procedure or function the line is
+    // in before the main program
+    pub fn lookup(&self, name: &str, line: usize) -> Option<&Def> {
+        let enclosing = self.defs.iter().find(|def| def.kind == FUNCTION && def.lines.0 <= line && line <= def.lines.1);
+        enclosing.and_then(|def| def.children.iter().find(|child| child.name.lexeme == name))
+            .or_else(|| self.defs.iter().find(|def| def.name.lexeme == name))
+    }
+
+    // every name that can be used on `line`
+    pub fn visible(&self, line: usize) -> Vec<&Def> {
+        let mut defs: Vec<&Def> = self.defs.iter().collect();
+        if let Some(def) = self.defs.iter().find(|def| def.kind == FUNCTION && def.lines.0 <= line && line <= def.lines.1) {
+            defs.extend(def.children.iter());
+        }
+        defs
+    }
+}
+
+fn def(name: &Token, kind: u8, detail: String) -> Def {
+    Def { name: name.clone(), kind, detail, lines: (name.line, name.line), children: Vec::new() }
+}
+// adds a definition unless the name already has one in this scope
+fn define(defs: &mut Vec<Def>, new: Def) {
+    if !defs.iter().any(|def| def.name.lexeme == new.name.lexeme) { defs.push(new) }
+}
+
+fn signature(params: &[(Token, Expr, bool)]) -> String {
+    if params.is_empty() { return String::new() }
+    let params: Vec<String> = params.iter().map(|(name, dtype, byref)| {
+        format!("{}{} : {}", if *byref { "BYREF " } else { "" }, name.lexeme, format_expr(dtype))
+    }).collect();
+    format!("({})", params.join(", "))
+}
+
+// the line of the keyword that ends the procedure or function starting on `line`
+fn end_line(tokens: &[Token], line: usize) -> usize {
+    tokens.iter().find(|tkn| tkn.line > line && matches!(tkn.ttype, TokenType::ENDPROCEDURE | TokenType::ENDFUNCTION))
+        .map_or(line, |tkn| tkn.line)
+}
+
+fn collect(stmt: &Stmt, defs: &mut Vec<Def>, tokens: &[Token]) {
+    match stmt {
+        Stmt::Procedure(name, params, block) | Stmt::Function(name, params, _, block) => {
+            let detail = match stmt {
+                Stmt::Function(_, _, ret_type, _) => format!("FUNCTION {}{} RETURNS {}", name.lexeme, signature(params), format_expr(ret_type)),
+                _ => format!("PROCEDURE {}{}", name.lexeme, signature(params))
+            };
+            let mut new = def(name, FUNCTION, detail);
+            new.lines.1 = end_line(tokens, name.line);
+            for (param, dtype, byref) in params {
+                let detail = format!("{}{} : {}", if *byref { "BYREF " } else { "" }, param.lexeme, format_expr(dtype));
+                define(&mut new.children, def(param, VARIABLE, detail));
+            }
+            collect(block, &mut new.children, tokens);
+            define(defs, new)
+        },
+        Stmt::Declare(name, dtype) => define(defs, def(name, VARIABLE, format!("DECLARE {} : {}", name.lexeme, format_expr(dtype)))),
+        Stmt::Constant(name, val) => define(defs, def(name, CONSTANT, format!("CONSTANT {} = {}", name.lexeme, format_expr(val)))),
+        // a variable that's never declared is defined where it's first given a value
+        Stmt::Assign(Expr::IdentExpr(name, _), val) => define(defs, def(name, VARIABLE, format!("{} <- {}", name.lexeme, format_expr(val)))),
+        Stmt::Input(Expr::IdentExpr(name, _)) => define(defs, def(name, VARIABLE, format!("INPUT {}", name.lexeme))),
+        Stmt::ForTo(counter, start, end, _, block) => {
+            define(defs, def(counter, VARIABLE, format!("FOR {} <- {} TO {}", counter.lexeme, format_expr(start), format_expr(end))));
+            collect(block, defs, tokens)
+        },
+        Stmt::Block(stmts) => for stmt in stmts { collect(stmt, defs, tokens) },
+        Stmt::IfThen(_, _, then_block, else_block) => {
+            collect(then_block, defs, tokens);
+            if let Some(block) = else_block { collect(block, defs, tokens) }
+        },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { collect(stmt, defs, tokens) }
+            if let Some(stmt) = otherwise { collect(stmt, defs, tokens) }
+        },
+        Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => collect(block, defs, tokens),
+        Stmt::ExprStmt(_) | Stmt::Assign(..) | Stmt::ProcCall(..) | Stmt::Input(_) | Stmt::Output(..) | Stmt::Ret(..) => {}
+    }
+}
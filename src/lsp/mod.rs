@@ -0,0 +1,231 @@
+// a Language Server Protocol server over stdin and stdout, so editors can show errors, types and
+// definitions while a program is being written
+mod analysis;
+
+use std::collections::HashMap;
+use std::io::{BufRead, Result, Write};
+use analysis::{Analysis, Def, Diagnostic};
+use super::json::{self, Value};
+use super::scanner::Scanner;
+
+struct Server {
+    documents: HashMap<String, Analysis>,
+    keywords: Vec<String>,
+    shutdown: bool
+}
+
+pub fn run() -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut output = std::io::stdout();
+    let mut keywords: Vec<String> = Scanner::new(String::new()).keywords().map(String::from).collect();
+    keywords.sort();
+    let mut server = Server { documents: HashMap::new(), keywords, shutdown: false };
+
+    while let Some(body) = read_message(&mut input)? {
+        let msg = match json::parse(&body) {
+            Ok(msg) => msg,
+            // stdout is for the protocol, so problems go to the editor's log on stderr
+            Err(err) => { eprintln!("json err: {}", err.msg); continue }
+        };
+        let method = str_at(&msg, &["method"]).unwrap_or_default().to_string();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        if method == "exit" { std::process::exit(if server.shutdown { 0 } else { 1 }) }
+
+        let replies = server.handle(&method, &params);
+        // requests have an id to answer, notifications don't
+        if let Some(id) = msg.get("id") {
+            let reply = match replies.result {
+                Some(result) => Value::object(vec![("jsonrpc", Value::String("2.0".into())), ("id", id.clone()), ("result", result)]),
+                None => Value::object(vec![("jsonrpc", Value::String("2.0".into())), ("id", id.clone()), ("error", Value::object(vec![
+                    ("code", Value::Number(-32601.0)),
+                    ("message", Value::String(format!("{} isn't supported", method)))
+                ]))])
+            };
+            write_message(&mut output, &reply)?;
+        }
+        for notification in replies.notifications { write_message(&mut output, &notification)? }
+    }
+    Ok(())
+}
+
+// messages are a Content-Length header, a blank line, then that many bytes of JSON, which the
+// debug adapter protocol uses too
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<String>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 { return Ok(None) }
+        let line = line.trim_end();
+        if line.is_empty() { break }
+        if let Some(val) = line.strip_prefix("Content-Length:") { len = val.trim().parse::<usize>().ok() }
+    }
+    let mut body = vec![0; len.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+pub fn write_message(output: &mut impl Write, msg: &Value) -> Result<()> {
+    let body = msg.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn str_at<'a>(val: &'a Value, path: &[&str]) -> Option<&'a str> {
+    match path.iter().try_fold(val, |val, key| val.get(key))? {
+        Value::String(text) => Some(text),
+        _ => None
+    }
+}
+fn num_at(val: &Value, path: &[&str]) -> Option<usize> {
+    match path.iter().try_fold(val, |val, key| val.get(key))? {
+        Value::Number(num) if *num >= 0.0 => Some(*num as usize),
+        _ => None
+    }
+}
+fn num(val: usize) -> Value {
+    Value::Number(val as f64)
+}
+
+// positions in the protocol count lines and characters from 0, tokens count them from 1
+fn position(line: usize, col: usize) -> Value {
+    Value::object(vec![("line", num(line.saturating_sub(1))), ("character", num(col.saturating_sub(1)))])
+}
+fn range(line: usize, col: usize, len: usize) -> Value {
+    Value::object(vec![("start", position(line, col)), ("end", position(line, col + len))])
+}
+fn def_range(def: &Def) -> (Value, Value) {
+    let name = range(def.name.line, def.name.col, def.name.lexeme.chars().count());
+    // a procedure's range runs to the end of the line its closing keyword is on
+    let whole = Value::object(vec![("start", position(def.lines.0, 1)), ("end", position(def.lines.1 + 1, 1))]);
+    (whole, name)
+}
+
+struct Replies {
+    result: Option<Value>, // None if the method isn't supported
+    notifications: Vec<Value>
+}
+
+impl Server {
+    fn handle(&mut self, method: &str, params: &Value) -> Replies {
+        let mut notifications = Vec::new();
+        let uri = str_at(params, &["textDocument", "uri"]).unwrap_or_default().to_string();
+        let result = match method {
+            "initialize" => Some(Value::object(vec![
+                ("capabilities", Value::object(vec![
+                    ("textDocumentSync", num(1)), // the whole document is sent on every change
+                    ("hoverProvider", Value::Bool(true)),
+                    ("definitionProvider", Value::Bool(true)),
+                    ("documentSymbolProvider", Value::Bool(true)),
+                    ("completionProvider", Value::object(vec![]))
+                ])),
+                ("serverInfo", Value::object(vec![("name", Value::String("camps".into()))]))
+            ])),
+            "shutdown" => { self.shutdown = true; Some(Value::Null) },
+            "initialized" | "$/cancelRequest" | "textDocument/didSave" => Some(Value::Null),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = match method {
+                    "textDocument/didOpen" => str_at(params, &["textDocument", "text"]),
+                    // with full syncing the last change is the whole document
+                    _ => match params.get("contentChanges") {
+                        Some(Value::Array(changes)) => changes.last().and_then(|change| str_at(change, &["text"])),
+                        _ => None
+                    }
+                };
+                if let Some(text) = text {
+                    let analysis = std::panic::catch_unwind(|| analysis::analyse(text)).unwrap_or_else(|_| Analysis {
+                        tokens: Vec::new(), defs: Vec::new(),
+                        diagnostics: vec![Diagnostic { line: 1, col: 0, len: 0, msg: "the checker crashed on this program".into() }]
+                    });
+                    notifications.push(diagnostics(&uri, &analysis.diagnostics));
+                    self.documents.insert(uri, analysis);
+                }
+                Some(Value::Null)
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                notifications.push(diagnostics(&uri, &[]));
+                Some(Value::Null)
+            },
+            "textDocument/hover" => Some(self.at_cursor(&uri, params, |def| Value::object(vec![
+                ("contents", Value::object(vec![
+                    ("kind", Value::String("markdown".into())),
+                    ("value", Value::String(format!("```\n{}\n```", def.detail)))
+                ]))
+            ]))),
+            "textDocument/definition" => Some(self.at_cursor(&uri, params, |def| Value::object(vec![
+                ("uri", Value::String(uri.clone())),
+                ("range", def_range(def).1)
+            ]))),
+            "textDocument/documentSymbol" => Some(match self.documents.get(&uri) {
+                Some(analysis) => Value::Array(analysis.defs.iter().map(symbol).collect()),
+                None => Value::Array(Vec::new())
+            }),
+            "textDocument/completion" => {
+                let line = num_at(params, &["position", "line"]).unwrap_or(0) + 1;
+                let mut items: Vec<Value> = self.keywords.iter().map(|keyword| completion(keyword, 14, None)).collect();
+                if let Some(analysis) = self.documents.get(&uri) {
+                    for def in analysis.visible(line) {
+                        let kind = match def.kind { analysis::FUNCTION => 3, analysis::CONSTANT => 21, _ => 6 };
+                        items.push(completion(&def.name.lexeme, kind, Some(&def.detail)));
+                    }
+                }
+                Some(Value::Array(items))
+            },
+            // notifications that aren't understood can be ignored
+            _ if method.starts_with("$/") => Some(Value::Null),
+            _ => None
+        };
+        Replies { result, notifications }
+    }
+
+    // the definition of the identifier at the request's position, turned into a reply
+    fn at_cursor(&self, uri: &str, params: &Value, reply: impl Fn(&Def) -> Value) -> Value {
+        let (Some(line), Some(character)) = (num_at(params, &["position", "line"]), num_at(params, &["position", "character"])) else {
+            return Value::Null
+        };
+        let Some(analysis) = self.documents.get(uri) else { return Value::Null };
+        analysis.ident_at(line + 1, character + 1)
+            .and_then(|tkn| analysis.lookup(&tkn.lexeme, tkn.line))
+            .map_or(Value::Null, reply)
+    }
+}
+
+fn diagnostics(uri: &str, diagnostics: &[Diagnostic]) -> Value {
+    let diagnostics = diagnostics.iter().map(|diagnostic| {
+        // errors that only know their line cover all of it
+        let range = match diagnostic.col {
+            0 => Value::object(vec![("start", position(diagnostic.line, 1)), ("end", position(diagnostic.line + 1, 1))]),
+            col => range(diagnostic.line, col, diagnostic.len)
+        };
+        Value::object(vec![
+            ("range", range),
+            ("severity", num(1)),
+            ("source", Value::String("camps".into())),
+            ("message", Value::String(diagnostic.msg.clone()))
+        ])
+    }).collect();
+    Value::object(vec![
+        ("jsonrpc", Value::String("2.0".into())),
+        ("method", Value::String("textDocument/publishDiagnostics".into())),
+        ("params", Value::object(vec![("uri", Value::String(uri.into())), ("diagnostics", Value::Array(diagnostics))]))
+    ])
+}
+
+fn symbol(def: &Def) -> Value {
+    let (range, selection) = def_range(def);
+    let mut fields = vec![
+        ("name", Value::String(def.name.lexeme.clone())),
+        ("detail", Value::String(def.detail.clone())),
+        ("kind", num(def.kind as usize)),
+        ("range", range),
+        ("selectionRange", selection)
+    ];
+    if !def.children.is_empty() { fields.push(("children", Value::Array(def.children.iter().map(symbol).collect()))) }
+    Value::object(fields)
+}
+
+fn completion(label: &str, kind: usize, detail: Option<&str>) -> Value {
+    let mut fields = vec![("label", Value::String(label.into())), ("kind", num(kind))];
+    if let Some(detail) = detail { fields.push(("detail", Value::String(detail.into()))) }
+    Value::object(fields)
+}
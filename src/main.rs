@@ -1,5 +1,8 @@
+// keyword token/literal names mirror the pseudocode keywords they stand for
+#![allow(clippy::upper_case_acronyms, clippy::enum_variant_names, clippy::inherent_to_string)]
+
 mod scanner;
-use env::Environment;
+use env::{Environment, Limits};
 use parser::Parser;
 use scanner::Scanner;
 mod parser;
@@ -10,40 +13,325 @@ mod stmt;
 mod pprint;
 mod interpreter;
 mod env;
+mod resolver;
+mod optimizer;
+mod cases;
+mod vm;
+mod transpile;
+mod formatter;
+mod debugger;
+mod lines;
+mod json;
+mod lsp;
+mod dap;
+mod trace;
+mod profile;
+mod flowchart;
+mod lint;
+mod syllabus;
 
-use std::io::{Read, Result};
+use std::io::{Result, Write};
 use interpreter::Interpreter;
 
 fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut limits = Limits::default();
+    match take_option(&mut args, "--max-steps").map(|val| val.parse::<u64>()) {
+        Some(Ok(0)) => limits.max_steps = None,
+        Some(Ok(n)) => limits.max_steps = Some(n),
+        Some(Err(_)) => { eprintln!("--max-steps expects a whole number"); std::process::exit(1) },
+        None => {}
+    }
+    match take_option(&mut args, "--timeout").map(|val| val.parse::<f64>()) {
+        Some(Ok(secs)) if secs > 0.0 => limits.timeout = Some(std::time::Duration::from_secs_f64(secs)),
+        Some(_) => { eprintln!("--timeout expects a number of seconds"); std::process::exit(1) },
+        None => {}
+    }
+    match take_option(&mut args, "--max-depth").map(|val| val.parse::<usize>()) {
+        Some(Ok(0)) => limits.max_depth = None,
+        Some(Ok(n)) => limits.max_depth = Some(n),
+        Some(Err(_)) => { eprintln!("--max-depth expects a whole number"); std::process::exit(1) },
+        None => {}
+    }
+    let trace_format = match take_option(&mut args, "--trace-format").map(|name| trace::Format::from_name(&name)) {
+        Some(Some(format)) => format,
+        Some(None) => { eprintln!("--trace-format expects text, csv or markdown"); std::process::exit(1) },
+        None => trace::Format::Text
+    };
+    let tracer = match take_option(&mut args, "--trace").map(|vars| trace::Tracer::new(&vars, trace_format)) {
+        Some(Ok(tracer)) => Some(tracer),
+        Some(Err(err)) => { eprintln!("--trace {}", err); std::process::exit(1) },
+        None => None
+    };
+    let syllabus = match take_option(&mut args, "--syllabus").map(|name| syllabus::Syllabus::from_name(&name)) {
+        Some(Some(syllabus)) => Some(syllabus),
+        Some(None) => { eprintln!("--syllabus expects 0478 or 9618"); std::process::exit(1) },
+        None => None
+    };
+    let profile = take_flag(&mut args, "--profile");
+    let use_vm = take_flag(&mut args, "--vm");
+    // the tracer and profiler watch each statement of the syntax tree, which the VM doesn't run
+    if use_vm && (tracer.is_some() || profile) { println!("--vm can't be used with --trace or --profile"); return Ok(()) }
+    let explain_opt = take_flag(&mut args, "--explain-opt");
+    let opt = if take_flag(&mut args, "--optimize") || explain_opt { Some(explain_opt) } else { None };
+
+    // anything the interpreter can't handle yet still panics, so report it like a runtime error
+    std::panic::set_hook(Box::new(|info| {
+        let msg = match info.payload().downcast_ref::<&str>() {
+            Some(msg) => msg.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(msg) => msg.clone(),
+                None => "unknown error".to_string()
+            }
+        };
+        eprintln!("runtime error: {}", msg);
+    }));
 
-    if args.len() == 2 {
-        parse_file(args[1].clone())?;
-    } else if args.len() == 1 {
-        parse_file("source.txt".to_string())?;
+    let to = if args.get(1).is_some_and(|arg| arg == "transpile") { take_option(&mut args, "--to") } else { None };
+    let write = args.get(1).is_some_and(|arg| arg == "fmt") && take_flag(&mut args, "--write");
+    let sexp = args.get(1).is_some_and(|arg| arg == "ast") && take_flag(&mut args, "--sexp");
+    let chart_format = if args.get(1).is_some_and(|arg| arg == "flowchart" || arg == "structure") { take_option(&mut args, "--format") } else { None };
+    let to_json = args.get(1).is_some_and(|arg| arg == "ast") && take_flag(&mut args, "--json");
+    let from_json = take_flag(&mut args, "--from-json");
+
+    if args.len() == 4 && args[1] == "test" {
+        test_file(args[2].clone(), args[3].clone())?;
+    } else if args.len() == 3 && args[1] == "transpile" {
+        transpile_file(args[2].clone(), to.as_deref().unwrap_or("python"), from_json, syllabus)?;
+    } else if args.len() == 3 && args[1] == "flowchart" {
+        flowchart_file(args[2].clone(), chart_format.as_deref().unwrap_or("mermaid"), false, from_json, syllabus)?;
+    } else if args.len() == 3 && args[1] == "structure" {
+        flowchart_file(args[2].clone(), chart_format.as_deref().unwrap_or("mermaid"), true, from_json, syllabus)?;
+    } else if args.len() == 3 && args[1] == "lint" {
+        lint_file(args[2].clone(), from_json, syllabus)?;
+    } else if args.len() == 3 && args[1] == "fmt" {
+        format_file(args[2].clone(), write)?;
+    } else if args.len() == 3 && args[1] == "ast" {
+        print_ast(args[2].clone(), sexp, to_json, from_json, syllabus)?;
+    } else if args.len() == 3 && args[1] == "debug" {
+        let observer = debugger::Debugger::new(&std::fs::read_to_string(&args[2])?);
+        parse_file(args[2].clone(), limits, false, opt, from_json, syllabus, Some(Box::new(observer)))?;
+    } else if args.len() == 2 && args[1] == "lsp" {
+        lsp::run()?;
+    } else if args.len() == 2 && args[1] == "dap" {
+        dap::run()?;
+    } else if args.len() <= 2 {
+        let path = args.get(1).cloned().unwrap_or("source.txt".to_string());
+        let observer: Option<Box<dyn env::Observer>> = if profile {
+            // a syntax tree saved as JSON has no source to show
+            let source = if from_json { String::new() } else { std::fs::read_to_string(&path).unwrap_or_default() };
+            Some(Box::new(profile::Profiler::new(&source)))
+        } else {
+            tracer.map(|tracer| Box::new(tracer) as Box<dyn env::Observer>)
+        };
+        parse_file(path, limits, use_vm, opt, from_json, syllabus, observer)?;
     } else {
-        println!(r#"Usage: camps <file name>"#)
+        println!(r#"Usage: camps [options] <file name>
+       camps test <file name> <test cases file>
+       camps transpile --to <language> <file name>
+       camps flowchart [--format dot | mermaid] <file name>
+       camps structure [--format dot | mermaid] <file name>
+       camps lint <file name>
+       camps fmt [--write] <file name>
+       camps ast [--sexp | --json] <file name>
+       camps debug <file name>
+       camps lsp
+       camps dap
+
+Options:
+    --max-steps <n>     stop loops after n statements have run (default {}, 0 for no limit)
+    --timeout <secs>    stop loops after the program has run for this long
+    --max-depth <n>     maximum depth of procedure and function calls (default {}, 0 for as deep as memory allows)
+    --vm                compile the program to bytecode and run that instead of the syntax tree
+    --optimize          simplify constant expressions and remove code that can never run first
+    --explain-opt       like --optimize, and list each change it makes
+    --from-json         read a syntax tree saved by `camps ast --json` instead of pseudocode
+    --trace <names>     print a trace table of these variables (like `Count,Total,Nums[1]`) and OUTPUT
+    --trace-format <f>  lay the trace table out as text (the default), csv or markdown
+    --profile           list the program with how many times each line ran, and time each call
+    --syllabus <code>   only allow the pseudocode in the 0478 (IGCSE and O Level) or 9618 (A Level) syllabus"#,
+            env::DEFAULT_MAX_STEPS, env::DEFAULT_MAX_DEPTH)
     }
 
     Ok(())
 }
 
-fn parse_file(path: String) -> Result<()> {
-    let mut file = std::fs::File::open(path)?;
-    let mut contents = String::default();
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+// removes `name <value>` from the arguments and returns the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|arg| arg == name)?;
+    args.remove(idx);
+    if idx < args.len() { Some(args.remove(idx)) }
+    else { Some(String::new()) }
+}
 
-    file.read_to_string(&mut contents)?;
+// removes `name` from the arguments and returns whether it was there
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(idx) => { args.remove(idx); true },
+        None => false
+    }
+}
 
+// reads, parses and resolves a program, printing any errors. With `from_json` the file holds a
+// syntax tree exported by `camps ast --json` instead of pseudocode, and with a `syllabus` anything
+// outside it is an error
+fn load(path: &str, from_json: bool, syllabus: Option<syllabus::Syllabus>) -> Result<Option<stmt::Stmt>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut prog = if from_json {
+        match json::parse(&contents).and_then(|doc| json::import(&doc)) {
+            Ok(prog) => prog,
+            Err(err) => { err.print(); return Ok(None) }
+        }
+    } else {
+        match parse(contents, syllabus) {
+            Some((_, prog)) => prog,
+            None => return Ok(None)
+        }
+    };
+    if let Err(err) = resolver::resolve(&mut prog) { err.print(); return Ok(None) }
+    Ok(Some(prog))
+}
+
+// scans and parses a program, printing any errors
+fn parse(contents: String, syllabus: Option<syllabus::Syllabus>) -> Option<(Vec<token::Token>, stmt::Stmt)> {
     let mut scanner = Scanner::new(contents);
-    match scanner.scan_tokens() {
-        Ok(tokens) => {
-            let mut parser = Parser::new(tokens);
-            if let Some(prog) = parser.parse() {
-                let mut env = Environment::new(None);
-                prog.interpret(&mut env);
-            }
+    let tokens = match scanner.scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(err) => { err.print(); return None }
+    };
+    if let Some(syllabus) = syllabus {
+        let errors = syllabus::check(&tokens, syllabus);
+        for err in &errors { err.print() }
+        if !errors.is_empty() { return None }
+    }
+    Parser::new(tokens.clone()).parse().map(|prog| (tokens, prog))
+}
+
+// `opt` is whether to optimize the program first, and if so whether to explain the changes, and
+// `observer` watches each statement run
+fn parse_file(path: String, limits: Limits, use_vm: bool, opt: Option<bool>, from_json: bool, syllabus: Option<syllabus::Syllabus>, observer: Option<Box<dyn env::Observer>>) -> Result<()> {
+    let mut prog = match load(&path, from_json, syllabus)? {
+        Some(prog) => prog,
+        None => return Ok(())
+    };
+    if let Some(explain) = opt {
+        let changes = optimizer::optimize(&mut prog);
+        if explain {
+            for change in changes { eprintln!("optimizer: {}", change) }
+        }
+    }
+    // each pseudocode call takes several native stack frames, so give the
+    // interpreter enough stack to reach the recursion limit
+    let interpreter = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            if use_vm { return vm::run(&prog, limits, observer) }
+            let mut env = Environment::new();
+            env.set_stack_size(STACK_SIZE);
+            env.set_limits(limits);
+            if let Some(observer) = observer { env.set_observer(observer) }
+            let result = prog.interpret(&mut env);
+            env.finish();
+            result
+        })?;
+    match interpreter.join() {
+        Ok(Ok(())) => {},
+        Ok(Err(err)) => {
+            std::io::stdout().flush()?;
+            err.print();
+            std::process::exit(1)
         },
-        Err(err) => { err.print() }
+        Err(_) => std::process::exit(1)
+    }
+
+    Ok(())
+}
+
+fn transpile_file(path: String, target: &str, from_json: bool, syllabus: Option<syllabus::Syllabus>) -> Result<()> {
+    let target = match transpile::Target::from_name(target) {
+        Some(target) => target,
+        None => { eprintln!("can't transpile to {:?}, expected python, java or vb", target); std::process::exit(1) }
+    };
+    // records and files aren't parsed yet, so they're reported before the parser fails on them
+    if !from_json {
+        if let Ok(tokens) = Scanner::new(std::fs::read_to_string(&path)?).scan_tokens() {
+            if let Some(tkn) = transpile::unsupported(&tokens) {
+                eprintln!("transpile error at line {}: {} can't be transpiled, records and file handling aren't supported yet", tkn.line, tkn.lexeme);
+                std::process::exit(1)
+            }
+        }
+    }
+    if let Some(prog) = load(&path, from_json, syllabus)? {
+        print!("{}", transpile::transpile(&prog, target));
+    }
+    Ok(())
+}
+
+// draws the program's flowcharts, or with `structure` its structure chart
+fn flowchart_file(path: String, format: &str, structure: bool, from_json: bool, syllabus: Option<syllabus::Syllabus>) -> Result<()> {
+    let format = match flowchart::Format::from_name(format) {
+        Some(format) => format,
+        None => { eprintln!("can't draw flowcharts as {:?}, expected dot or mermaid", format); std::process::exit(1) }
+    };
+    if let Some(prog) = load(&path, from_json, syllabus)? {
+        if structure { print!("{}", flowchart::structure_chart(&prog, format)) }
+        else { print!("{}", flowchart::flowchart(&prog, format)) }
+    }
+    Ok(())
+}
+
+// prints warnings about likely mistakes in the program, exiting with an error if there are any
+fn lint_file(path: String, from_json: bool, syllabus: Option<syllabus::Syllabus>) -> Result<()> {
+    let Some(prog) = load(&path, from_json, syllabus)? else { std::process::exit(1) };
+    let warnings = lint::lint(&prog);
+    for warning in &warnings { warning.print() }
+    if !warnings.is_empty() { std::process::exit(1) }
+    Ok(())
+}
+
+// prints the program in the standard layout, or with `write` saves it back to the file
+fn format_file(path: String, write: bool) -> Result<()> {
+    let contents = std::fs::read_to_string(&path)?;
+    let mut scanner = Scanner::new(contents).with_lowercase_keywords();
+    let tokens = match scanner.scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(err) => { err.print(); std::process::exit(1) }
+    };
+    let prog = match Parser::new(tokens.clone()).parse() {
+        Some(prog) => prog,
+        None => std::process::exit(1)
+    };
+    let formatted = formatter::format(&prog, &tokens, scanner.comments());
+    if write { std::fs::write(&path, formatted)? }
+    else { print!("{}", formatted) }
+    Ok(())
+}
+
+// prints the program's syntax tree as a diagram, with `sexp` as one S-expression, or with `to_json`
+// as JSON along with its tokens
+fn print_ast(path: String, sexp: bool, to_json: bool, from_json: bool, syllabus: Option<syllabus::Syllabus>) -> Result<()> {
+    use pprint::PPrint;
+    if to_json && !from_json {
+        if let Some((tokens, mut prog)) = parse(std::fs::read_to_string(&path)?, syllabus) {
+            if let Err(err) = resolver::resolve(&mut prog) { err.print(); return Ok(()) }
+            println!("{}", json::export(&tokens, &prog).pretty());
+        }
+    } else if let Some(prog) = load(&path, from_json, syllabus)? {
+        if to_json { println!("{}", json::export(&[], &prog).pretty()) }
+        else if sexp { println!("{}", prog.prettify()) }
+        else { print!("{}", prog.tree()) }
+    }
+    Ok(())
+}
+
+fn test_file(path: String, cases_path: String) -> Result<()> {
+    let contents = std::fs::read_to_string(cases_path)?;
+
+    match cases::parse_cases(&contents) {
+        Ok(cases) => if !cases::run_cases(&path, &cases) { std::process::exit(1) },
+        Err(err) => { err.print(); std::process::exit(1) }
     }
 
     Ok(())
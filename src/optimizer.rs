@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use super::expr::Expr;
+use super::interpreter::ops;
+use super::stmt::Stmt;
+use super::token::{Literal, TokenType};
+
+/// Simplifies a resolved program without changing what it does: works out operators on
+/// literals, replaces global constants with their values, drops IF branches that can never
+/// run and statements after a RETURN. Returns a description of each change, in program order.
+pub fn optimize(prog: &mut Stmt) -> Vec<String> {
+    let mut optimizer = Optimizer { consts: HashMap::new(), definitions: HashMap::new(), changes: Vec::new() };
+    count_definitions(prog, true, &mut optimizer.definitions);
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts.iter_mut() {
+            optimizer.stmt(stmt);
+            // a constant in the main program's own block is defined before anything after it can run
+            if let Stmt::Constant(name, Expr::Literal(val)) = stmt {
+                if optimizer.definitions.get(&name.lexeme) == Some(&1) {
+                    optimizer.consts.insert(name.lexeme.clone(), val.clone());
+                }
+            }
+        }
+        optimizer.block(stmts);
+    } else { optimizer.stmt(prog) }
+    optimizer.changes
+}
+
+// how many times each global variable is declared or given a value
+fn count_definitions(stmt: &Stmt, top_level: bool, counts: &mut HashMap<String, usize>) {
+    let mut count = |name: &str| *counts.entry(name.to_string()).or_insert(0) += 1;
+    match stmt {
+        Stmt::Declare(name, _) | Stmt::Constant(name, _) if top_level => count(&name.lexeme),
+        Stmt::Assign(target, _) | Stmt::Input(target) => match target {
+            Expr::IdentExpr(name, None) | Expr::ArrIdx(name, _, _, None) => count(&name.lexeme),
+            _ => {}
+        },
+        Stmt::Block(stmts) => for stmt in stmts { count_definitions(stmt, top_level, counts) },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { count_definitions(stmt, top_level, counts) }
+            if let Some(stmt) = otherwise { count_definitions(stmt, top_level, counts) }
+        },
+        Stmt::Procedure(_, _, block) | Stmt::Function(_, _, _, block) | Stmt::ForTo(_, _, _, _, block)
+            | Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => count_definitions(block, false, counts),
+        Stmt::IfThen(_, _, then_block, else_block) => {
+            count_definitions(then_block, false, counts);
+            if let Some(block) = else_block { count_definitions(block, false, counts) }
+        },
+        _ => {}
+    }
+}
+
+struct Optimizer {
+    consts: HashMap<String, Literal>, // global constants that can be replaced with their value
+    definitions: HashMap<String, usize>,
+    changes: Vec<String>
+}
+
+impl Optimizer {
+    fn block(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Block(inner) if inner.is_empty()));
+        if let Some(idx) = stmts.iter().position(|stmt| matches!(stmt, Stmt::Ret(_, _))) {
+            let unreachable = stmts.len() - idx - 1;
+            if unreachable > 0 {
+                let line = if let Stmt::Ret(keyword, _) = &stmts[idx] { keyword.line } else { unreachable!() };
+                self.changes.push(format!("line {}: removed {} statement{} after RETURN that can never run",
+                    line, unreachable, if unreachable == 1 { "" } else { "s" }));
+                stmts.truncate(idx + 1);
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => {
+                for stmt in stmts.iter_mut() { self.stmt(stmt) }
+                self.block(stmts);
+            },
+            Stmt::ExprStmt(expr) | Stmt::Ret(_, expr) | Stmt::Declare(_, expr) | Stmt::Constant(_, expr) => self.expr(expr),
+            Stmt::Assign(target, val) => {
+                self.expr(val);
+                if let Expr::ArrIdx(_, idx1, _, _) = target { self.expr(idx1) }
+            },
+            Stmt::Input(_) => {},
+            Stmt::ProcCall(_, args) => self.args(args),
+            Stmt::Output(_, exprs) => for expr in exprs { self.expr(expr) },
+            Stmt::Procedure(_, params, block) => {
+                for param in params { self.expr(&mut param.1) }
+                self.stmt(block);
+            },
+            Stmt::Function(_, params, ret_type, block) => {
+                for param in params { self.expr(&mut param.1) }
+                self.expr(ret_type);
+                self.stmt(block);
+            },
+            Stmt::ForTo(_, start, end, step, block) => {
+                self.expr(start);
+                self.expr(end);
+                if let Some(step) = step { self.expr(step) }
+                self.stmt(block);
+            },
+            Stmt::IfThen(keyword, cond, then_block, else_block) => {
+                self.expr(cond);
+                self.stmt(then_block);
+                if let Some(block) = else_block { self.stmt(block) }
+                // the branch that's left keeps its own scope, so variables still resolve the same way
+                match (&*cond, else_block.take()) {
+                    (Expr::Literal(Literal::TRUE), Some(_)) =>
+                        self.changes.push(format!("line {}: IF condition is always TRUE, removed the ELSE branch", keyword.line)),
+                    (Expr::Literal(Literal::FALSE), Some(block)) => {
+                        self.changes.push(format!("line {}: IF condition is always FALSE, removed the THEN branch", keyword.line));
+                        *cond = Expr::Literal(Literal::TRUE);
+                        *then_block = block;
+                    },
+                    (Expr::Literal(Literal::FALSE), None) => {
+                        self.changes.push(format!("line {}: IF condition is always FALSE, removed the IF statement", keyword.line));
+                        *stmt = Stmt::Block(Vec::new());
+                    },
+                    (_, block) => *else_block = block
+                }
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                self.expr(val);
+                for (vals, stmt) in cases {
+                    for (from, to) in vals {
+                        self.expr(from);
+                        if let Some(to) = to { self.expr(to) }
+                    }
+                    self.stmt(stmt);
+                }
+                if let Some(stmt) = otherwise { self.stmt(stmt) }
+            },
+            Stmt::Repeat(_, cond, block) => {
+                self.stmt(block);
+                self.expr(cond);
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                self.expr(cond);
+                self.stmt(block);
+            },
+        }
+    }
+
+    // a variable passed straight to a procedure or function is left alone, as it may be a BYREF argument
+    fn args(&mut self, args: &mut [Expr]) {
+        for arg in args {
+            if !matches!(arg, Expr::IdentExpr(_, _)) { self.expr(arg) }
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        let folded = match expr {
+            Expr::Unary(op, right) => {
+                self.expr(right);
+                match &**right {
+                    Expr::Literal(val) if can_fold_unary(&op.ttype, val) => match ops::unary(&op.ttype, val.clone()) {
+                        Ok(res) => {
+                            let space = if op.ttype == TokenType::NOT { " " } else { "" };
+                            self.changes.push(format!("line {}: folded {}{}{} to {}", op.line, op.lexeme, space, val.to_string(), res.to_string()));
+                            Some(res)
+                        },
+                        Err(_) => None // left for the program to report when it gets there
+                    },
+                    _ => None
+                }
+            },
+            Expr::Binary(left, op, right) => {
+                self.expr(left);
+                self.expr(right);
+                match (&**left, &**right) {
+                    (Expr::Literal(l), Expr::Literal(r)) if can_fold_binary(&op.ttype, l, r) => match ops::binary(&op.ttype, l.clone(), r.clone()) {
+                        Ok(res) => {
+                            self.changes.push(format!("line {}: folded {} {} {} to {}", op.line, l.to_string(), op.lexeme, r.to_string(), res.to_string()));
+                            Some(res)
+                        },
+                        Err(_) => None
+                    },
+                    _ => None
+                }
+            },
+            Expr::Grouping(inner) => {
+                self.expr(inner);
+                match &**inner {
+                    Expr::Literal(val) => Some(val.clone()),
+                    _ => None
+                }
+            },
+            Expr::IdentExpr(name, None) => match self.consts.get(&name.lexeme) {
+                Some(val) => {
+                    self.changes.push(format!("line {}: replaced constant {} with its value {}", name.line, name.lexeme, val.to_string()));
+                    Some(val.clone())
+                },
+                None => None
+            },
+            Expr::IdentExpr(_, _) | Expr::Literal(_) => None,
+            Expr::FnCall(_, args) => {
+                self.args(args);
+                None
+            },
+            Expr::ArrIdx(_, idx1, _, _) => {
+                self.expr(idx1);
+                None
+            },
+            Expr::ArrType((start1, end1), idx2, dtype) => {
+                self.expr(start1);
+                self.expr(end1);
+                if let Some((start2, end2)) = idx2 {
+                    self.expr(start2);
+                    self.expr(end2);
+                }
+                self.expr(dtype);
+                None
+            },
+        };
+        if let Some(val) = folded { *expr = Expr::Literal(val) }
+    }
+}
+
+// operators that would panic rather than give an error (ones that aren't supported yet, or
+// integer overflow) are left for when the program runs, so it fails at the same point
+fn can_fold_unary(op: &TokenType, right: &Literal) -> bool {
+    match (op, right) {
+        (TokenType::Minus, Literal::Int(n)) => n.checked_neg().is_some(),
+        _ => matches!(op, TokenType::Minus | TokenType::NOT)
+    }
+}
+fn can_fold_binary(op: &TokenType, left: &Literal, right: &Literal) -> bool {
+    match (op, left, right) {
+        (TokenType::Plus, Literal::Int(l), Literal::Int(r)) => l.checked_add(*r).is_some(),
+        (TokenType::Minus, Literal::Int(l), Literal::Int(r)) => l.checked_sub(*r).is_some(),
+        (TokenType::Star, Literal::Int(l), Literal::Int(r)) => l.checked_mul(*r).is_some(),
+        _ => matches!(op, TokenType::Equal | TokenType::NotEqual | TokenType::Star | TokenType::Slash | TokenType::Plus
+            | TokenType::Minus | TokenType::Less | TokenType::Greater | TokenType::LessEqual | TokenType::GreaterEqual
+            | TokenType::AND | TokenType::OR)
+    }
+}
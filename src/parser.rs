@@ -1,6 +1,6 @@
 use super::token::{Token, TokenType};
 use super::expr::Expr;
-use super::stmt::Stmt;
+use super::stmt::{CaseClause, Stmt};
 
 pub struct Parser {
     tokens: Vec<Token>,
@@ -16,7 +16,7 @@ impl Parser {
     }
 
     pub fn parse(&mut self) -> Option<Stmt> {
-        match self.program() {
+        match self.try_parse() {
             Ok(prog) => Some(prog),
             Err(err) => { match err.token.ttype {
                 TokenType::End => println!("parse error at end: {}", err.msg),
@@ -25,11 +25,13 @@ impl Parser {
         }
     }
 
+    /// Parses the program, returning the first error instead of printing it
+    pub fn try_parse(&mut self) -> Result<Stmt, ParseError> {
+        self.program()
+    }
+
     fn is_at_end(&self) -> bool {
-        match self.peak().ttype {
-            TokenType::End => true,
-            _ => false
-        }
+        matches!(self.peak().ttype, TokenType::End)
     }
     fn peak(&self) -> Token {
         self.tokens[self.current].clone()
@@ -130,18 +132,18 @@ impl Parser {
         Ok(Stmt::Input(self.expr()?))
     }
     fn output(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
+        let keyword = self.advance();
         let mut exprs = Vec::new();
         exprs.push(self.expr()?);
         while self.peak().ttype == TokenType::Comma {
             self.advance();
             exprs.push(self.expr()?);
         }
-        Ok(Stmt::Output(exprs))
+        Ok(Stmt::Output(keyword, exprs))
     }
     fn ret(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
-        Ok(Stmt::Ret(self.expr()?))
+        let keyword = self.advance();
+        Ok(Stmt::Ret(keyword, self.expr()?))
     }
     fn procedure(&mut self) -> Result<Stmt, ParseError> {
         self.advance();
@@ -167,6 +169,7 @@ impl Parser {
                 args.push((name, dtype, byref));
     
                 if self.peak().ttype != TokenType::Comma { break }
+                self.advance();
             }
             if self.peak().ttype == TokenType::RightParen { self.advance(); }
             else { return Err(ParseError::new(self.peak(), "Expected ')' token".into())) }
@@ -202,6 +205,7 @@ impl Parser {
                 args.push((name, dtype, byref));
     
                 if self.peak().ttype != TokenType::Comma { break }
+                self.advance();
             }
             if self.peak().ttype == TokenType::RightParen { self.advance(); }
             else { return Err(ParseError::new(self.peak(), "Expected ')' token".into())) }
@@ -241,7 +245,7 @@ impl Parser {
         Ok(Stmt::ForTo(name, val1, val2, step, Box::new(block)))
     }
     fn ifthen(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
+        let keyword = self.advance();
         let condition = self.expr()?;
         if self.peak().ttype == TokenType::NL { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "Expected newline".into())) }
@@ -254,53 +258,86 @@ impl Parser {
             if self.peak().ttype == TokenType::NL { self.advance(); }
             else { return Err(ParseError::new(self.peak(), "Expected newline".into())) }
             let else_block = self.block(vec![TokenType::ENDIF])?;
-            Ok(Stmt::IfThen(condition, Box::new(then_block), Some(Box::new(else_block))))
+            Ok(Stmt::IfThen(keyword, condition, Box::new(then_block), Some(Box::new(else_block))))
         }
-        else { Ok(Stmt::IfThen(condition, Box::new(then_block), None)) }
+        else { Ok(Stmt::IfThen(keyword, condition, Box::new(then_block), None)) }
     }
     fn case(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
+        let keyword = self.advance();
         if self.peak().ttype == TokenType::OF { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "'OF' required after 'CASE'".into())) }
         let val = self.expr()?;
         if self.peak().ttype == TokenType::NL { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "Expected newline".into())) }
-        let mut cases: Vec<(Expr, Stmt)> = Vec::new();
+        let mut cases: Vec<CaseClause> = Vec::new();
         while self.peak().ttype != TokenType::ENDCASE {
             if let TokenType::OTHERWISE = self.peak().ttype {
                 self.advance();
                 if self.peak().ttype == TokenType::Colon { self.advance(); }
                 else { return Err(ParseError::new(self.peak(), "Expected ':' token".into())) }
-                let ret = Ok(Stmt::Case(val, cases, Some(Box::new(self.statement()?))));
+                let ret = Ok(Stmt::Case(keyword, val, cases, Some(Box::new(self.case_body()?))));
                 if self.peak().ttype == TokenType::ENDCASE { self.advance(); return ret }
-                else { return Err(ParseError::new(self.peak(), "Expected 'ENDWHILE' token".into())) }
+                else { return Err(ParseError::new(self.peak(), "Expected 'ENDCASE' token".into())) }
+            }
+            // a comma separated list of values and ranges
+            let mut vals = Vec::new();
+            loop {
+                let from = self.expr()?;
+                let to = if self.peak().ttype == TokenType::TO { self.advance(); Some(self.expr()?) } else { None };
+                vals.push((from, to));
+                if self.peak().ttype == TokenType::Comma { self.advance(); } else { break }
             }
-            let case = self.expr()?;
             if self.peak().ttype == TokenType::Colon { self.advance(); }
             else { return Err(ParseError::new(self.peak(), "Expected ':' token".into())) }
-            let stmt = self.statement()?;
-            cases.push((case, stmt));
+            let stmt = self.case_body()?;
+            cases.push((vals, stmt));
         }
         self.advance();
-        Ok(Stmt::Case(val, cases, None))
+        Ok(Stmt::Case(keyword, val, cases, None))
+    }
+    // the statements after a CASE clause's colon, which run on until the next clause, OTHERWISE or
+    // ENDCASE. A single statement is kept as it is rather than put in a block
+    fn case_body(&mut self) -> Result<Stmt, ParseError> {
+        if self.peak().ttype == TokenType::NL { self.advance(); }
+        let mut stmts = vec![self.statement()?];
+        while !matches!(self.peak().ttype, TokenType::ENDCASE | TokenType::OTHERWISE | TokenType::End) && !self.at_case_clause() {
+            stmts.push(self.statement()?)
+        }
+        if stmts.len() == 1 { Ok(stmts.remove(0)) } else { Ok(Stmt::Block(stmts)) }
+    }
+    // whether the line starting here is a new CASE clause, which has a colon outside any brackets
+    // that isn't part of a declaration
+    fn at_case_clause(&self) -> bool {
+        if matches!(self.peak().ttype, TokenType::DECLARE | TokenType::PROCEDURE | TokenType::FUNCTION) { return false }
+        let mut depth = 0;
+        for tkn in &self.tokens[self.current..] {
+            match tkn.ttype {
+                TokenType::LeftParen | TokenType::LeftBracket => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket => depth -= 1,
+                TokenType::Colon if depth == 0 => return true,
+                TokenType::NL | TokenType::End => return false,
+                _ => {}
+            }
+        }
+        false
     }
     fn repeat(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
+        let keyword = self.advance();
         if self.peak().ttype == TokenType::NL { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "Expected newline".into())) }
         let block = self.block(vec![TokenType::UNTIL])?;
         let condition = self.expr()?;
-        Ok(Stmt::Repeat(condition, Box::new(block)))
+        Ok(Stmt::Repeat(keyword, condition, Box::new(block)))
     }
     fn whiledo(&mut self) -> Result<Stmt, ParseError> {
-        self.advance();
+        let keyword = self.advance();
         let condition = self.expr()?;
         if self.peak().ttype == TokenType::DO { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "'DO' required after 'WHILE'".into())) }
         if self.peak().ttype == TokenType::NL { self.advance(); }
         else { return Err(ParseError::new(self.peak(), "Expected newline".into())) }
         let block = self.block(vec![TokenType::ENDWHILE])?;
-        Ok(Stmt::WhileDo(condition, Box::new(block)))
+        Ok(Stmt::WhileDo(keyword, condition, Box::new(block)))
     }
 
     pub fn expr(&mut self) -> Result<Expr, ParseError> {
@@ -308,71 +345,41 @@ impl Parser {
     }
     fn equality(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.logic()?;
-        let mut tkn = self.peak();
-        loop { match tkn.ttype {
-                TokenType::Equal | TokenType::NotEqual => {
-                    self.advance();
-                    expr = Expr::Binary(Box::new(expr), tkn.clone(), Box::new(self.logic()?));
-                },
-                _ => {break;}
-            }
-            tkn = self.peak();
+        while let TokenType::Equal | TokenType::NotEqual = self.peak().ttype {
+            let tkn = self.advance();
+            expr = Expr::Binary(Box::new(expr), tkn, Box::new(self.logic()?));
         }
         Ok(expr)
     }
     fn logic(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.comparison()?;
-        let mut tkn = self.peak();
-        loop { match tkn.ttype {
-                TokenType::AND | TokenType::OR => {
-                    self.advance();
-                    expr = Expr::Binary(Box::new(expr), tkn.clone(), Box::new(self.comparison()?));
-                },
-                _ => {break;}
-            }
-            tkn = self.peak();
+        while let TokenType::AND | TokenType::OR = self.peak().ttype {
+            let tkn = self.advance();
+            expr = Expr::Binary(Box::new(expr), tkn, Box::new(self.comparison()?));
         }
         Ok(expr)
     }
     fn comparison(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.term()?;
-        let mut tkn = self.peak();
-        loop { match tkn.ttype {
-                TokenType::Greater | TokenType::Less | TokenType::GreaterEqual | TokenType::LessEqual => {
-                    self.advance();
-                    expr = Expr::Binary(Box::new(expr), tkn.clone(), Box::new(self.term()?));
-                },
-                _ => {break;}
-            }
-            tkn = self.peak();
+        while let TokenType::Greater | TokenType::Less | TokenType::GreaterEqual | TokenType::LessEqual = self.peak().ttype {
+            let tkn = self.advance();
+            expr = Expr::Binary(Box::new(expr), tkn, Box::new(self.term()?));
         }
         Ok(expr)
     }
     fn term(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.factor()?;
-        let mut tkn = self.peak();
-        loop { match tkn.ttype {
-                TokenType::Plus | TokenType::Minus => {
-                    self.advance();
-                    expr = Expr::Binary(Box::new(expr), tkn.clone(), Box::new(self.factor()?));
-                },
-                _ => {break;}
-            }
-            tkn = self.peak();
+        while let TokenType::Plus | TokenType::Minus = self.peak().ttype {
+            let tkn = self.advance();
+            expr = Expr::Binary(Box::new(expr), tkn, Box::new(self.factor()?));
         }
         Ok(expr)
     }
     fn factor(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary()?;
-        let mut tkn = self.peak();
-        loop { match tkn.ttype {
-                TokenType::Slash | TokenType::Star | TokenType::MOD | TokenType::DIV => {
-                    self.advance();
-                    expr = Expr::Binary(Box::new(expr), tkn.clone(), Box::new(self.unary()?));
-                }
-                _ => {break;}
-            }
-            tkn = self.peak();
+        while let TokenType::Slash | TokenType::Star | TokenType::MOD | TokenType::DIV = self.peak().ttype {
+            let tkn = self.advance();
+            expr = Expr::Binary(Box::new(expr), tkn, Box::new(self.unary()?));
         }
         Ok(expr)
     }
@@ -433,10 +440,10 @@ impl Parser {
                             Err(ParseError::new(self.peak(), "unterminated array index".into()))
                         } else {
                             self.advance();
-                            Ok(Expr::ArrIdx(tkn, Box::new(expr1), expr2))
+                            Ok(Expr::ArrIdx(tkn, Box::new(expr1), expr2, None))
                         }
                     },
-                    _ => Ok(Expr::IdentExpr(tkn))
+                    _ => Ok(Expr::IdentExpr(tkn, None))
                 }
             },
             TokenType::ARRAY => {
@@ -483,12 +490,11 @@ impl Parser {
         }
     }
 
+    #[allow(dead_code)]
     fn dtype(&mut self) -> Result<Expr, ParseError> {
-        let dtype = match self.peak().ttype {
-            // TokenType::Identifier => Ok(Type::UDT), TODO
-            // TokenType::ARRAY => Ok(Type::Array(Type, size)),
-            _ => Err(ParseError::new(self.peak(), "Expected type".into()))
-        };
+        // TokenType::Identifier => Ok(Type::UDT), TODO
+        // TokenType::ARRAY => Ok(Type::Array(Type, size)),
+        let dtype = Err(ParseError::new(self.peak(), "Expected type".into()));
         self.advance();
         dtype
     }
@@ -1,7 +1,87 @@
 use super::expr::Expr;
+use super::stmt::Stmt;
+use super::token::{Literal, Token};
 
+/// Prints a syntax tree either as an S-expression on one line (`prettify`) or as a tree diagram
+/// with one node per line (`tree`).
 pub trait PPrint {
     fn prettify(&self) -> String;
+    fn node(&self) -> Node;
+
+    fn tree(&self) -> String {
+        let node = self.node();
+        let mut out = node.label.clone() + "\n";
+        node.children(&mut out, "");
+        out
+    }
+}
+
+pub struct Node {
+    label: String,
+    children: Vec<Node>
+}
+
+impl Node {
+    fn new(label: impl Into<String>, children: Vec<Node>) -> Self {
+        Node { label: label.into(), children }
+    }
+    fn leaf(label: impl Into<String>) -> Self {
+        Node::new(label, Vec::new())
+    }
+
+    fn children(&self, out: &mut String, indent: &str) {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i == self.children.len() - 1;
+            out.push_str(&format!("{}{}{}\n", indent, if last { "└── " } else { "├── " }, child.label));
+            child.children(out, &format!("{}{}", indent, if last { "    " } else { "│   " }));
+        }
+    }
+}
+
+// `( head parts... )`, leaving out any empty parts
+fn sexp(head: &str, parts: &[String]) -> String {
+    let mut out = format!("( {}", head);
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        out.push(' ');
+        out.push_str(part);
+    }
+    out + " )"
+}
+fn all<T: PPrint>(items: &[T]) -> Vec<String> {
+    items.iter().map(PPrint::prettify).collect()
+}
+fn nodes<T: PPrint>(items: &[T]) -> Vec<Node> {
+    items.iter().map(PPrint::node).collect()
+}
+
+// how far out a resolved variable lives
+fn scope(depth: &Option<usize>) -> String {
+    match depth {
+        None => "global".into(),
+        Some(0) => "local".into(),
+        Some(depth) => format!("{} out", depth)
+    }
+}
+
+fn params_sexp(params: &[(Token, Expr, bool)]) -> String {
+    let params: Vec<String> = params.iter().map(|(name, dtype, byref)| {
+        sexp(if *byref { "byref" } else { "byval" }, &[name.lexeme.clone(), dtype.prettify()])
+    }).collect();
+    sexp("params", &params)
+}
+fn params_node(params: &[(Token, Expr, bool)]) -> Node {
+    Node::new("Params", params.iter().map(|(name, dtype, byref)| {
+        Node::new(format!("{} {}", if *byref { "BYREF" } else { "BYVALUE" }, name.lexeme), vec![dtype.node()])
+    }).collect())
+}
+
+// a CASE clause's values, with a range as `( to from to )`
+fn case_values(vals: &[(Expr, Option<Expr>)]) -> String {
+    let vals: Vec<String> = vals.iter().map(|(from, to)| match to {
+        Some(to) => sexp("to", &[from.prettify(), to.prettify()]),
+        None => from.prettify()
+    }).collect();
+    vals.join(" ")
 }
 
 impl PPrint for Expr {
@@ -10,11 +90,111 @@ impl PPrint for Expr {
             Expr::Unary(operator, right) => format!("( {} {} )", operator.lexeme, (**right).prettify()),
             Expr::Binary(left, operator, right) => format!("( {} {} {} )", operator.lexeme, (**left).prettify(), (**right).prettify()),
             Expr::Grouping(inner) => format!("( {} )", (**inner).prettify()),
-            Expr::IdentExpr(name) => name.lexeme.clone(),
-            Expr::FnCall(_, _) => todo!(),
+            Expr::IdentExpr(name, _) => name.lexeme.clone(),
+            Expr::FnCall(name, args) => sexp(&name.lexeme, &all(args)),
+            Expr::Literal(Literal::Type(dtype)) => dtype.keyword().into(),
             Expr::Literal(value) => value.clone().to_string(),
-            Expr::ArrIdx(_, _, _) => todo!(),
-            Expr::ArrType(_, _, _) => todo!(),
+            Expr::ArrIdx(name, idx1, idx2, _) => {
+                let mut parts = vec![name.lexeme.clone(), idx1.prettify()];
+                if let Some(idx2) = idx2 { parts.push(idx2.prettify()) }
+                sexp("index", &parts)
+            },
+            Expr::ArrType((start1, end1), idx2, dtype) => {
+                let mut parts = vec![sexp(":", &[start1.prettify(), end1.prettify()])];
+                if let Some((start2, end2)) = idx2 { parts.push(sexp(":", &[start2.prettify(), end2.prettify()])) }
+                parts.push(dtype.prettify());
+                sexp("array", &parts)
+            },
+        }
+    }
+
+    fn node(&self) -> Node {
+        match self {
+            Expr::Unary(operator, right) => Node::new(format!("Unary {}", operator.lexeme), vec![right.node()]),
+            Expr::Binary(left, operator, right) => Node::new(format!("Binary {}", operator.lexeme), vec![left.node(), right.node()]),
+            Expr::Grouping(inner) => Node::new("Grouping", vec![inner.node()]),
+            Expr::IdentExpr(name, depth) => Node::leaf(format!("Ident {} ({})", name.lexeme, scope(depth))),
+            Expr::FnCall(name, args) => Node::new(format!("FnCall {}", name.lexeme), nodes(args)),
+            Expr::Literal(Literal::Type(dtype)) => Node::leaf(format!("Type {}", dtype.keyword())),
+            Expr::Literal(value) => Node::leaf(format!("Literal {}", value.to_string())),
+            Expr::ArrIdx(name, idx1, idx2, depth) => {
+                let mut children = vec![idx1.node()];
+                if let Some(idx2) = idx2 { children.push(idx2.node()) }
+                Node::new(format!("ArrIdx {} ({})", name.lexeme, scope(depth)), children)
+            },
+            Expr::ArrType((start1, end1), idx2, dtype) => {
+                let mut children = vec![Node::new("Bounds", vec![start1.node(), end1.node()])];
+                if let Some((start2, end2)) = idx2 { children.push(Node::new("Bounds", vec![start2.node(), end2.node()])) }
+                children.push(Node::new("Of", vec![dtype.node()]));
+                Node::new("ArrType", children)
+            },
         }
     }
-}
\ No newline at end of file
+}
+
+impl PPrint for Stmt {
+    fn prettify(&self) -> String {
+        match self {
+            Stmt::Block(stmts) => sexp("block", &all(stmts)),
+            Stmt::ExprStmt(expr) => expr.prettify(),
+            Stmt::Declare(name, dtype) => sexp("declare", &[name.lexeme.clone(), dtype.prettify()]),
+            Stmt::Constant(name, val) => sexp("constant", &[name.lexeme.clone(), val.prettify()]),
+            Stmt::Assign(target, val) => sexp("<-", &[target.prettify(), val.prettify()]),
+            Stmt::ProcCall(name, args) => sexp("call", &[vec![name.lexeme.clone()], all(args)].concat()),
+            Stmt::Input(target) => sexp("input", &[target.prettify()]),
+            Stmt::Output(_, exprs) => sexp("output", &all(exprs)),
+            Stmt::Ret(_, val) => sexp("return", &[val.prettify()]),
+            Stmt::Procedure(name, params, block) => sexp("procedure", &[name.lexeme.clone(), params_sexp(params), block.prettify()]),
+            Stmt::Function(name, params, ret_type, block) => sexp("function", &[name.lexeme.clone(), params_sexp(params),
+                sexp("returns", &[ret_type.prettify()]), block.prettify()]),
+            Stmt::ForTo(counter, start, end, step, block) => sexp("for", &[counter.lexeme.clone(), start.prettify(), end.prettify(),
+                step.as_ref().map(|step| sexp("step", &[step.prettify()])).unwrap_or_default(), block.prettify()]),
+            Stmt::IfThen(_, cond, then_block, else_block) => sexp("if", &[cond.prettify(), then_block.prettify(),
+                else_block.as_ref().map(|block| block.prettify()).unwrap_or_default()]),
+            Stmt::Case(_, val, cases, otherwise) => {
+                let mut parts = vec![val.prettify()];
+                for (vals, stmt) in cases { parts.push(format!("( {} {} )", case_values(vals), stmt.prettify())) }
+                if let Some(stmt) = otherwise { parts.push(sexp("otherwise", &[stmt.prettify()])) }
+                sexp("case", &parts)
+            },
+            Stmt::Repeat(_, cond, block) => sexp("repeat", &[block.prettify(), sexp("until", &[cond.prettify()])]),
+            Stmt::WhileDo(_, cond, block) => sexp("while", &[cond.prettify(), block.prettify()]),
+        }
+    }
+
+    fn node(&self) -> Node {
+        match self {
+            Stmt::Block(stmts) => Node::new("Block", nodes(stmts)),
+            Stmt::ExprStmt(expr) => Node::new("ExprStmt", vec![expr.node()]),
+            Stmt::Declare(name, dtype) => Node::new(format!("Declare {}", name.lexeme), vec![dtype.node()]),
+            Stmt::Constant(name, val) => Node::new(format!("Constant {}", name.lexeme), vec![val.node()]),
+            Stmt::Assign(target, val) => Node::new("Assign", vec![target.node(), val.node()]),
+            Stmt::ProcCall(name, args) => Node::new(format!("Call {}", name.lexeme), nodes(args)),
+            Stmt::Input(target) => Node::new("Input", vec![target.node()]),
+            Stmt::Output(_, exprs) => Node::new("Output", nodes(exprs)),
+            Stmt::Ret(_, val) => Node::new("Return", vec![val.node()]),
+            Stmt::Procedure(name, params, block) => Node::new(format!("Procedure {}", name.lexeme), vec![params_node(params), block.node()]),
+            Stmt::Function(name, params, ret_type, block) => Node::new(format!("Function {}", name.lexeme),
+                vec![params_node(params), Node::new("Returns", vec![ret_type.node()]), block.node()]),
+            Stmt::ForTo(counter, start, end, step, block) => {
+                let mut children = vec![Node::new("From", vec![start.node()]), Node::new("To", vec![end.node()])];
+                if let Some(step) = step { children.push(Node::new("Step", vec![step.node()])) }
+                children.push(block.node());
+                Node::new(format!("For {}", counter.lexeme), children)
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let mut children = vec![cond.node(), Node::new("Then", vec![then_block.node()])];
+                if let Some(block) = else_block { children.push(Node::new("Else", vec![block.node()])) }
+                Node::new("If", children)
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                let mut children = vec![val.node()];
+                for (vals, stmt) in cases { children.push(Node::new(format!("Branch {}", case_values(vals)), vec![stmt.node()])) }
+                if let Some(stmt) = otherwise { children.push(Node::new("Otherwise", vec![stmt.node()])) }
+                Node::new("Case", children)
+            },
+            Stmt::Repeat(_, cond, block) => Node::new("Repeat", vec![block.node(), Node::new("Until", vec![cond.node()])]),
+            Stmt::WhileDo(_, cond, block) => Node::new("While", vec![cond.node(), block.node()]),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// counts how many times each line runs and how long each procedure and function takes, to show
+// where a program spends its time
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use super::env::{Environment, Observer};
+use super::interpreter::RuntimeError;
+use super::lines::{expr_line, stmt_line, stmt_lines};
+use super::parser::Parser;
+use super::scanner::Scanner;
+use super::stmt::Stmt;
+
+#[derive(Debug, Default)]
+struct Calls {
+    count: u64,
+    time: Duration // from the start of each call to its end, with recursive calls only counted once
+}
+
+/// Prints the program annotated with how many times each line ran, then how many times each
+/// procedure and function was called and how long they took
+#[derive(Debug)]
+pub struct Profiler {
+    source: Vec<String>,
+    lines: BTreeSet<usize>, // the lines with statements, which are shown as run 0 times if they never were
+    hits: HashMap<usize, u64>,
+    calls: HashMap<String, Calls>,
+    running: Vec<(String, Instant)>,
+    steps: u64,
+    start: Option<Instant> // when the first statement ran
+}
+
+impl Profiler {
+    pub fn new(source: &str) -> Self {
+        let prog = Scanner::new(source.to_string()).scan_tokens().ok().and_then(|tokens| Parser::new(tokens).try_parse().ok());
+        Self {
+            source: source.lines().map(String::from).collect(),
+            lines: prog.as_ref().map(stmt_lines).unwrap_or_default(),
+            hits: HashMap::new(),
+            calls: HashMap::new(),
+            running: Vec::new(),
+            steps: 0,
+            start: None
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = String::from("   hits line\n");
+        let last = self.hits.keys().chain(&self.lines).copied().max().unwrap_or(0).max(self.source.len());
+        for num in 1..=last {
+            let hits = match self.hits.get(&num) {
+                Some(hits) => hits.to_string(),
+                None if self.lines.contains(&num) => "0".into(),
+                None => String::new()
+            };
+            let text = self.source.get(num - 1).map_or("", String::as_str);
+            out.push_str(format!("{:>7} {:>4} | {}", hits, num, text).trim_end());
+            out.push('\n');
+        }
+
+        out.push_str(&format!("\n{} statements ran in {}\n", self.steps, millis(self.start.map_or(Duration::ZERO, |start| start.elapsed()))));
+        if self.calls.is_empty() { return out }
+        let mut calls: Vec<(&String, &Calls)> = self.calls.iter().collect();
+        // the slowest first
+        calls.sort_by(|a, b| b.1.time.cmp(&a.1.time).then(a.0.cmp(b.0)));
+        let width = calls.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        out.push_str("\ncalls:\n");
+        for (name, call) in calls {
+            let count = format!("{} call{}", call.count, if call.count == 1 { "" } else { "s" });
+            out.push_str(&format!("    {:<3$}  {:>12}  {}\n", name, count, millis(call.time), width));
+        }
+        out
+    }
+}
+
+fn millis(time: Duration) -> String {
+    format!("{:.3} ms", time.as_secs_f64() * 1000.0)
+}
+
+impl Observer for Profiler {
+    fn before(&mut self, stmt: &Stmt, _env: &Environment) -> Result<(), RuntimeError> {
+        // defining a procedure or function doesn't count as running its header
+        if matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..)) { return Ok(()) }
+        self.steps += 1;
+        self.start.get_or_insert_with(Instant::now);
+        // a WHILE loop's line counts each time its condition is checked, the first included
+        if matches!(stmt, Stmt::WhileDo(..)) { return Ok(()) }
+        if let Some(line) = stmt_line(stmt) { *self.hits.entry(line).or_default() += 1 }
+        Ok(())
+    }
+
+    // a FOR loop's line also counts each time its counter is stepped, and a REPEAT loop's UNTIL
+    // line each time its condition is checked
+    fn looping(&mut self, stmt: &Stmt) {
+        let line = match stmt {
+            Stmt::Repeat(_, cond, _) => expr_line(cond),
+            stmt => stmt_line(stmt)
+        };
+        if let Some(line) = line { *self.hits.entry(line).or_default() += 1 }
+    }
+
+    fn entered(&mut self, env: &Environment) {
+        let Some((name, _)) = env.call_stack().into_iter().next() else { return };
+        self.calls.entry(name.clone()).or_default().count += 1;
+        self.running.push((name, Instant::now()));
+    }
+
+    fn leaving(&mut self, _env: &Environment) {
+        let Some((name, start)) = self.running.pop() else { return };
+        // a recursive call's time is already part of the outermost call's
+        if self.running.iter().any(|(running, _)| *running == name) { return }
+        if let Some(calls) = self.calls.get_mut(&name) { calls.time += start.elapsed() }
+    }
+
+    fn finished(&mut self, _env: &Environment) {
+        println!();
+        print!("{}", self.report());
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+use super::expr::Expr;
+use super::stmt::Stmt;
+use super::token::Token;
+
+/// Works out which scope each variable refers to before the program runs, following the
+/// CAIE rules: variables given a value in the main program are global and can be used in
+/// any procedure or function, anything else a procedure or function declares, assigns or
+/// takes as a parameter is local to it, and it can't see the variables of whoever called it.
+pub fn resolve(prog: &mut Stmt) -> Result<(), ResolveError> {
+    let mut resolver = Resolver { scopes: Vec::new(), globals: HashSet::new() };
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts.iter() {
+            if let Some(name) = declared_name(stmt) { resolver.globals.insert(name.to_string()); }
+            assigned_names(stmt, &mut resolver.globals);
+        }
+    }
+    resolver.stmt(prog)
+}
+
+// the variables given a value anywhere inside the main program's IF, CASE and loop blocks, which
+// are still global; a DECLARE inside one of those blocks only lasts until the block ends
+fn assigned_names(stmt: &Stmt, names: &mut HashSet<String>) {
+    match stmt {
+        Stmt::Block(stmts) => for stmt in stmts {
+            if let (Stmt::Assign(..) | Stmt::Input(_), Some(name)) = (stmt, declared_name(stmt)) {
+                names.insert(name.to_string());
+            }
+            assigned_names(stmt, names);
+        },
+        Stmt::ForTo(_, _, _, _, block) | Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => assigned_names(block, names),
+        Stmt::IfThen(_, _, then_block, else_block) => {
+            assigned_names(then_block, names);
+            if let Some(block) = else_block { assigned_names(block, names) }
+        },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { assigned_names(stmt, names) }
+            if let Some(stmt) = otherwise { assigned_names(stmt, names) }
+        },
+        _ => {}
+    }
+}
+
+// the variable a statement in a block declares or gives a value to, if any
+fn declared_name(stmt: &Stmt) -> Option<&str> {
+    match stmt {
+        Stmt::Declare(name, _) | Stmt::Constant(name, _) => Some(&name.lexeme),
+        Stmt::Assign(target, _) | Stmt::Input(target) => match target {
+            Expr::IdentExpr(name, _) | Expr::ArrIdx(name, _, _, _) => Some(&name.lexeme),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+struct Resolver {
+    scopes: Vec<HashSet<String>>, // local scopes, innermost last; empty in the main program's own scope
+    globals: HashSet<String>
+}
+
+impl Resolver {
+    fn in_scope(&mut self, names: Vec<String>, f: impl FnOnce(&mut Resolver) -> Result<(), ResolveError>) -> Result<(), ResolveError> {
+        self.scopes.push(names.into_iter().collect());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+    fn declare(&mut self, name: &str) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string()),
+            None => self.globals.insert(name.to_string())
+        };
+    }
+    // how many scopes out `name` is declared, or None if it isn't local
+    fn depth(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rev().position(|scope| scope.contains(name))
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) -> Result<(), ResolveError> {
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts {
+                self.stmt(stmt)?
+            },
+            Stmt::ExprStmt(expr) | Stmt::Ret(_, expr) => self.expr(expr)?,
+            Stmt::Declare(name, dtype) | Stmt::Constant(name, dtype) => {
+                self.expr(dtype)?;
+                self.declare(&name.lexeme);
+            },
+            Stmt::Assign(target, val) => {
+                self.expr(val)?;
+                self.target(target)?;
+            },
+            Stmt::Input(target) => self.target(target)?,
+            Stmt::ProcCall(_, args) | Stmt::Output(_, args) => for arg in args {
+                self.expr(arg)?
+            },
+            Stmt::Procedure(name, params, block) => self.module(name, params, None, block)?,
+            Stmt::Function(name, params, ret_type, block) => self.module(name, params, Some(ret_type), block)?,
+            Stmt::ForTo(counter, start, end, step, block) => {
+                self.expr(start)?;
+                self.expr(end)?;
+                if let Some(step) = step { self.expr(step)? }
+                self.in_scope(vec![counter.lexeme.clone()], |resolver| resolver.stmt(block))?;
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                self.expr(cond)?;
+                self.in_scope(Vec::new(), |resolver| resolver.stmt(then_block))?;
+                if let Some(block) = else_block {
+                    self.in_scope(Vec::new(), |resolver| resolver.stmt(block))?;
+                }
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                self.expr(val)?;
+                for (vals, stmt) in cases {
+                    for (from, to) in vals {
+                        self.expr(from)?;
+                        if let Some(to) = to { self.expr(to)? }
+                    }
+                    self.stmt(stmt)?;
+                }
+                if let Some(stmt) = otherwise { self.stmt(stmt)? }
+            },
+            Stmt::Repeat(_, cond, block) => self.in_scope(Vec::new(), |resolver| {
+                resolver.stmt(block)?;
+                resolver.expr(cond)
+            })?,
+            Stmt::WhileDo(_, cond, block) => self.in_scope(Vec::new(), |resolver| {
+                resolver.expr(cond)?;
+                resolver.stmt(block)
+            })?,
+        }
+        Ok(())
+    }
+
+    fn module(&mut self, name: &Token, params: &mut [(Token, Expr, bool)], ret_type: Option<&mut Expr>, block: &mut Stmt) -> Result<(), ResolveError> {
+        if !self.scopes.is_empty() {
+            return Err(ResolveError::new(format!("{} must be defined in the main program, not inside another block", name.lexeme), name.line))
+        }
+        for param in params.iter_mut() { self.expr(&mut param.1)? }
+        if let Some(ret_type) = ret_type { self.expr(ret_type)? }
+        self.in_scope(params.iter().map(|param| param.0.lexeme.clone()).collect(), |resolver| resolver.stmt(block))
+    }
+
+    // a variable being given a value is declared in the current scope if it doesn't exist yet
+    fn target(&mut self, target: &mut Expr) -> Result<(), ResolveError> {
+        let (name, depth) = match target {
+            Expr::IdentExpr(name, depth) => (name, depth),
+            Expr::ArrIdx(name, idx1, idx2, depth) => {
+                self.expr(idx1)?;
+                if let Some(idx2) = idx2 { self.expr(idx2)? }
+                (name, depth)
+            },
+            _ => return self.expr(target)
+        };
+        *depth = self.depth(&name.lexeme);
+        if depth.is_none() && !self.globals.contains(&name.lexeme) {
+            self.declare(&name.lexeme);
+            *depth = self.depth(&name.lexeme);
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &mut Expr) -> Result<(), ResolveError> {
+        match expr {
+            Expr::Unary(_, right) | Expr::Grouping(right) => self.expr(right)?,
+            Expr::Binary(left, _, right) => {
+                self.expr(left)?;
+                self.expr(right)?;
+            },
+            Expr::IdentExpr(name, depth) => *depth = self.depth(&name.lexeme),
+            Expr::FnCall(_, args) => for arg in args {
+                self.expr(arg)?
+            },
+            Expr::ArrIdx(name, idx1, idx2, depth) => {
+                self.expr(idx1)?;
+                if let Some(idx2) = idx2 { self.expr(idx2)? }
+                *depth = self.depth(&name.lexeme);
+            },
+            Expr::ArrType((start1, end1), idx2, dtype) => {
+                self.expr(start1)?;
+                self.expr(end1)?;
+                if let Some((start2, end2)) = idx2 {
+                    self.expr(start2)?;
+                    self.expr(end2)?;
+                }
+                self.expr(dtype)?;
+            },
+            Expr::Literal(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct ResolveError {
+    pub msg: String,
+    pub line: usize
+}
+
+impl ResolveError {
+    pub fn new(msg: String, line: usize) -> Self { Self { msg, line } }
+    pub fn print(&self) {
+        println!("scope error at line {}: {}", self.line, self.msg)
+    }
+}
@@ -9,7 +9,16 @@ pub struct Scanner {
     current: usize,
     line: usize,
 
-    keywords: HashMap<String, TokenType>
+    keywords: HashMap<String, TokenType>,
+    lowercase_keywords: bool,
+    comments: Vec<Comment>
+}
+
+/// A `//` comment, which the parser never sees but tools like the formatter keep
+#[derive(Clone, Debug)]
+pub struct Comment {
+    pub text: String, // including the slashes
+    pub line: usize
 }
 
 impl Scanner {
@@ -80,9 +89,22 @@ impl Scanner {
                 map.insert("OF".to_string(), TokenType::OF);
                 map.insert("TO".to_string(), TokenType::TO);
                 map
-            }
+            },
+            lowercase_keywords: false,
+            comments: Vec::new()
         }
     }
+    /// Also recognises keywords written in lowercase, for tools that tidy up source code
+    pub fn with_lowercase_keywords(mut self) -> Self {
+        self.lowercase_keywords = true;
+        self
+    }
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.keywords.keys().map(String::as_str)
+    }
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
 
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>, ScannerError> {
         let mut tokens: Vec<Token> = Vec::new();
@@ -100,12 +122,12 @@ impl Scanner {
         let t = match c {
             ' '|'\r'|'\t' => { self.start = self.current; self.scan_token()? }, // Ignore whitespace
             '/' => if self.peak() == '/' {
-                self.advance();
-                while self.advance() != '\n' && self.peak() != '\0' { }
+                self.comment(self.current - 1);
                 self.start = self.current;
-                self.scan_token()?
+                if self.is_at_end() { self.new_token(TokenType::NL) } else { self.scan_token()? }
             } else { self.new_token(TokenType::Slash) },
             '\n' => {
+                let line = self.line;
                 self.line += 1;
                 loop {
                     if self.peak() == '\n' {
@@ -113,9 +135,13 @@ impl Scanner {
                         self.line += 1;
                     } else if self.peak() == ' ' || self.peak() == '\r' || self.peak() == '\t' {
                         self.advance();
+                    } else if self.peak() == '/' && self.peak_next() == '/' {
+                        // a line with only a comment on it doesn't end a statement again
+                        self.comment(self.current);
                     } else { break }
                 }
-                self.new_token(TokenType::NL)
+                // the newline belongs to the line it ends
+                Token { line, ..self.new_token(TokenType::NL) }
             },
 
             '=' => self.new_token(TokenType::Equal),
@@ -130,6 +156,7 @@ impl Scanner {
             '*' => self.new_token(TokenType::Star),
             '+' => self.new_token(TokenType::Plus),
             '-' => self.new_token(TokenType::Minus),
+            '^' => self.new_token(TokenType::Caret),
             '<' => if self.peak() == '-' {
                 self.advance();
                 self.new_token(TokenType::Arrow)
@@ -157,7 +184,7 @@ impl Scanner {
                 self.new_token(TokenType::Literal(Literal::Char(val)))
             },
 
-            _ => if c.is_digit(10) {
+            _ => if c.is_ascii_digit() {
                 self.scan_digit()
             } else if c.is_alphabetic() {
                 self.scan_ident()
@@ -175,8 +202,9 @@ impl Scanner {
     }
     fn new_token(&self, ttype: TokenType) -> Token {
         let text = self.source.get(self.start..self.current).unwrap().to_string();
+        let line_start = self.source[..self.start].rfind('\n').map_or(0, |idx| idx + 1);
 
-        Token::new(ttype, text, self.line)
+        Token::new(ttype, text, self.line).at_col(self.start - line_start + 1)
     }
     fn advance(&mut self) -> char {
         self.current += 1;
@@ -192,14 +220,21 @@ impl Scanner {
         else { self.source.chars().nth(self.current + 1).unwrap() }
     }
 
+    // the comment starting at `start` runs to the end of the line, which is left for the newline token
+    fn comment(&mut self, start: usize) {
+        while self.peak() != '\n' && !self.is_at_end() { self.advance(); }
+        let text = self.source.get(start..self.current).unwrap().trim_end().to_string();
+        self.comments.push(Comment { text, line: self.line });
+    }
+
     fn scan_digit(&mut self) -> Token {
-        while self.peak().is_digit(10) {
+        while self.peak().is_ascii_digit() {
             self.advance();
         }
-        if self.peak() == '.' && self.peak_next().is_digit(10) {
+        if self.peak() == '.' && self.peak_next().is_ascii_digit() {
             self.advance();
             self.advance();
-            while self.peak().is_digit(10) {
+            while self.peak().is_ascii_digit() {
                 self.advance();
             }
             self.new_token(TokenType::Literal(Literal::Float(
@@ -216,15 +251,19 @@ impl Scanner {
             self.advance();
         }
         let text = self.source.get(self.start..self.current).unwrap().to_string();
-        if let Some(ttype) = self.keywords.get(&text) {
+        let keyword = match self.keywords.get(&text) {
+            None if self.lowercase_keywords && !text.chars().any(char::is_uppercase) => self.keywords.get(&text.to_uppercase()),
+            keyword => keyword
+        };
+        if let Some(ttype) = keyword {
             self.new_token(ttype.clone())
         } else { self.new_token(TokenType::Identifier) }
     }
 }
 
 pub struct ScannerError {
-    msg: String,
-    line: usize
+    pub msg: String,
+    pub line: usize
 }
 
 impl ScannerError {
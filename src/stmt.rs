@@ -2,7 +2,12 @@ use super::expr::Expr;
 use super::token::Token;
 use std::fmt::Debug;
 
+/// A CASE clause: the values it matches, each one value or a range like `'A' TO 'Z'` that includes
+/// both ends, and the statements it runs
+pub type CaseClause = (Vec<(Expr, Option<Expr>)>, Stmt);
+
 #[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Stmt {
     Block(Vec<Stmt>),
     ExprStmt(Expr),
@@ -11,13 +16,13 @@ pub enum Stmt {
     Assign(Expr, Expr),
     ProcCall(Token, Vec<Expr>),
     Input(Expr),
-    Output(Vec<Expr>),
-    Ret(Expr),
+    Output(Token, Vec<Expr>),
+    Ret(Token, Expr),
     Procedure(Token, Vec<(Token, Expr, bool)>, Box<Stmt>),
     Function(Token, Vec<(Token, Expr, bool)>, Expr, Box<Stmt>),
     ForTo(Token, Expr, Expr, Option<Expr>, Box<Stmt>),
-    IfThen(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    Case(Expr, Vec<(Expr, Stmt)>, Option<Box<Stmt>>),
-    Repeat(Expr, Box<Stmt>),
-    WhileDo(Expr, Box<Stmt>),
+    IfThen(Token, Expr, Box<Stmt>, Option<Box<Stmt>>),
+    Case(Token, Expr, Vec<CaseClause>, Option<Box<Stmt>>),
+    Repeat(Token, Expr, Box<Stmt>),
+    WhileDo(Token, Expr, Box<Stmt>),
 }
\ No newline at end of file
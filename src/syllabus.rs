@@ -0,0 +1,80 @@
+// the two Cambridge syllabuses use different subsets of pseudocode, so a program can be checked
+// against the one a student is sitting to keep them to what the exam allows
+use super::env::Type;
+use super::token::{Literal, Token, TokenType};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Syllabus {
+    Igcse, // IGCSE and O Level Computer Science, 0478 and 2210
+    ALevel // AS and A Level Computer Science, 9618
+}
+
+impl Syllabus {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "0478" | "2210" | "igcse" | "o-level" => Some(Syllabus::Igcse),
+            "9618" | "a-level" => Some(Syllabus::ALevel),
+            _ => None
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Syllabus::Igcse => "0478",
+            Syllabus::ALevel => "9618"
+        }
+    }
+}
+
+// words for the A Level's object-oriented programming, which an IGCSE program can't use even as names
+const CLASS_WORDS: &[&str] = &["CLASS", "ENDCLASS", "INHERITS", "NEW", "SUPER", "PUBLIC", "PRIVATE"];
+// library routines only in the A Level's insert
+const A_LEVEL_ROUTINES: &[&str] = &[
+    "LEFT", "RIGHT", "MID", "ASC", "CHR", "INT", "RAND", "DAY", "MONTH", "YEAR", "DAYINDEX", "SETDATE", "NOW", "EOF"
+];
+// library routines only in the IGCSE's guide, and what the A Level uses instead
+const IGCSE_ROUTINES: &[(&str, &str)] = &[("SUBSTRING", "MID"), ("ROUND", "INT"), ("RANDOM", "RAND")];
+
+#[derive(Debug)]
+pub struct SyllabusError {
+    pub msg: String,
+    pub line: usize
+}
+
+impl SyllabusError {
+    pub fn print(&self) {
+        println!("syllabus error at line {}: {}", self.line, self.msg)
+    }
+}
+
+/// Finds everything in a scanned program that isn't part of the syllabus
+pub fn check(tokens: &[Token], syllabus: Syllabus) -> Vec<SyllabusError> {
+    let mut errors = Vec::new();
+    for (i, tkn) in tokens.iter().enumerate() {
+        let called = tokens.get(i + 1).is_some_and(|next| next.ttype == TokenType::LeftParen);
+        let msg = match (&tkn.ttype, syllabus) {
+            // PRINT is accepted for OUTPUT, but neither syllabus has it
+            (TokenType::OUTPUT, _) if tkn.lexeme == "PRINT" => Some(format!("PRINT isn't in the {} syllabus, use OUTPUT", syllabus.code())),
+            (TokenType::BYREF | TokenType::BYVALUE, Syllabus::Igcse) =>
+                Some(format!("{} parameters are only in the 9618 syllabus, 0478 parameters are always passed by value", tkn.lexeme)),
+            (TokenType::TYPE | TokenType::ENDTYPE, Syllabus::Igcse) => Some("user-defined types are only in the 9618 syllabus".into()),
+            (TokenType::GETRECORD | TokenType::PUTRECORD | TokenType::SEEK | TokenType::Literal(Literal::RANDOM), Syllabus::Igcse) if !called =>
+                Some("random files are only in the 9618 syllabus".into()),
+            (TokenType::Literal(Literal::APPEND), Syllabus::Igcse) => Some("APPEND mode is only in the 9618 syllabus".into()),
+            (TokenType::Literal(Literal::Type(Type::Date)), Syllabus::Igcse) => Some("the DATE type is only in the 9618 syllabus".into()),
+            (TokenType::Caret, Syllabus::Igcse) => Some("pointers are only in the 9618 syllabus".into()),
+            (TokenType::Identifier, Syllabus::Igcse) if CLASS_WORDS.contains(&tkn.lexeme.as_str())
+                || called && A_LEVEL_ROUTINES.contains(&tkn.lexeme.as_str()) => Some(format!("{} is only in the 9618 syllabus", tkn.lexeme)),
+            (TokenType::Identifier | TokenType::Literal(Literal::RANDOM), Syllabus::ALevel) if called => IGCSE_ROUTINES.iter()
+                .find(|(name, _)| *name == tkn.lexeme)
+                .map(|(name, instead)| format!("{} is only in the 0478 syllabus, 9618 uses {}", name, instead)),
+            _ => None
+        };
+        // the same thing twice on a line, like two BYREF parameters, is only pointed out once
+        let Some(msg) = msg else { continue };
+        if !errors.iter().any(|err: &SyllabusError| err.line == tkn.line && err.msg == msg) {
+            errors.push(SyllabusError { msg, line: tkn.line })
+        }
+    }
+    errors
+}
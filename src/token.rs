@@ -5,7 +5,8 @@ use super::env::Type;
 pub struct Token {
     pub ttype: TokenType,
     pub lexeme: String,
-    line: usize
+    pub line: usize,
+    pub col: usize // where the token starts on its line, from 1, or 0 if it wasn't scanned
 }
 
 impl Token {
@@ -13,9 +14,13 @@ impl Token {
         Self {
             ttype,
             lexeme,
-            line
+            line,
+            col: 0
         }
     }
+    pub fn at_col(self, col: usize) -> Self {
+        Self { col, ..self }
+    }
 }
 
 impl Display for Token {
@@ -35,6 +40,7 @@ pub enum TokenType {
     Period,
     Star, Slash, Plus, Minus,
     Less, Greater,
+    Caret, // for pointers, which are scanned so a syllabus can point them out
 
     // Multi-char
     LessEqual, GreaterEqual, NotEqual,
@@ -42,6 +48,7 @@ pub enum TokenType {
 
     // Keywords
     //// Primitives
+    #[allow(dead_code)]
     DATE,
     ARRAY,
     //// Single-line statements
@@ -110,11 +117,11 @@ pub enum Literal {
     Float(f32),
     Char(char), //''
     String(String), //""
+    #[allow(dead_code)]
     Date(i8, i8, i16), // dd/mm/yyyy
     Array(Vec<Literal>),
 
-    Type(Type),
-    Ref(Box<Literal>, String)
+    Type(Type)
 }
 
 impl Literal {
@@ -132,7 +139,6 @@ impl Literal {
             Literal::String(val) => format!("\"{}\"", val),
             Literal::Date(d, m, y) => format!("{}/{}/{}", d, m, y),
             Literal::Type(inner) => format!("{:?}", inner),
-            Literal::Ref(val, _) => format!("&{}", val.to_string()),
             Literal::Array(vals) => {
                 let mut string = "[ ".to_string();
                 for val in vals {
@@ -0,0 +1,162 @@
+// trace tables, like the ones exam questions ask for: a column for each chosen variable and one
+// for OUTPUT, with a row for each time one of them changes
+use super::env::{Environment, Observer};
+use super::interpreter::RuntimeError;
+use super::stmt::Stmt;
+use super::token::Literal;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Markdown
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "text" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None
+        }
+    }
+}
+
+/// Watches the chosen variables as the program runs and prints the trace table once it stops
+#[derive(Debug)]
+pub struct Tracer {
+    columns: Vec<(String, Option<usize>)>, // each variable's name, and an array element's index
+    format: Format,
+    last: Vec<Option<String>>, // each variable's value in the last row it changed in
+    rows: Vec<(usize, String)>, // the column that changed and its new value, with OUTPUT last
+    line: String // output so far that hasn't ended with a newline
+}
+
+impl Tracer {
+    /// Takes the variables to trace as a comma separated list, where `Name[i]` traces one element
+    /// of an array
+    pub fn new(vars: &str, format: Format) -> Result<Self, String> {
+        let mut columns = Vec::new();
+        for var in vars.split(',').map(str::trim) {
+            let column = match var.split_once('[') {
+                Some((name, idx)) => match idx.strip_suffix(']').and_then(|idx| idx.trim().parse::<usize>().ok()) {
+                    Some(idx) if idx > 0 => (name.trim().to_string(), Some(idx)),
+                    _ => return Err(format!("{} isn't a variable or an array element like Name[1]", var))
+                },
+                None => (var.to_string(), None)
+            };
+            if column.0.is_empty() || !column.0.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("{:?} isn't a variable name", var))
+            }
+            columns.push(column);
+        }
+        Ok(Self { last: vec![None; columns.len()], columns, format, rows: Vec::new(), line: String::new() })
+    }
+
+    fn heading(&self, column: usize) -> String {
+        match self.columns.get(column) {
+            Some((name, Some(idx))) => format!("{}[{}]", name, idx),
+            Some((name, None)) => name.clone(),
+            None => "OUTPUT".into()
+        }
+    }
+
+    // adds a row for each traced variable whose value has changed
+    fn check(&mut self, env: &Environment) {
+        // the variable that can be seen from the statement running, so a procedure's local hides a global
+        let vars: Vec<(String, Option<Literal>)> = env.variables().into_iter().flat_map(|(_, vars)| vars).collect();
+        for (column, (name, idx)) in self.columns.iter().enumerate() {
+            let Some((_, Some(val))) = vars.iter().find(|(var, _)| var == name) else { continue };
+            let val = match (idx, val) {
+                (Some(idx), Literal::Array(vals)) => match vals.get(idx - 1) {
+                    Some(val) => val,
+                    None => continue
+                },
+                (Some(_), _) => continue,
+                (None, val) => val
+            };
+            let val = cell(val);
+            if self.last[column].as_ref() != Some(&val) {
+                self.rows.push((column, val.clone()));
+                self.last[column] = Some(val);
+            }
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let headings: Vec<String> = (0..=self.columns.len()).map(|column| self.heading(column)).collect();
+        let rows: Vec<Vec<&str>> = self.rows.iter().map(|(changed, val)| {
+            (0..headings.len()).map(|column| if column == *changed { val.as_str() } else { "" }).collect()
+        }).collect();
+        let mut out = String::new();
+        match self.format {
+            Format::Text => {
+                let widths: Vec<usize> = headings.iter().enumerate().map(|(column, heading)| {
+                    rows.iter().map(|row| row[column].chars().count()).chain([heading.chars().count()]).max().unwrap_or(0)
+                }).collect();
+                let line = |cells: Vec<&str>| {
+                    let cells: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<1$}", cell, width)).collect();
+                    format!("{}\n", cells.join(" | ").trim_end())
+                };
+                out.push_str(&line(headings.iter().map(String::as_str).collect()));
+                out.push_str(&format!("{}\n", widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("-+-")));
+                for row in rows { out.push_str(&line(row)) }
+            },
+            Format::Csv => {
+                let line = |cells: Vec<&str>| format!("{}\n", cells.iter().map(|cell| csv(cell)).collect::<Vec<_>>().join(","));
+                out.push_str(&line(headings.iter().map(String::as_str).collect()));
+                for row in rows { out.push_str(&line(row)) }
+            },
+            Format::Markdown => {
+                let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+                out.push_str(&line(headings.clone()));
+                out.push_str(&line(headings.iter().map(|_| "---".to_string()).collect()));
+                for row in rows { out.push_str(&line(row.iter().map(|cell| cell.replace('|', "\\|")).collect())) }
+            }
+        }
+        out
+    }
+}
+
+// values are shown the way OUTPUT would show them
+fn cell(val: &Literal) -> String {
+    match val {
+        Literal::String(val) => val.clone(),
+        Literal::Char(val) => val.to_string(),
+        val => val.to_string()
+    }
+}
+fn csv(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) { format!("\"{}\"", cell.replace('"', "\"\"")) }
+    else { cell.to_string() }
+}
+
+impl Observer for Tracer {
+    // a statement's changes are seen just before the next one runs
+    fn before(&mut self, _stmt: &Stmt, env: &Environment) -> Result<(), RuntimeError> {
+        self.check(env);
+        Ok(())
+    }
+
+    // and the last statement of a procedure or function's, while its variables can still be seen
+    fn leaving(&mut self, env: &Environment) {
+        self.check(env);
+    }
+
+    fn output(&mut self, text: &str) -> bool {
+        self.line.push_str(text);
+        while let Some((line, rest)) = self.line.split_once('\n') {
+            self.rows.push((self.columns.len(), line.to_string()));
+            self.line = rest.to_string();
+        }
+        false
+    }
+
+    fn finished(&mut self, env: &Environment) {
+        self.check(env);
+        if !self.line.is_empty() { self.rows.push((self.columns.len(), std::mem::take(&mut self.line))) }
+        println!();
+        print!("{}", self.render());
+    }
+}
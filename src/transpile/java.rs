@@ -0,0 +1,722 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::env::Type;
+use crate::expr::Expr;
+use crate::stmt::{CaseClause, Stmt};
+use crate::token::{Literal, Token, TokenType};
+use super::{AND, ATOM, COMPARISON, Exprs, NEGATION, OR, PRODUCT, SUM, Scopes, Signature, Writer,
+    calls_byref, case_condition, fresh, int_literal, names, paren, signatures, string_literal, type_expr};
+
+pub fn transpile(prog: &Stmt) -> String {
+    let mut java = Java {
+        out: Writer::new("    "),
+        sigs: signatures(prog),
+        scopes: Scopes::new(prog),
+        holders: HashSet::new(),
+        names: HashSet::new(),
+        hoisted: Vec::new(),
+        shown: BTreeSet::new(),
+        shows_reals: false,
+        func: None,
+        input: false
+    };
+    names(prog, &mut java.names);
+    let globals = java.scopes.globals(prog);
+    java.out.depth = 1;
+    if let Stmt::Block(stmts) = prog {
+        java.fields(&globals);
+        for stmt in stmts {
+            if matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..)) { java.stmt(stmt) }
+        }
+        java.out.line("public static void main(String[] args) {");
+        java.out.depth += 1;
+        for stmt in stmts {
+            if !matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..) | Stmt::Declare(..) | Stmt::Constant(..)) { java.stmt(stmt) }
+        }
+        java.out.depth -= 1;
+        java.out.line("}");
+        java.show_methods();
+    }
+
+    let mut out = Writer::new("    ");
+    out.line("// Transpiled from pseudocode by camps");
+    if java.input { out.line("import java.util.Scanner;") }
+    out.blank();
+    out.line("public class Program {");
+    if java.input {
+        out.depth += 1;
+        out.line("static Scanner input = new Scanner(System.in);");
+        out.blank();
+    }
+    out.lines.extend(java.out.lines);
+    out.depth = 0;
+    out.line("}");
+    out.finish()
+}
+
+// names that can't be used as variables, or that the generated code relies on
+const RESERVED: &[&str] = &[
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const", "continue",
+    "default", "do", "double", "else", "enum", "extends", "final", "finally", "float", "for", "goto", "if",
+    "implements", "import", "instanceof", "int", "interface", "long", "native", "new", "package", "private",
+    "protected", "public", "return", "short", "static", "strictfp", "super", "switch", "synchronized", "this",
+    "throw", "throws", "transient", "try", "void", "volatile", "while", "true", "false", "null", "var",
+    "input", "args", "i", "main", "show", "Program", "String", "System", "Math", "Integer", "Double", "Character", "Scanner"
+];
+
+struct Java {
+    out: Writer,
+    sigs: HashMap<String, Signature>,
+    scopes: Scopes,
+    holders: HashSet<String>, // BYREF parameters, passed as one-element arrays as Java can't change the caller's variables
+    names: HashSet<String>, // the program's own names and the ones the generated code has taken
+    hoisted: Vec<String>, // calls to functions with BYREF parameters, made on lines of their own before the statement using them
+    shown: BTreeSet<String>, // the types of the arrays that are output, which each need a method to show them
+    shows_reals: bool, // whether REALs are output, which are shown by a method of their own
+    func: Option<bool>, // whether the procedure or function being transpiled returns a value
+    input: bool
+}
+
+fn name(name: &str) -> String {
+    if RESERVED.contains(&name) { format!("{}_", name) } else { name.to_string() }
+}
+
+fn type_name(dtype: &Expr) -> String {
+    match dtype {
+        Expr::Literal(Literal::Type(dtype)) => match dtype {
+            Type::Int => "int",
+            Type::Float => "double",
+            Type::Bool => "boolean",
+            Type::Char => "char",
+            Type::String => "String",
+            Type::Date => "java.time.LocalDate",
+            Type::Array(..) | Type::Type => "Object"
+        }.into(),
+        Expr::ArrType(_, idx2, inner) => format!("{}[]{}", type_name(inner), if idx2.is_some() { "[]" } else { "" }),
+        _ => "Object".into()
+    }
+}
+fn default_value(dtype: &Expr) -> &'static str {
+    match dtype {
+        Expr::Literal(Literal::Type(Type::Int)) => "0",
+        Expr::Literal(Literal::Type(Type::Float)) => "0.0",
+        Expr::Literal(Literal::Type(Type::Bool)) => "false",
+        Expr::Literal(Literal::Type(Type::Char)) => "' '",
+        Expr::Literal(Literal::Type(Type::String)) => "\"\"",
+        _ => "null"
+    }
+}
+
+impl Java {
+    // the statements in the main program's block that declare a variable become fields, so
+    // procedures and functions can use them
+    fn fields(&mut self, globals: &[&Stmt]) {
+        for &stmt in globals {
+            let tkn = match stmt {
+                Stmt::Declare(tkn, _) | Stmt::Constant(tkn, _) | Stmt::Assign(Expr::IdentExpr(tkn, _), _)
+                    | Stmt::Input(Expr::IdentExpr(tkn, _)) => tkn,
+                _ => continue
+            };
+            let dtype = self.scopes.globals[&tkn.lexeme].clone();
+            let decl = format!("static {} {}", type_name(&dtype), name(&tkn.lexeme));
+            match stmt {
+                Stmt::Constant(_, val) => {
+                    let val = self.expr(val).0;
+                    self.out.line(format!("static final {} {} = {};", type_name(&dtype), name(&tkn.lexeme), val))
+                },
+                _ if matches!(dtype, Expr::ArrType(..)) => {
+                    let val = self.array_value(&dtype);
+                    self.out.line(format!("{} = {};", decl, val))
+                },
+                _ => self.out.line(format!("{};", decl))
+            }
+        }
+        self.out.blank();
+    }
+
+    fn array_value(&mut self, dtype: &Expr) -> String {
+        match dtype {
+            Expr::ArrType((start1, end1), idx2, inner) => {
+                let mut val = format!("new {}[{}]", type_name(inner), self.size(start1, end1));
+                if let Some((start2, end2)) = idx2 { val.push_str(&format!("[{}]", self.size(start2, end2))) }
+                val
+            },
+            _ => "null".into()
+        }
+    }
+    fn index(&mut self, arr: &str, idx1: &Expr, idx2: Option<&Expr>) -> String {
+        let one = Expr::Literal(Literal::Int(1));
+        let (lower1, lower2) = match self.scopes.get(arr) {
+            Some(Expr::ArrType((start1, _), idx2, _)) => ((**start1).clone(), idx2.as_ref().map_or(one.clone(), |idx2| (*idx2.0).clone())),
+            _ => (one.clone(), one)
+        };
+        let mut text = format!("{}[{}]", name(arr), self.zero_based(idx1, &lower1));
+        if let Some(idx2) = idx2 { text.push_str(&format!("[{}]", self.zero_based(idx2, &lower2))) }
+        text
+    }
+    fn target(&mut self, target: &Expr) -> String {
+        match target {
+            Expr::IdentExpr(tkn, _) if self.holders.contains(&tkn.lexeme) => format!("{}[0]", name(&tkn.lexeme)),
+            Expr::IdentExpr(tkn, _) => name(&tkn.lexeme),
+            Expr::ArrIdx(tkn, idx1, idx2, _) => self.index(&tkn.lexeme, idx1, idx2.as_deref()),
+            _ => self.expr(target).0
+        }
+    }
+
+    // writes a line, after the calls its expressions need made first
+    fn line(&mut self, text: impl AsRef<str>) {
+        for line in std::mem::take(&mut self.hoisted) { self.out.line(line) }
+        self.out.line(text)
+    }
+    fn body(&mut self, stmt: &Stmt) {
+        self.out.depth += 1;
+        self.scopes.push();
+        self.stmt(stmt);
+        self.scopes.pop();
+        self.out.depth -= 1;
+    }
+    // `while (true)` with a test that breaks out of it, for a loop condition that calls a function
+    // with BYREF parameters, which has to be called inside the loop on a line of its own
+    fn breaking_loop(&mut self, cond: &Expr, until: bool, block: &Stmt) {
+        self.line("while (true) {");
+        self.out.depth += 1;
+        self.scopes.push();
+        if until { self.stmt(block) }
+        let cond = if until { self.expr(cond).0 } else { format!("!{}", paren(self.expr(cond), NEGATION)) };
+        self.line(format!("if ({}) break;", cond));
+        if !until { self.stmt(block) }
+        self.scopes.pop();
+        self.out.depth -= 1;
+        self.line("}")
+    }
+
+    // a value to OUTPUT, written so it's shown the way the interpreter shows it
+    fn show(&mut self, expr: &Expr) -> (String, u8) {
+        if let Expr::IdentExpr(tkn, _) = expr {
+            if let Some(Expr::ArrType(_, idx2, inner)) = self.scopes.get(&tkn.lexeme) {
+                let inner = type_name(inner);
+                if inner == "double" { self.shows_reals = true }
+                if idx2.is_some() { self.shown.insert(format!("{}[]", inner)); }
+                self.shown.insert(inner);
+                return (format!("show({})", self.target(expr)), ATOM)
+            }
+        }
+        match expr {
+            Expr::Literal(Literal::TRUE) => ("\"TRUE\"".into(), ATOM),
+            Expr::Literal(Literal::FALSE) => ("\"FALSE\"".into(), ATOM),
+            _ if self.scopes.type_of(expr) == Some(Type::Bool) => (format!("{} ? \"TRUE\" : \"FALSE\"", paren(self.expr(expr), OR)), 0),
+            _ if self.scopes.type_of(expr) == Some(Type::Float) => {
+                self.shows_reals = true;
+                (format!("show({})", self.expr(expr).0), ATOM)
+            },
+            _ => self.expr(expr)
+        }
+    }
+    fn shown_as_string(&self, expr: &Expr) -> bool {
+        let array = matches!(expr, Expr::IdentExpr(tkn, _) if matches!(self.scopes.get(&tkn.lexeme), Some(Expr::ArrType(..))));
+        array || matches!(self.scopes.type_of(expr), Some(Type::String | Type::Bool | Type::Float))
+    }
+    // the methods that show each type of array that's output, like the interpreter does
+    fn show_methods(&mut self) {
+        for dtype in std::mem::take(&mut self.shown) {
+            self.out.blank();
+            match dtype.strip_suffix("[]") {
+                Some(row) => {
+                    self.out.line(format!("static String show({}[] rows) {{", dtype));
+                    self.out.depth += 1;
+                    self.out.line("String text = \"[ \";");
+                    self.out.line(format!("for ({} row : rows) text += show(row) + \" \";", row));
+                },
+                None => {
+                    self.out.line(format!("static String show({}[] values) {{", dtype));
+                    self.out.depth += 1;
+                    self.out.line("String text = \"[ \";");
+                    let value = match dtype.as_str() {
+                        "boolean" => "(value ? \"TRUE\" : \"FALSE\") + \" \"",
+                        "char" => "\"'\" + value + \"' \"",
+                        "String" => "\"\\\"\" + value + \"\\\" \"",
+                        "double" => "show(value) + \" \"",
+                        _ => "value + \" \""
+                    };
+                    self.out.line(format!("for ({} value : values) text += {};", dtype, value));
+                }
+            }
+            self.out.line("return text + \"]\";");
+            self.out.depth -= 1;
+            self.out.line("}");
+        }
+        if self.shows_reals {
+            self.out.blank();
+            self.out.line("static String show(double value) {");
+            self.out.line("    // a whole number is shown without a fraction, like the pseudocode interpreter does");
+            self.out.line("    return value == Math.rint(value) ? String.valueOf((long) value) : String.valueOf(value);");
+            self.out.line("}");
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts { self.stmt(stmt) },
+            Stmt::ExprStmt(expr) => {
+                let expr = self.expr(expr).0;
+                self.line(format!("{};", expr))
+            },
+            Stmt::Declare(tkn, dtype) => {
+                self.scopes.declare(&tkn.lexeme, dtype.clone());
+                let val = match dtype {
+                    Expr::ArrType(..) => self.array_value(dtype),
+                    _ => default_value(dtype).into()
+                };
+                self.line(format!("{} {} = {};", type_name(dtype), name(&tkn.lexeme), val))
+            },
+            Stmt::Constant(tkn, val) => {
+                let dtype = self.scopes.infer(val);
+                let val = self.expr(val).0;
+                self.line(format!("final {} {} = {};", type_name(&dtype), name(&tkn.lexeme), val));
+                self.scopes.declare(&tkn.lexeme, dtype);
+            },
+            Stmt::Assign(target, val) => {
+                let (target_text, val_text) = (self.target(target), self.expr(val).0);
+                match target {
+                    // the first value given to a variable declares it
+                    Expr::IdentExpr(tkn, _) if self.scopes.get(&tkn.lexeme).is_none() => {
+                        let dtype = self.scopes.infer(val);
+                        self.line(format!("{} {} = {};", type_name(&dtype), target_text, val_text));
+                        self.scopes.declare(&tkn.lexeme, dtype);
+                    },
+                    _ => self.line(format!("{} = {};", target_text, val_text))
+                }
+            },
+            Stmt::ProcCall(tkn, args) => self.proc_call(tkn, args),
+            Stmt::Input(target) => {
+                self.input = true;
+                let line = match self.scopes.type_of(target) {
+                    Some(Type::Int) => "Integer.parseInt(input.nextLine())",
+                    Some(Type::Float) => "Double.parseDouble(input.nextLine())",
+                    Some(Type::Bool) => "input.nextLine().equals(\"TRUE\")",
+                    Some(Type::Char) => "input.nextLine().charAt(0)",
+                    _ => "input.nextLine()"
+                };
+                let target_text = self.target(target);
+                match target {
+                    Expr::IdentExpr(tkn, _) if self.scopes.get(&tkn.lexeme).is_none() => {
+                        self.line(format!("String {} = {};", target_text, line));
+                        self.scopes.declare(&tkn.lexeme, type_expr(Type::String));
+                    },
+                    _ => self.line(format!("{} = {};", target_text, line))
+                }
+            },
+            Stmt::Output(_, exprs) => {
+                // values are joined by adding them to a string, starting with an empty one if
+                // the first isn't shown as a string already
+                let mut parts = Vec::new();
+                if exprs.len() > 1 && !self.shown_as_string(&exprs[0]) { parts.push("\"\"".to_string()) }
+                for expr in exprs {
+                    // only the first value can be a sum, as anything added to a string is joined to it
+                    let min = match (exprs.len(), parts.is_empty()) {
+                        (1, _) => 0,
+                        (_, true) => SUM,
+                        _ => PRODUCT
+                    };
+                    parts.push(paren(self.show(expr), min));
+                }
+                self.line(format!("System.out.println({});", parts.join(" + ")))
+            },
+            Stmt::Ret(_, val) => match self.func {
+                Some(true) => {
+                    let val = self.expr(val).0;
+                    self.line(format!("return {};", val))
+                },
+                _ => self.line("return;")
+            },
+            Stmt::Procedure(tkn, params, block) => self.module(tkn, params, None, block),
+            Stmt::Function(tkn, params, ret_type, block) => self.module(tkn, params, Some(ret_type), block),
+            Stmt::ForTo(counter, start, end, step, block) => {
+                let counter_name = name(&counter.lexeme);
+                // a counter that's already a local variable is reused, as Java can't have two with the same name
+                let reuse = self.scopes.is_local(&counter.lexeme);
+                let dtype = self.scopes.infer(start);
+                let init = if reuse { format!("{} = {}", counter_name, self.expr(start).0) }
+                    else { format!("{} {} = {}", type_name(&dtype), counter_name, self.expr(start).0) };
+                let (cond, update) = match step.as_ref().map(|step| (int_literal(step), step)) {
+                    None | Some((Some(1), _)) => (format!("{} <= {}", counter_name, paren(self.expr(end), SUM)), format!("{}++", counter_name)),
+                    Some((Some(-1), _)) => (format!("{} >= {}", counter_name, paren(self.expr(end), SUM)), format!("{}--", counter_name)),
+                    Some((Some(n), _)) => (
+                        format!("{} {} {}", counter_name, if n > 0 { "<=" } else { ">=" }, paren(self.expr(end), SUM)),
+                        format!("{} {} {}", counter_name, if n > 0 { "+=" } else { "-=" }, n.abs())
+                    ),
+                    Some((None, step)) => {
+                        let (step, end) = (paren(self.expr(step), SUM), paren(self.expr(end), SUM));
+                        (format!("{} > 0 ? {} <= {} : {} >= {}", step, counter_name, end, counter_name, end), format!("{} += {}", counter_name, step))
+                    }
+                };
+                self.line(format!("for ({}; {}; {}) {{", init, cond, update));
+                self.scopes.push();
+                if !reuse { self.scopes.declare(&counter.lexeme, dtype) }
+                self.body(block);
+                self.scopes.pop();
+                self.line("}")
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let cond = self.expr(cond).0;
+                self.line(format!("if ({}) {{", cond));
+                self.body(then_block);
+                let mut else_block = else_block.as_deref();
+                while let Some(block) = else_block {
+                    // ELSE followed by just another IF is written as else if, unless its condition calls
+                    // a function that has to be called before the IF
+                    match block {
+                        Stmt::Block(stmts) if matches!(stmts.as_slice(), [Stmt::IfThen(_, cond, ..)] if !calls_byref(&self.sigs, cond)) => {
+                            if let Stmt::IfThen(_, cond, then_block, next) = &stmts[0] {
+                                let cond = self.expr(cond).0;
+                                self.line(format!("}} else if ({}) {{", cond));
+                                self.body(then_block);
+                                else_block = next.as_deref();
+                            }
+                        },
+                        _ => {
+                            self.line("} else {");
+                            self.body(block);
+                            else_block = None;
+                        }
+                    }
+                }
+                self.line("}")
+            },
+            Stmt::Case(_, val, cases, otherwise) => self.case(val, cases, otherwise.as_deref()),
+            Stmt::Repeat(_, cond, block) if calls_byref(&self.sigs, cond) => self.breaking_loop(cond, true, block),
+            Stmt::Repeat(_, cond, block) => {
+                // the condition is outside the loop's block, so variables first given a value in it are declared before it
+                let mut firsts = Vec::new();
+                let level = self.scopes.locals.len();
+                self.scopes.push();
+                self.scopes.first_values(block, level, &mut firsts);
+                self.scopes.pop();
+                for first in firsts {
+                    if let Stmt::Assign(Expr::IdentExpr(tkn, _), _) | Stmt::Input(Expr::IdentExpr(tkn, _)) = first {
+                        let dtype = self.scopes.get(&tkn.lexeme).cloned().unwrap_or(type_expr(Type::Type));
+                        self.line(format!("{} {} = {};", type_name(&dtype), name(&tkn.lexeme), default_value(&dtype)))
+                    }
+                }
+                self.line("do {");
+                self.out.depth += 1;
+                self.scopes.push();
+                self.stmt(block);
+                let cond = match cond {
+                    Expr::Unary(op, right) if op.ttype == TokenType::NOT => self.expr(right).0,
+                    Expr::Literal(Literal::FALSE) => "true".into(),
+                    _ => format!("!{}", paren(self.expr(cond), ATOM))
+                };
+                self.scopes.pop();
+                self.out.depth -= 1;
+                self.line(format!("}} while ({});", cond))
+            },
+            Stmt::WhileDo(_, cond, block) if calls_byref(&self.sigs, cond) => self.breaking_loop(cond, false, block),
+            Stmt::WhileDo(_, cond, block) => {
+                let cond = match cond {
+                    // Java won't compile a loop it can tell never runs, unless the condition is an object
+                    Expr::Literal(Literal::FALSE) => "Boolean.FALSE".into(),
+                    _ => self.expr(cond).0
+                };
+                self.line(format!("while ({}) {{", cond));
+                self.body(block);
+                self.line("}")
+            },
+        }
+    }
+
+    // the arguments of a call, with each BYREF one put in an array the procedure or function can
+    // change, which is copied back afterwards. The arrays need names of their own if they stay in
+    // scope after the call, as Java doesn't let a variable hide another
+    fn holders<'a>(&mut self, callee: &str, args: &'a [Expr], unique: bool) -> (Vec<String>, Vec<(String, Expr, &'a Expr)>) {
+        let params: Vec<_> = match self.sigs.get(callee) {
+            Some(sig) => sig.params.iter().map(|param| (param.name.clone(), param.byref && !param.array, param.dtype.clone())).collect(),
+            None => Vec::new()
+        };
+        let mut holders = Vec::new();
+        let mut arg_texts = Vec::new();
+        for (idx, arg) in args.iter().enumerate() {
+            match params.get(idx) {
+                Some((param, true, dtype)) => match arg {
+                    Expr::IdentExpr(arg_tkn, _) if self.holders.contains(&arg_tkn.lexeme) => arg_texts.push(name(&arg_tkn.lexeme)),
+                    _ => {
+                        let holder = format!("ref{}", param);
+                        let holder = if unique || self.names.contains(&holder) { fresh(&holder, &mut self.names) } else { holder };
+                        holders.push((holder.clone(), dtype.clone(), arg));
+                        arg_texts.push(holder);
+                    }
+                },
+                _ => arg_texts.push(self.expr(arg).0)
+            }
+        }
+        (arg_texts, holders)
+    }
+    fn copy_back(&mut self, holders: &[(String, Expr, &Expr)]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (holder, _, arg) in holders {
+            if matches!(arg, Expr::IdentExpr(..) | Expr::ArrIdx(..)) {
+                let target = self.target(arg);
+                lines.push(format!("{} = {}[0];", target, holder))
+            }
+        }
+        lines
+    }
+
+    fn proc_call(&mut self, tkn: &Token, args: &[Expr]) {
+        let (arg_texts, holders) = self.holders(&tkn.lexeme, args, false);
+        let call = format!("{}({});", name(&tkn.lexeme), arg_texts.join(", "));
+        if holders.is_empty() { return self.line(call) }
+
+        self.line("{");
+        self.out.depth += 1;
+        for (holder, dtype, arg) in &holders {
+            let arg = self.expr(arg).0;
+            self.line(format!("{}[] {} = {{{}}};", type_name(dtype), holder, arg))
+        }
+        self.line(call);
+        for line in self.copy_back(&holders) { self.line(line) }
+        self.out.depth -= 1;
+        self.line("}")
+    }
+    // a call to a function with BYREF parameters, made before the statement that uses its value
+    // so the arguments can be copied back straight away
+    fn byref_call(&mut self, tkn: &Token, args: &[Expr]) -> String {
+        let (arg_texts, holders) = self.holders(&tkn.lexeme, args, true);
+        for (holder, dtype, arg) in &holders {
+            let arg = self.expr(arg).0;
+            self.hoisted.push(format!("{}[] {} = {{{}}};", type_name(dtype), holder, arg));
+        }
+        let ret = self.scopes.funcs.get(&tkn.lexeme).map_or("Object".into(), type_name);
+        let result = fresh("result", &mut self.names);
+        self.hoisted.push(format!("{} {} = {}({});", ret, result, name(&tkn.lexeme), arg_texts.join(", ")));
+        let copies = self.copy_back(&holders);
+        self.hoisted.extend(copies);
+        result
+    }
+
+    fn case(&mut self, val: &Expr, cases: &[CaseClause], otherwise: Option<&Stmt>) {
+        // a switch can only have single values, not ranges
+        let switchable = matches!(self.scopes.type_of(val), Some(Type::Int | Type::Char | Type::String))
+            && cases.iter().flat_map(|(vals, _)| vals).all(|(case, to)| to.is_none()
+                && (matches!(case, Expr::Literal(Literal::Int(_) | Literal::Char(_) | Literal::String(_))) || int_literal(case).is_some()));
+        if switchable {
+            let selector = self.expr(val).0;
+            self.line(format!("switch ({}) {{", selector));
+            let branches = cases.iter().map(|(vals, stmt)| (Some(vals), stmt)).chain(otherwise.map(|stmt| (None, stmt)));
+            for (vals, stmt) in branches {
+                self.out.depth += 1;
+                match vals {
+                    Some(vals) => for (case, _) in vals {
+                        let case = self.expr(case).0;
+                        self.line(format!("case {}:", case))
+                    },
+                    None => self.line("default:")
+                }
+                self.body(stmt);
+                // a break after a return could never run, which Java doesn't allow
+                let returns = match stmt {
+                    Stmt::Block(stmts) => matches!(stmts.last(), Some(Stmt::Ret(..))),
+                    stmt => matches!(stmt, Stmt::Ret(..))
+                };
+                if !returns {
+                    self.out.depth += 1;
+                    self.line("break;");
+                    self.out.depth -= 1;
+                }
+                self.out.depth -= 1;
+            }
+            self.line("}");
+            return
+        }
+
+        let selector = match val {
+            Expr::IdentExpr(..) | Expr::Literal(_) => val.clone(),
+            _ => {
+                let dtype = self.scopes.infer(val);
+                let val_text = self.expr(val).0;
+                let selector = fresh("selector", &mut self.names);
+                self.line(format!("{} {} = {};", type_name(&dtype), selector, val_text));
+                self.scopes.declare(&selector, dtype);
+                Expr::IdentExpr(Token::new(TokenType::Identifier, selector, 0), Some(0))
+            }
+        };
+        for (idx, (vals, stmt)) in cases.iter().enumerate() {
+            let cond = match &vals[..] {
+                [(case, None)] => self.equals(&selector, case, true).0,
+                vals => self.expr(&case_condition(&selector, vals)).0
+            };
+            self.line(format!("{}if ({}) {{", if idx == 0 { "" } else { "} else " }, cond));
+            self.body(stmt);
+        }
+        if let Some(stmt) = otherwise {
+            if cases.is_empty() { self.stmt(stmt) }
+            else {
+                self.line("} else {");
+                self.body(stmt);
+            }
+        }
+        if !cases.is_empty() { self.line("}") }
+    }
+
+    fn module(&mut self, tkn: &Token, params: &[(Token, Expr, bool)], ret_type: Option<&Expr>, block: &Stmt) {
+        self.scopes.push();
+        let mut param_list = Vec::new();
+        let mut copies = Vec::new();
+        for (param, dtype, byref) in params {
+            let param_name = name(&param.lexeme);
+            self.scopes.declare(&param.lexeme, dtype.clone());
+            match dtype {
+                // arrays are always passed by reference, so a BYVALUE array is copied
+                Expr::ArrType(_, idx2, _) => {
+                    param_list.push(format!("{} {}", type_name(dtype), param_name));
+                    if !byref {
+                        copies.push(format!("{0} = {0}.clone();", param_name));
+                        if idx2.is_some() { copies.push(format!("for (int i = 0; i < {0}.length; i++) {0}[i] = {0}[i].clone();", param_name)) }
+                    }
+                },
+                _ if *byref => {
+                    param_list.push(format!("{}[] {}", type_name(dtype), param_name));
+                    self.holders.insert(param.lexeme.clone());
+                },
+                _ => param_list.push(format!("{} {}", type_name(dtype), param_name))
+            }
+        }
+        let ret = ret_type.map_or("void".into(), type_name);
+
+        self.out.line(format!("static {} {}({}) {{", ret, name(&tkn.lexeme), param_list.join(", ")));
+        self.out.depth += 1;
+        for copy in copies { self.out.line(copy) }
+        self.func = Some(ret_type.is_some());
+        self.stmt(block);
+        self.func = None;
+        self.out.depth -= 1;
+        self.out.line("}");
+        self.out.blank();
+        self.holders.clear();
+        self.scopes.pop();
+    }
+
+    fn call(&mut self, callee: &str, args: &[Expr]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| self.expr(arg).0).collect();
+        format!("{}({})", name(callee), args.join(", "))
+    }
+    // the pseudocode library functions, unless the program defines its own with the same name
+    fn builtin(&mut self, callee: &str, args: &[Expr]) -> Option<(String, u8)> {
+        if self.sigs.contains_key(callee) { return None }
+        let arg = |java: &mut Java, idx: usize, min: u8| paren(java.expr(&args[idx]), min);
+        let is_char = |java: &Java| java.scopes.type_of(&args[0]) == Some(Type::Char);
+        Some(match (callee, args.len()) {
+            ("LENGTH", 1) => (format!("{}.length()", arg(self, 0, ATOM)), ATOM),
+            ("UCASE" | "TO_UPPER", 1) if is_char(self) => (format!("Character.toUpperCase({})", arg(self, 0, 0)), ATOM),
+            ("LCASE" | "TO_LOWER", 1) if is_char(self) => (format!("Character.toLowerCase({})", arg(self, 0, 0)), ATOM),
+            ("UCASE" | "TO_UPPER", 1) => (format!("{}.toUpperCase()", arg(self, 0, ATOM)), ATOM),
+            ("LCASE" | "TO_LOWER", 1) => (format!("{}.toLowerCase()", arg(self, 0, ATOM)), ATOM),
+            ("LEFT", 2) => (format!("{}.substring(0, {})", arg(self, 0, ATOM), arg(self, 1, 0)), ATOM),
+            ("RIGHT", 2) => {
+                let string = arg(self, 0, ATOM);
+                (format!("{0}.substring({0}.length() - {1})", string, arg(self, 1, PRODUCT)), ATOM)
+            },
+            ("MID" | "SUBSTRING", 3) => {
+                let (string, start) = (arg(self, 0, ATOM), self.offset(&args[1], -1));
+                let end = match (int_literal(&args[1]), int_literal(&args[2])) {
+                    (Some(start), Some(len)) => (start - 1 + len).to_string(),
+                    _ => format!("{} + {}", start, arg(self, 2, PRODUCT))
+                };
+                (format!("{}.substring({}, {})", string, start, end), ATOM)
+            },
+            ("INT", 1) => (format!("(int) {}", arg(self, 0, NEGATION)), NEGATION),
+            ("NUM_TO_STR", 1) => (format!("String.valueOf({})", arg(self, 0, 0)), ATOM),
+            ("STR_TO_NUM", 1) => (format!("Double.parseDouble({})", arg(self, 0, 0)), ATOM),
+            ("ROUND", 2) => {
+                let (val, places) = (arg(self, 0, PRODUCT), arg(self, 1, 0));
+                (format!("Math.round({} * Math.pow(10, {1})) / Math.pow(10, {1})", val, places), PRODUCT)
+            },
+            ("ASC", 1) => (format!("(int) {}", arg(self, 0, NEGATION)), NEGATION),
+            ("CHR", 1) => (format!("(char) {}", arg(self, 0, NEGATION)), NEGATION),
+            ("MOD", 2) => (format!("{} % {}", arg(self, 0, PRODUCT), arg(self, 1, NEGATION)), PRODUCT),
+            ("DIV", 2) => (format!("{} / {}", arg(self, 0, PRODUCT), arg(self, 1, NEGATION)), PRODUCT),
+            ("RAND", 1) => (format!("Math.random() * {}", arg(self, 0, NEGATION)), PRODUCT),
+            ("RANDOMBETWEEN", 2) => {
+                let (low, high) = (arg(self, 0, SUM), arg(self, 1, PRODUCT));
+                (format!("{0} + (int) (Math.random() * ({1} - {0} + 1))", low, high), SUM)
+            },
+            _ => return None
+        })
+    }
+
+    // strings are compared with equals rather than ==, which only checks they're the same object
+    fn equals(&mut self, left: &Expr, right: &Expr, equal: bool) -> (String, u8) {
+        let strings = self.scopes.type_of(left) == Some(Type::String) || self.scopes.type_of(right) == Some(Type::String);
+        let (left, right) = (self.expr(left), self.expr(right));
+        match (strings, equal) {
+            (true, true) => (format!("{}.equals({})", paren(left, ATOM), right.0), ATOM),
+            (true, false) => (format!("!{}.equals({})", paren(left, ATOM), right.0), NEGATION),
+            (false, _) => (format!("{} {} {}", paren(left, COMPARISON + 1), if equal { "==" } else { "!=" }, paren(right, COMPARISON + 1)), COMPARISON)
+        }
+    }
+}
+
+impl Exprs for Java {
+    fn expr(&mut self, expr: &Expr) -> (String, u8) {
+        match expr {
+            Expr::Unary(op, right) => match op.ttype {
+                TokenType::NOT => (format!("!{}", paren(self.expr(right), NEGATION)), NEGATION),
+                _ => (format!("-{}", paren(self.expr(right), NEGATION)), NEGATION)
+            },
+            Expr::Binary(left, op, right) => {
+                let (text, prec) = match op.ttype {
+                    TokenType::Equal => return self.equals(left, right, true),
+                    TokenType::NotEqual => return self.equals(left, right, false),
+                    TokenType::Less | TokenType::Greater | TokenType::LessEqual | TokenType::GreaterEqual
+                        if self.scopes.type_of(left) == Some(Type::String) => {
+                        let (left, right) = (paren(self.expr(left), ATOM), self.expr(right).0);
+                        return (format!("{}.compareTo({}) {} 0", left, right, op.lexeme), COMPARISON)
+                    },
+                    // dividing two integers with / in Java drops the fraction, unlike in pseudocode
+                    TokenType::Slash if self.scopes.type_of(left) == Some(Type::Int) && self.scopes.type_of(right) == Some(Type::Int) => {
+                        let (left, right) = (paren(self.expr(left), NEGATION), paren(self.expr(right), PRODUCT + 1));
+                        return (format!("(double) {} / {}", left, right), PRODUCT)
+                    },
+                    TokenType::OR => ("||", OR),
+                    TokenType::AND => ("&&", AND),
+                    TokenType::Less => ("<", COMPARISON),
+                    TokenType::Greater => (">", COMPARISON),
+                    TokenType::LessEqual => ("<=", COMPARISON),
+                    TokenType::GreaterEqual => (">=", COMPARISON),
+                    TokenType::Plus => ("+", SUM),
+                    TokenType::Minus => ("-", SUM),
+                    TokenType::Star => ("*", PRODUCT),
+                    TokenType::Slash => ("/", PRODUCT),
+                    TokenType::MOD => ("%", PRODUCT),
+                    TokenType::DIV => ("/", PRODUCT),
+                    _ => (op.lexeme.as_str(), COMPARISON)
+                };
+                let left_min = if prec == COMPARISON { prec + 1 } else { prec };
+                let left = paren(self.expr(left), left_min);
+                let right = paren(self.expr(right), prec + 1);
+                (format!("{} {} {}", left, text, right), prec)
+            },
+            Expr::Grouping(inner) => match self.expr(inner) {
+                (text, ATOM) => (text, ATOM),
+                (text, _) => (format!("({})", text), ATOM)
+            },
+            Expr::IdentExpr(..) => (self.target(expr), ATOM),
+            Expr::FnCall(tkn, args) => match self.builtin(&tkn.lexeme, args) {
+                Some(builtin) => builtin,
+                None if self.sigs.get(&tkn.lexeme).is_some_and(Signature::passes_back) => (self.byref_call(tkn, args), ATOM),
+                None => (self.call(&tkn.lexeme, args), ATOM)
+            },
+            Expr::Literal(lit) => (match lit {
+                Literal::TRUE => "true".into(),
+                Literal::FALSE => "false".into(),
+                Literal::Int(n) => n.to_string(),
+                Literal::Float(n) => format!("{:?}", n),
+                Literal::Char(c) => string_literal(&c.to_string(), '\''),
+                Literal::String(s) => string_literal(s, '"'),
+                Literal::Type(_) => type_name(expr),
+                lit => lit.to_string()
+            }, if matches!(lit, Literal::Int(n) if *n < 0) || matches!(lit, Literal::Float(n) if *n < 0.0) { NEGATION } else { ATOM }),
+            Expr::ArrIdx(tkn, idx1, idx2, _) => (self.index(&tkn.lexeme, idx1, idx2.as_deref()), ATOM),
+            Expr::ArrType(..) => (type_name(expr), ATOM),
+        }
+    }
+}
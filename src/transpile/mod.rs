@@ -0,0 +1,440 @@
+mod java;
+mod python;
+mod vbnet;
+
+use std::collections::{HashMap, HashSet};
+use super::env::Type;
+use super::expr::Expr;
+use super::stmt::Stmt;
+use super::token::{Literal, Token, TokenType};
+
+/// A language programs can be transpiled to
+#[derive(Clone, Copy, Debug)]
+pub enum Target {
+    Python,
+    Java,
+    VbNet
+}
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Target> {
+        match name.to_lowercase().as_str() {
+            "python" | "py" => Some(Target::Python),
+            "java" => Some(Target::Java),
+            "vb" | "vb.net" | "vbnet" => Some(Target::VbNet),
+            _ => None
+        }
+    }
+}
+
+/// Turns a resolved program into source code in another language, written to be read
+/// by students moving on from pseudocode as much as to be run.
+pub fn transpile(prog: &Stmt, target: Target) -> String {
+    match target {
+        Target::Python => python::transpile(prog),
+        Target::Java => java::transpile(prog),
+        Target::VbNet => vbnet::transpile(prog)
+    }
+}
+
+/// Finds the first keyword of a record type or file handling in a scanned program, which the
+/// interpreter doesn't support yet, so there's nothing to transpile them from
+pub fn unsupported(tokens: &[Token]) -> Option<&Token> {
+    tokens.iter().find(|tkn| matches!(tkn.ttype, TokenType::TYPE | TokenType::ENDTYPE | TokenType::OPENFILE
+        | TokenType::CLOSEFILE | TokenType::READFILE | TokenType::WRITEFILE | TokenType::GETRECORD | TokenType::PUTRECORD
+        | TokenType::SEEK))
+}
+
+// a procedure or function as seen from the places that call it
+struct Signature {
+    params: Vec<Param>
+}
+
+struct Param {
+    name: String,
+    byref: bool,
+    array: bool,
+    dtype: Expr
+}
+
+impl Signature {
+    // whether any parameter is BYREF, other than arrays, which the languages without BYREF have to pass back
+    fn passes_back(&self) -> bool {
+        self.params.iter().any(|param| param.byref && !param.array)
+    }
+}
+
+// whether an expression calls a function with BYREF parameters, which has to be called on a
+// line of its own in languages without them
+fn calls_byref(sigs: &HashMap<String, Signature>, expr: &Expr) -> bool {
+    match expr {
+        Expr::FnCall(name, args) => sigs.get(&name.lexeme).is_some_and(Signature::passes_back) || args.iter().any(|arg| calls_byref(sigs, arg)),
+        Expr::Unary(_, inner) | Expr::Grouping(inner) => calls_byref(sigs, inner),
+        Expr::Binary(left, _, right) => calls_byref(sigs, left) || calls_byref(sigs, right),
+        Expr::ArrIdx(_, idx1, idx2, _) => calls_byref(sigs, idx1) || idx2.as_ref().is_some_and(|idx2| calls_byref(sigs, idx2)),
+        _ => false
+    }
+}
+
+fn signatures(prog: &Stmt) -> HashMap<String, Signature> {
+    let mut sigs = HashMap::new();
+    if let Stmt::Block(stmts) = prog {
+        for stmt in stmts {
+            let (name, params) = match stmt {
+                Stmt::Procedure(name, params, _) | Stmt::Function(name, params, _, _) => (name, params),
+                _ => continue
+            };
+            let params = params.iter().map(|(name, dtype, byref)| Param {
+                name: name.lexeme.clone(),
+                byref: *byref,
+                array: matches!(dtype, Expr::ArrType(..)),
+                dtype: dtype.clone()
+            }).collect();
+            sigs.insert(name.lexeme.clone(), Signature { params });
+        }
+    }
+    sigs
+}
+
+// global variables a procedure or function body gives a new value to
+fn assigned_globals(stmt: &Stmt, names: &mut Vec<String>) {
+    match stmt {
+        Stmt::Assign(Expr::IdentExpr(name, None), _) | Stmt::Input(Expr::IdentExpr(name, None))
+            if !names.contains(&name.lexeme) => names.push(name.lexeme.clone()),
+        Stmt::Block(stmts) => for stmt in stmts { assigned_globals(stmt, names) },
+        Stmt::ForTo(_, _, _, _, block) | Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => assigned_globals(block, names),
+        Stmt::IfThen(_, _, then_block, else_block) => {
+            assigned_globals(then_block, names);
+            if let Some(block) = else_block { assigned_globals(block, names) }
+        },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { assigned_globals(stmt, names) }
+            if let Some(stmt) = otherwise { assigned_globals(stmt, names) }
+        },
+        _ => {}
+    }
+}
+
+// every name the program gives to a variable, array, procedure or function
+fn names(stmt: &Stmt, names: &mut HashSet<String>) {
+    match stmt {
+        Stmt::Declare(name, _) | Stmt::Constant(name, _)
+            | Stmt::Assign(Expr::IdentExpr(name, _) | Expr::ArrIdx(name, ..), _)
+            | Stmt::Input(Expr::IdentExpr(name, _) | Expr::ArrIdx(name, ..)) => { names.insert(name.lexeme.clone()); },
+        Stmt::Block(stmts) => for stmt in stmts { self::names(stmt, names) },
+        Stmt::Procedure(name, params, block) | Stmt::Function(name, params, _, block) => {
+            names.insert(name.lexeme.clone());
+            for (param, _, _) in params { names.insert(param.lexeme.clone()); }
+            self::names(block, names)
+        },
+        Stmt::ForTo(counter, _, _, _, block) => {
+            names.insert(counter.lexeme.clone());
+            self::names(block, names)
+        },
+        Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => self::names(block, names),
+        Stmt::IfThen(_, _, then_block, else_block) => {
+            self::names(then_block, names);
+            if let Some(block) = else_block { self::names(block, names) }
+        },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { self::names(stmt, names) }
+            if let Some(stmt) = otherwise { self::names(stmt, names) }
+        },
+        _ => {}
+    }
+}
+
+// `base`, or `base2`, `base3` and so on if that's taken, for a variable the generated code adds
+fn fresh(base: &str, names: &mut HashSet<String>) -> String {
+    let mut name = base.to_string();
+    let mut n = 1;
+    while names.contains(&name) {
+        n += 1;
+        name = format!("{}{}", base, n);
+    }
+    names.insert(name.clone());
+    name
+}
+
+// the condition for a CASE clause matching, for languages without anything like it: the selector
+// equals one of the values, or is between the ends of one of the ranges
+fn case_condition(selector: &Expr, vals: &[(Expr, Option<Expr>)]) -> Expr {
+    let op = |ttype: TokenType, lexeme: &str| Token::new(ttype, lexeme.into(), 0);
+    let conds = vals.iter().map(|(from, to)| match to {
+        Some(to) => Expr::Binary(
+            Box::new(Expr::Binary(Box::new(from.clone()), op(TokenType::LessEqual, "<="), Box::new(selector.clone()))),
+            op(TokenType::AND, "AND"),
+            Box::new(Expr::Binary(Box::new(selector.clone()), op(TokenType::LessEqual, "<="), Box::new(to.clone())))
+        ),
+        None => Expr::Binary(Box::new(selector.clone()), op(TokenType::Equal, "="), Box::new(from.clone()))
+    });
+    conds.reduce(|left, right| Expr::Binary(Box::new(left), op(TokenType::OR, "OR"), Box::new(right))).expect("a CASE clause has a value")
+}
+
+// the value of an integer literal, possibly negated or in brackets
+fn int_literal(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(Literal::Int(n)) => Some(*n as i64),
+        Expr::Unary(op, right) if op.ttype == TokenType::Minus => int_literal(right).map(|n| -n),
+        Expr::Grouping(inner) => int_literal(inner),
+        _ => None
+    }
+}
+
+// operator precedence, loosest first; each language puts its own operators on this scale
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARISON: u8 = 4;
+const CONCAT: u8 = 5;
+const SUM: u8 = 6;
+const PRODUCT: u8 = 7;
+const NEGATION: u8 = 8;
+const ATOM: u8 = 9;
+
+fn paren((text, prec): (String, u8), min: u8) -> String {
+    if prec < min { format!("({})", text) } else { text }
+}
+
+fn string_literal(val: &str, quote: char) -> String {
+    let mut out = String::from(quote);
+    for c in val.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c == quote => { out.push('\\'); out.push(c) },
+            c => out.push(c)
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Writes expressions in one language, which is all the index arithmetic below needs
+trait Exprs {
+    // the expression and its precedence, so callers know when it needs brackets
+    fn expr(&mut self, expr: &Expr) -> (String, u8);
+
+    // `expr + delta`, worked out if `expr` is a number
+    fn offset(&mut self, expr: &Expr, delta: i64) -> String {
+        if let Some(n) = int_literal(expr) { return (n + delta).to_string() }
+        // `I + 1` as an index becomes `I` rather than `I + 1 - 1`
+        if let Expr::Binary(left, op, right) = expr {
+            match (&op.ttype, int_literal(right)) {
+                (TokenType::Plus, Some(n)) => return self.offset(left, delta + n),
+                (TokenType::Minus, Some(n)) => return self.offset(left, delta - n),
+                _ => {}
+            }
+        }
+        let expr = self.expr(expr);
+        match delta {
+            0 => expr.0,
+            _ => format!("{} {} {}", paren(expr, SUM), if delta > 0 { "+" } else { "-" }, delta.abs())
+        }
+    }
+    // `expr - lower`, for turning an index into one that starts at 0
+    fn zero_based(&mut self, expr: &Expr, lower: &Expr) -> String {
+        match int_literal(lower) {
+            Some(lower) => self.offset(expr, -lower),
+            None => {
+                let (expr, lower) = (self.expr(expr), self.expr(lower));
+                format!("{} - {}", paren(expr, SUM), paren(lower, PRODUCT))
+            }
+        }
+    }
+    // how many elements there are from `start` to `end`
+    fn size(&mut self, start: &Expr, end: &Expr) -> String {
+        match int_literal(start) {
+            Some(start) => match int_literal(end) {
+                Some(end) => (end - start + 1).to_string(),
+                None => paren((self.offset(end, 1 - start), SUM), PRODUCT)
+            },
+            None => {
+                let (end, start) = (self.expr(end), self.expr(start));
+                format!("({} - {} + 1)", paren(end, SUM), paren(start, PRODUCT))
+            }
+        }
+    }
+}
+
+/// The variables in scope at each point of the program with their declared or inferred types,
+/// for languages where every variable is declared before it's used. Scopes follow the resolver.
+struct Scopes {
+    globals: HashMap<String, Expr>,
+    locals: Vec<HashMap<String, Expr>>,
+    funcs: HashMap<String, Expr> // the return type of each function
+}
+
+fn type_expr(dtype: Type) -> Expr { Expr::Literal(Literal::Type(dtype)) }
+
+impl Scopes {
+    fn new(prog: &Stmt) -> Self {
+        let mut scopes = Scopes { globals: HashMap::new(), locals: Vec::new(), funcs: HashMap::new() };
+        if let Stmt::Block(stmts) = prog {
+            for stmt in stmts {
+                if let Stmt::Function(name, _, ret_type, _) = stmt { scopes.funcs.insert(name.lexeme.clone(), ret_type.clone()); }
+            }
+        }
+        scopes
+    }
+
+    // the statement in the main program that first declares or gives a value to each global
+    // variable, in order; like in the resolver, a variable first given a value inside one of the
+    // main program's blocks is still global
+    fn globals<'a>(&mut self, prog: &'a Stmt) -> Vec<&'a Stmt> {
+        let mut firsts = Vec::new();
+        if let Stmt::Block(stmts) = prog {
+            for stmt in stmts {
+                match stmt {
+                    Stmt::Declare(name, dtype) if !self.globals.contains_key(&name.lexeme) => {
+                        self.globals.insert(name.lexeme.clone(), dtype.clone());
+                        firsts.push(stmt);
+                    },
+                    Stmt::Constant(name, val) if !self.globals.contains_key(&name.lexeme) => {
+                        let dtype = self.infer(val);
+                        self.globals.insert(name.lexeme.clone(), dtype);
+                        firsts.push(stmt);
+                    },
+                    _ => self.first_values(stmt, 0, &mut firsts)
+                }
+            }
+        }
+        firsts
+    }
+
+    // the statements in `stmt` that give a value to a variable that isn't in scope yet, which is
+    // declared `level` scopes in (0 for a global) with the type of that first value
+    fn first_values<'a>(&mut self, stmt: &'a Stmt, level: usize, firsts: &mut Vec<&'a Stmt>) {
+        let mut nested = |scopes: &mut Scopes, stmt: &'a Stmt, counter: Option<(&Token, Expr)>| {
+            scopes.push();
+            if let Some((counter, dtype)) = counter { scopes.declare(&counter.lexeme, dtype) }
+            scopes.first_values(stmt, level, firsts);
+            scopes.pop();
+        };
+        match stmt {
+            Stmt::Assign(Expr::IdentExpr(name, _), _) | Stmt::Input(Expr::IdentExpr(name, _)) if self.get(&name.lexeme).is_none() => {
+                let dtype = match stmt {
+                    Stmt::Assign(_, val) => self.infer(val),
+                    _ => type_expr(Type::String)
+                };
+                match level {
+                    0 => self.globals.insert(name.lexeme.clone(), dtype),
+                    level => self.locals[level - 1].insert(name.lexeme.clone(), dtype)
+                };
+                firsts.push(stmt);
+            },
+            Stmt::Declare(name, dtype) => self.declare(&name.lexeme, dtype.clone()),
+            Stmt::Block(stmts) => for stmt in stmts { self.first_values(stmt, level, firsts) },
+            Stmt::ForTo(counter, start, _, _, block) => {
+                let dtype = self.infer(start);
+                nested(self, block, Some((counter, dtype)))
+            },
+            Stmt::Repeat(_, _, block) | Stmt::WhileDo(_, _, block) => nested(self, block, None),
+            Stmt::IfThen(_, _, then_block, else_block) => {
+                nested(self, then_block, None);
+                if let Some(block) = else_block { nested(self, block, None) }
+            },
+            Stmt::Case(_, _, cases, otherwise) => {
+                for (_, stmt) in cases { nested(self, stmt, None) }
+                if let Some(stmt) = otherwise { nested(self, stmt, None) }
+            },
+            _ => {}
+        }
+    }
+
+    fn push(&mut self) { self.locals.push(HashMap::new()) }
+    fn pop(&mut self) { self.locals.pop(); }
+    fn declare(&mut self, name: &str, dtype: Expr) {
+        match self.locals.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), dtype),
+            None => self.globals.insert(name.to_string(), dtype)
+        };
+    }
+    fn get(&self, name: &str) -> Option<&Expr> {
+        self.locals.iter().rev().find_map(|scope| scope.get(name)).or_else(|| self.globals.get(name))
+    }
+    fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|scope| scope.contains_key(name))
+    }
+
+    // the type of a variable or array element given a value, as a type expression
+    fn infer(&self, expr: &Expr) -> Expr {
+        match self.type_of(expr) {
+            Some(dtype) => type_expr(dtype),
+            None => Expr::Literal(Literal::Type(Type::Type))
+        }
+    }
+    fn type_of(&self, expr: &Expr) -> Option<Type> {
+        let of = |dtype: &Expr| match dtype {
+            Expr::Literal(Literal::Type(dtype)) => Some(dtype.clone()),
+            _ => None
+        };
+        match expr {
+            Expr::Literal(lit) => match lit {
+                Literal::TRUE | Literal::FALSE => Some(Type::Bool),
+                Literal::Int(_) => Some(Type::Int),
+                Literal::Float(_) => Some(Type::Float),
+                Literal::Char(_) => Some(Type::Char),
+                Literal::String(_) => Some(Type::String),
+                Literal::Date(..) => Some(Type::Date),
+                _ => None
+            },
+            Expr::IdentExpr(name, _) => self.get(&name.lexeme).and_then(of),
+            Expr::ArrIdx(name, _, _, _) => match self.get(&name.lexeme) {
+                Some(Expr::ArrType(_, _, inner)) => of(inner),
+                _ => None
+            },
+            Expr::Grouping(inner) => self.type_of(inner),
+            Expr::Unary(op, right) => match op.ttype {
+                TokenType::NOT => Some(Type::Bool),
+                _ => self.type_of(right)
+            },
+            Expr::Binary(left, op, right) => match op.ttype {
+                TokenType::Plus | TokenType::Minus | TokenType::Star => match (self.type_of(left), self.type_of(right)) {
+                    (Some(Type::Int), Some(Type::Int)) => Some(Type::Int),
+                    (Some(Type::String), _) | (_, Some(Type::String)) => Some(Type::String),
+                    (None, _) | (_, None) => None,
+                    _ => Some(Type::Float)
+                },
+                TokenType::Slash => Some(Type::Float),
+                TokenType::MOD | TokenType::DIV => Some(Type::Int),
+                _ => Some(Type::Bool)
+            },
+            Expr::FnCall(name, args) => match self.funcs.get(&name.lexeme) {
+                Some(ret_type) => of(ret_type),
+                None => match name.lexeme.as_str() {
+                    "LENGTH" | "INT" | "ASC" | "MOD" | "DIV" | "RANDOMBETWEEN" => Some(Type::Int),
+                    "UCASE" | "TO_UPPER" | "LCASE" | "TO_LOWER" => args.first().and_then(|arg| self.type_of(arg)),
+                    "LEFT" | "RIGHT" | "MID" | "SUBSTRING" | "NUM_TO_STR" => Some(Type::String),
+                    "STR_TO_NUM" | "ROUND" | "RAND" => Some(Type::Float),
+                    "CHR" => Some(Type::Char),
+                    _ => None
+                }
+            },
+            Expr::ArrType(..) => None
+        }
+    }
+}
+
+/// Collects the lines of the generated program, indenting them to the current depth
+struct Writer {
+    lines: Vec<String>,
+    depth: usize,
+    indent: &'static str
+}
+
+impl Writer {
+    fn new(indent: &'static str) -> Self { Self { lines: Vec::new(), depth: 0, indent } }
+
+    fn line(&mut self, text: impl AsRef<str>) {
+        self.lines.push(format!("{}{}", self.indent.repeat(self.depth), text.as_ref()));
+    }
+    // an empty line, unless there already is one
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|line| !line.is_empty()) { self.lines.push(String::new()) }
+    }
+    fn finish(mut self) -> String {
+        while self.lines.last().is_some_and(|line| line.is_empty()) { self.lines.pop(); }
+        self.lines.join("\n") + "\n"
+    }
+}
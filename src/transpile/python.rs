@@ -0,0 +1,533 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::env::Type;
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::{Literal, Token, TokenType};
+use super::{AND, ATOM, COMPARISON, Exprs, NEGATION, NOT, OR, PRODUCT, SUM, Scopes, Signature, Writer,
+    assigned_globals, calls_byref, case_condition, fresh, int_literal, names, paren, signatures, string_literal, type_expr};
+
+pub fn transpile(prog: &Stmt) -> String {
+    let mut py = Python {
+        out: Writer::new("    "),
+        sigs: signatures(prog),
+        scopes: Scopes::new(prog),
+        names: HashSet::new(),
+        hoisted: Vec::new(),
+        imports: BTreeSet::new(),
+        shows_reals: false,
+        module: None
+    };
+    names(prog, &mut py.names);
+    py.scopes.globals(prog);
+    py.stmt(prog);
+
+    let mut out = Writer::new("    ");
+    out.line("# Transpiled from pseudocode by camps");
+    for import in &py.imports { out.line(format!("import {}", import)) }
+    out.blank();
+    if py.shows_reals {
+        out.line("def show_real(value: float) -> str:");
+        out.line("    # a whole number is shown without a fraction, like the pseudocode interpreter does");
+        out.line("    return str(int(value)) if value.is_integer() else str(value)");
+        out.blank();
+        out.blank();
+    }
+    out.lines.extend(py.out.lines);
+    out.finish()
+}
+
+// names that can't be used as variables, or that the generated code relies on
+const RESERVED: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+    "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    "print", "input", "len", "range", "int", "float", "str", "bool", "list", "round", "chr", "ord", "random", "datetime", "show_real"
+];
+
+// the procedure or function being transpiled
+struct Module {
+    func: bool,
+    returned: Vec<String> // BYREF parameters passed back to the caller, as Python can't change the caller's variables
+}
+
+struct Python {
+    out: Writer,
+    sigs: HashMap<String, Signature>,
+    scopes: Scopes,
+    names: HashSet<String>, // the program's own names and the ones the generated code has taken
+    hoisted: Vec<String>, // calls to functions with BYREF parameters, made on lines of their own before the statement using them
+    imports: BTreeSet<&'static str>,
+    shows_reals: bool, // whether OUTPUT uses `show_real`, which is then defined at the top
+    module: Option<Module>
+}
+
+fn name(name: &str) -> String {
+    if RESERVED.contains(&name) { format!("{}_", name) } else { name.to_string() }
+}
+
+// each value in `list` written the way the interpreter shows it inside an array
+fn shown_elements(list: &str, dtype: &Expr) -> String {
+    match dtype {
+        Expr::Literal(Literal::Type(Type::Bool)) => format!("\"TRUE\" if value else \"FALSE\" for value in {}", list),
+        Expr::Literal(Literal::Type(Type::Char)) => format!("f\"'{{value}}'\" for value in {}", list),
+        Expr::Literal(Literal::Type(Type::String)) => format!("f'\"{{value}}\"' for value in {}", list),
+        Expr::Literal(Literal::Type(Type::Float)) => format!("map(show_real, {})", list),
+        _ => format!("map(str, {})", list)
+    }
+}
+
+impl Python {
+    fn type_hint(&mut self, dtype: &Expr) -> String {
+        match dtype {
+            Expr::Literal(Literal::Type(dtype)) => match dtype {
+                Type::Int => "int".into(),
+                Type::Float => "float".into(),
+                Type::Bool => "bool".into(),
+                Type::Char | Type::String => "str".into(),
+                Type::Date => {
+                    self.imports.insert("datetime");
+                    "datetime.date".into()
+                },
+                Type::Array(..) => "list".into(),
+                Type::Type => "type".into()
+            },
+            Expr::ArrType(_, idx2, inner) => {
+                let inner = self.type_hint(inner);
+                if idx2.is_some() { format!("list[list[{}]]", inner) } else { format!("list[{}]", inner) }
+            },
+            _ => "object".into()
+        }
+    }
+    fn default_value(dtype: &Expr) -> &'static str {
+        match dtype {
+            Expr::Literal(Literal::Type(Type::Int)) => "0",
+            Expr::Literal(Literal::Type(Type::Float)) => "0.0",
+            Expr::Literal(Literal::Type(Type::Bool)) => "False",
+            Expr::Literal(Literal::Type(Type::Char | Type::String)) => "\"\"",
+            _ => "None"
+        }
+    }
+
+    fn index(&mut self, arr: &str, idx1: &Expr, idx2: Option<&Expr>) -> String {
+        let one = Expr::Literal(Literal::Int(1));
+        let (lower1, lower2) = match self.scopes.get(arr) {
+            Some(Expr::ArrType((start1, _), idx2, _)) => ((**start1).clone(), idx2.as_ref().map_or(one.clone(), |idx2| (*idx2.0).clone())),
+            _ => (one.clone(), one)
+        };
+        let mut text = format!("{}[{}]", name(arr), self.zero_based(idx1, &lower1));
+        if let Some(idx2) = idx2 { text.push_str(&format!("[{}]", self.zero_based(idx2, &lower2))) }
+        text
+    }
+    fn array_value(&mut self, dtype: &Expr) -> String {
+        let (size, idx2, inner) = match dtype {
+            Expr::ArrType((start, end), idx2, inner) => (self.size(start, end), idx2, inner),
+            _ => return "None".into()
+        };
+        let default = Python::default_value(inner);
+        match idx2 {
+            Some((start2, end2)) => format!("[[{}] * {} for _ in range({})]", default, self.size(start2, end2), size),
+            None => format!("[{}] * {}", default, size)
+        }
+    }
+    fn target(&mut self, target: &Expr) -> String {
+        match target {
+            Expr::IdentExpr(tkn, _) => name(&tkn.lexeme),
+            Expr::ArrIdx(tkn, idx1, idx2, _) => self.index(&tkn.lexeme, idx1, idx2.as_deref()),
+            _ => "_".into()
+        }
+    }
+
+    // writes a line, after the calls its expressions need made first
+    fn line(&mut self, text: impl AsRef<str>) {
+        for line in std::mem::take(&mut self.hoisted) { self.out.line(line) }
+        self.out.line(text)
+    }
+    fn body(&mut self, stmt: &Stmt) {
+        self.out.depth += 1;
+        self.scopes.push();
+        let len = self.out.lines.len();
+        self.stmt(stmt);
+        if self.out.lines.len() == len { self.out.line("pass") }
+        self.scopes.pop();
+        self.out.depth -= 1;
+    }
+    fn returned(&self) -> Option<String> {
+        match &self.module {
+            Some(module) if !module.returned.is_empty() =>
+                Some(module.returned.iter().map(|param| name(param)).collect::<Vec<_>>().join(", ")),
+            _ => None
+        }
+    }
+    // the variables given as BYREF arguments, which are assigned what the procedure or function passes back
+    fn byref_targets(&mut self, callee: &str, args: &[Expr]) -> Vec<String> {
+        let args: Vec<&Expr> = match self.sigs.get(callee) {
+            Some(sig) => sig.params.iter().zip(args).filter(|(param, _)| param.byref && !param.array).map(|(_, arg)| arg).collect(),
+            None => Vec::new()
+        };
+        args.into_iter().map(|arg| self.target(arg)).collect()
+    }
+    // a value to OUTPUT, written so it's shown the way the interpreter shows it
+    fn show(&mut self, expr: &Expr) -> String {
+        if let Expr::IdentExpr(tkn, _) = expr {
+            if let Some(Expr::ArrType(_, idx2, inner)) = self.scopes.get(&tkn.lexeme).cloned() {
+                let arr = name(&tkn.lexeme);
+                if matches!(*inner, Expr::Literal(Literal::Type(Type::Float))) { self.shows_reals = true }
+                return match idx2 {
+                    Some(_) => format!("\"[ \" + \" \".join(\"[ \" + \" \".join({}) + \" ]\" for row in {}) + \" ]\"", shown_elements("row", &inner), arr),
+                    None => format!("\"[ \" + \" \".join({}) + \" ]\"", shown_elements(&arr, &inner))
+                }
+            }
+        }
+        match expr {
+            Expr::Literal(Literal::TRUE) => "\"TRUE\"".into(),
+            Expr::Literal(Literal::FALSE) => "\"FALSE\"".into(),
+            _ if self.scopes.type_of(expr) == Some(Type::Bool) => format!("\"TRUE\" if {} else \"FALSE\"", paren(self.expr(expr), OR)),
+            _ if self.scopes.type_of(expr) == Some(Type::Float) => {
+                self.shows_reals = true;
+                format!("show_real({})", self.expr(expr).0)
+            },
+            _ => self.expr(expr).0
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts { self.stmt(stmt) },
+            Stmt::ExprStmt(expr) => {
+                let expr = self.expr(expr).0;
+                self.line(expr)
+            },
+            Stmt::Declare(tkn, dtype) => {
+                self.scopes.declare(&tkn.lexeme, dtype.clone());
+                let hint = self.type_hint(dtype);
+                if let Expr::ArrType(..) = dtype {
+                    let val = self.array_value(dtype);
+                    self.line(format!("{}: {} = {}", name(&tkn.lexeme), hint, val))
+                } else {
+                    self.line(format!("{}: {}", name(&tkn.lexeme), hint))
+                }
+            },
+            Stmt::Constant(tkn, val) => {
+                let dtype = self.scopes.infer(val);
+                let val = self.expr(val).0;
+                self.line(format!("{} = {}", name(&tkn.lexeme), val));
+                self.scopes.declare(&tkn.lexeme, dtype);
+            },
+            Stmt::Assign(target, val) => {
+                if let Expr::IdentExpr(tkn, _) = target {
+                    if self.scopes.get(&tkn.lexeme).is_none() {
+                        let dtype = self.scopes.infer(val);
+                        self.scopes.declare(&tkn.lexeme, dtype);
+                    }
+                }
+                let (target, val) = (self.target(target), self.expr(val).0);
+                self.line(format!("{} = {}", target, val))
+            },
+            Stmt::ProcCall(tkn, args) => {
+                let call = self.call(&tkn.lexeme, args);
+                // BYREF arguments come back as the procedure's return value
+                let targets = self.byref_targets(&tkn.lexeme, args);
+                if targets.is_empty() { self.line(call) }
+                else { self.line(format!("{} = {}", targets.join(", "), call)) }
+            },
+            Stmt::Input(target) => {
+                if let Expr::IdentExpr(tkn, _) = target {
+                    if self.scopes.get(&tkn.lexeme).is_none() { self.scopes.declare(&tkn.lexeme, type_expr(Type::String)) }
+                }
+                let dtype = self.scopes.type_of(target);
+                let target = self.target(target);
+                match dtype {
+                    Some(Type::Int) => self.line(format!("{} = int(input())", target)),
+                    Some(Type::Float) => self.line(format!("{} = float(input())", target)),
+                    Some(Type::Bool) => self.line(format!("{} = input() == \"TRUE\"", target)),
+                    _ => self.line(format!("{} = input()", target))
+                }
+            },
+            Stmt::Output(_, exprs) => {
+                let mut args: Vec<String> = exprs.iter().map(|expr| self.show(expr)).collect();
+                // OUTPUT doesn't put anything between the values
+                if args.len() > 1 { args.push("sep=\"\"".into()) }
+                self.line(format!("print({})", args.join(", ")))
+            },
+            Stmt::Ret(_, val) => match (&self.module, self.returned()) {
+                // a function passes its BYREF parameters back along with its value
+                (Some(Module { func: true, .. }), Some(returned)) => {
+                    let val = self.expr(val).0;
+                    self.line(format!("return {}, {}", val, returned))
+                },
+                (Some(Module { func: true, .. }), None) => {
+                    let val = self.expr(val).0;
+                    self.line(format!("return {}", val))
+                },
+                (_, Some(returned)) => self.line(format!("return {}", returned)),
+                _ => self.line("return")
+            },
+            Stmt::Procedure(tkn, params, block) => self.module(&tkn.lexeme, params, None, block),
+            Stmt::Function(tkn, params, ret_type, block) => self.module(&tkn.lexeme, params, Some(ret_type), block),
+            Stmt::ForTo(counter, start_expr, end, step, block) => {
+                let start = self.expr(start_expr).0;
+                let range = match step.as_ref().map(|step| (int_literal(step), step)) {
+                    None | Some((Some(1), _)) => match start.as_str() {
+                        "0" => format!("range({})", self.offset(end, 1)),
+                        _ => format!("range({}, {})", start, self.offset(end, 1))
+                    },
+                    Some((Some(n), _)) => format!("range({}, {}, {})", start, self.offset(end, n.signum()), n),
+                    Some((None, step)) => {
+                        let step = paren(self.expr(step), ATOM);
+                        let end = paren(self.expr(end), SUM);
+                        format!("range({}, {} + (1 if {} > 0 else -1), {})", start, end, step, step)
+                    }
+                };
+                self.line(format!("for {} in {}:", name(&counter.lexeme), range));
+                self.scopes.push();
+                let dtype = self.scopes.infer(start_expr);
+                self.scopes.declare(&counter.lexeme, dtype);
+                self.body(block);
+                self.scopes.pop();
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let cond = self.expr(cond).0;
+                self.line(format!("if {}:", cond));
+                self.body(then_block);
+                let mut else_block = else_block.as_deref();
+                while let Some(block) = else_block {
+                    // ELSE followed by just another IF is written as elif
+                    match block {
+                        // unless its condition calls a function that has to be called before the IF
+                        Stmt::Block(stmts) if matches!(stmts.as_slice(), [Stmt::IfThen(_, cond, ..)] if !calls_byref(&self.sigs, cond)) => {
+                            if let Stmt::IfThen(_, cond, then_block, next) = &stmts[0] {
+                                let cond = self.expr(cond).0;
+                                self.line(format!("elif {}:", cond));
+                                self.body(then_block);
+                                else_block = next.as_deref();
+                            }
+                        },
+                        _ => {
+                            self.line("else:");
+                            self.body(block);
+                            else_block = None;
+                        }
+                    }
+                }
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                let selector = match val {
+                    Expr::IdentExpr(..) | Expr::Literal(_) => val.clone(),
+                    _ => {
+                        let val = self.expr(val).0;
+                        let selector = fresh("selector", &mut self.names);
+                        self.line(format!("{} = {}", selector, val));
+                        Expr::IdentExpr(Token::new(TokenType::Identifier, selector, 0), Some(0))
+                    }
+                };
+                for (idx, (vals, stmt)) in cases.iter().enumerate() {
+                    let cond = match &vals[..] {
+                        [(case, None)] => format!("{} == {}", self.expr(&selector).0, paren(self.expr(case), SUM)),
+                        vals => self.expr(&case_condition(&selector, vals)).0
+                    };
+                    self.line(format!("{} {}:", if idx == 0 { "if" } else { "elif" }, cond));
+                    self.body(stmt);
+                }
+                if let Some(stmt) = otherwise {
+                    if cases.is_empty() { self.stmt(stmt) }
+                    else {
+                        self.line("else:");
+                        self.body(stmt);
+                    }
+                }
+            },
+            Stmt::Repeat(_, cond, block) => {
+                self.line("while True:");
+                self.body(block);
+                let cond = self.expr(cond).0;
+                self.out.depth += 1;
+                self.line(format!("if {}:", cond));
+                self.out.depth += 1;
+                self.line("break");
+                self.out.depth -= 2;
+            },
+            // a condition calling a function with BYREF parameters is checked inside the loop, after the call
+            Stmt::WhileDo(_, cond, block) if calls_byref(&self.sigs, cond) => {
+                self.line("while True:");
+                self.out.depth += 1;
+                let cond = paren(self.expr(cond), NOT);
+                self.line(format!("if not {}:", cond));
+                self.out.depth += 1;
+                self.line("break");
+                self.out.depth -= 1;
+                self.scopes.push();
+                self.stmt(block);
+                self.scopes.pop();
+                self.out.depth -= 1;
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                let cond = self.expr(cond).0;
+                self.line(format!("while {}:", cond));
+                self.body(block)
+            },
+        }
+    }
+
+    fn module(&mut self, module_name: &str, params: &[(crate::token::Token, Expr, bool)], ret_type: Option<&Expr>, block: &Stmt) {
+        self.scopes.push();
+        let mut param_list = Vec::new();
+        let mut returned = Vec::new();
+        let mut returned_hints = Vec::new();
+        let mut copies = Vec::new();
+        for (tkn, dtype, byref) in params {
+            self.scopes.declare(&tkn.lexeme, dtype.clone());
+            let hint = self.type_hint(dtype);
+            param_list.push(format!("{}: {}", name(&tkn.lexeme), hint));
+            if let Expr::ArrType(_, idx2, _) = dtype {
+                // lists are always passed by reference, so a BYVALUE array is copied
+                if !byref {
+                    copies.push(match idx2 {
+                        Some(_) => format!("{0} = [list(row) for row in {0}]", name(&tkn.lexeme)),
+                        None => format!("{0} = list({0})", name(&tkn.lexeme))
+                    })
+                }
+            } else if *byref {
+                returned.push(tkn.lexeme.clone());
+                returned_hints.push(hint);
+            }
+        }
+        let ret_hint = match ret_type {
+            Some(ret_type) if !returned.is_empty() => format!(" -> tuple[{}, {}]", self.type_hint(ret_type), returned_hints.join(", ")),
+            Some(ret_type) => format!(" -> {}", self.type_hint(ret_type)),
+            None => String::new()
+        };
+
+        self.out.blank();
+        self.out.line(format!("def {}({}){}:", name(module_name), param_list.join(", "), ret_hint));
+        self.out.depth += 1;
+        let mut globals = Vec::new();
+        assigned_globals(block, &mut globals);
+        if !globals.is_empty() {
+            self.out.line(format!("global {}", globals.iter().map(|global| name(global)).collect::<Vec<_>>().join(", ")))
+        }
+        for copy in copies { self.out.line(copy) }
+        self.out.depth -= 1;
+
+        self.module = Some(Module { func: ret_type.is_some(), returned });
+        self.body(block);
+        // a procedure that runs off its end still passes back its BYREF parameters; a function can't
+        let ends_in_return = matches!(block, Stmt::Block(stmts) if matches!(stmts.last(), Some(Stmt::Ret(..))));
+        if let (Some(returned), false, None) = (self.returned(), ends_in_return, ret_type) {
+            self.out.depth += 1;
+            self.out.line(format!("return {}", returned));
+            self.out.depth -= 1;
+        }
+        self.module = None;
+        self.scopes.pop();
+        self.out.blank();
+    }
+
+    fn args(&mut self, args: &[Expr]) -> Vec<String> {
+        args.iter().map(|arg| self.expr(arg).0).collect()
+    }
+    fn call(&mut self, callee: &str, args: &[Expr]) -> String {
+        format!("{}({})", name(callee), self.args(args).join(", "))
+    }
+    // the pseudocode library functions, unless the program defines its own with the same name
+    fn builtin(&mut self, callee: &str, args: &[Expr]) -> Option<(String, u8)> {
+        if self.sigs.contains_key(callee) { return None }
+        let arg = |py: &mut Python, idx: usize, min: u8| paren(py.expr(&args[idx]), min);
+        Some(match (callee, args.len()) {
+            ("LENGTH", 1) => (format!("len({})", arg(self, 0, 0)), ATOM),
+            ("UCASE" | "TO_UPPER", 1) => (format!("{}.upper()", arg(self, 0, ATOM)), ATOM),
+            ("LCASE" | "TO_LOWER", 1) => (format!("{}.lower()", arg(self, 0, ATOM)), ATOM),
+            ("LEFT", 2) => (format!("{}[:{}]", arg(self, 0, ATOM), arg(self, 1, 0)), ATOM),
+            ("RIGHT", 2) => (format!("{}[-{}:]", arg(self, 0, ATOM), arg(self, 1, NEGATION)), ATOM),
+            ("MID" | "SUBSTRING", 3) => {
+                let (string, start) = (arg(self, 0, ATOM), self.offset(&args[1], -1));
+                let end = match (int_literal(&args[1]), int_literal(&args[2])) {
+                    (Some(start), Some(len)) => (start - 1 + len).to_string(),
+                    _ => format!("{} + {}", start, arg(self, 2, PRODUCT))
+                };
+                (format!("{}[{}:{}]", string, start, end), ATOM)
+            },
+            ("INT", 1) => (format!("int({})", arg(self, 0, 0)), ATOM),
+            ("NUM_TO_STR", 1) => (format!("str({})", arg(self, 0, 0)), ATOM),
+            ("STR_TO_NUM", 1) => (format!("float({})", arg(self, 0, 0)), ATOM),
+            ("ROUND", 2) => (format!("round({}, {})", arg(self, 0, 0), arg(self, 1, 0)), ATOM),
+            ("ASC", 1) => (format!("ord({})", arg(self, 0, 0)), ATOM),
+            ("CHR", 1) => (format!("chr({})", arg(self, 0, 0)), ATOM),
+            ("MOD", 2) => (format!("{} % {}", arg(self, 0, PRODUCT), arg(self, 1, NEGATION)), PRODUCT),
+            ("DIV", 2) => (format!("{} // {}", arg(self, 0, PRODUCT), arg(self, 1, NEGATION)), PRODUCT),
+            ("RAND", 1) => {
+                self.imports.insert("random");
+                (format!("random.random() * {}", arg(self, 0, NEGATION)), PRODUCT)
+            },
+            ("RANDOMBETWEEN", 2) => {
+                self.imports.insert("random");
+                (format!("random.randint({}, {})", arg(self, 0, 0), arg(self, 1, 0)), ATOM)
+            },
+            _ => return None
+        })
+    }
+}
+
+impl Exprs for Python {
+    fn expr(&mut self, expr: &Expr) -> (String, u8) {
+        match expr {
+            Expr::Unary(op, right) => match op.ttype {
+                TokenType::NOT => (format!("not {}", paren(self.expr(right), NOT)), NOT),
+                _ => (format!("-{}", paren(self.expr(right), NEGATION)), NEGATION)
+            },
+            Expr::Binary(left, op, right) => {
+                let (text, prec) = match op.ttype {
+                    TokenType::OR => ("or", OR),
+                    TokenType::AND => ("and", AND),
+                    TokenType::Equal => ("==", COMPARISON),
+                    TokenType::NotEqual => ("!=", COMPARISON),
+                    TokenType::Less => ("<", COMPARISON),
+                    TokenType::Greater => (">", COMPARISON),
+                    TokenType::LessEqual => ("<=", COMPARISON),
+                    TokenType::GreaterEqual => (">=", COMPARISON),
+                    TokenType::Plus => ("+", SUM),
+                    TokenType::Minus => ("-", SUM),
+                    TokenType::Star => ("*", PRODUCT),
+                    TokenType::Slash => ("/", PRODUCT),
+                    TokenType::MOD => ("%", PRODUCT),
+                    TokenType::DIV => ("//", PRODUCT),
+                    _ => (op.lexeme.as_str(), COMPARISON)
+                };
+                // comparisons chain in Python, so one inside another always gets brackets
+                let left_min = if prec == COMPARISON { prec + 1 } else { prec };
+                let left = paren(self.expr(left), left_min);
+                let right = paren(self.expr(right), prec + 1);
+                (format!("{} {} {}", left, text, right), prec)
+            },
+            Expr::Grouping(inner) => match self.expr(inner) {
+                (text, ATOM) => (text, ATOM),
+                (text, _) => (format!("({})", text), ATOM)
+            },
+            Expr::IdentExpr(tkn, _) => (name(&tkn.lexeme), ATOM),
+            Expr::FnCall(tkn, args) => match self.builtin(&tkn.lexeme, args) {
+                Some(builtin) => builtin,
+                None => {
+                    let call = self.call(&tkn.lexeme, args);
+                    let targets = self.byref_targets(&tkn.lexeme, args);
+                    if targets.is_empty() { return (call, ATOM) }
+                    // the function passes its BYREF arguments back with its value, so it's called on a line
+                    // of its own and the value kept for the statement that uses it
+                    let result = fresh("result", &mut self.names);
+                    self.hoisted.push(format!("{}, {} = {}", result, targets.join(", "), call));
+                    (result, ATOM)
+                }
+            },
+            Expr::Literal(lit) => (match lit {
+                Literal::TRUE => "True".into(),
+                Literal::FALSE => "False".into(),
+                Literal::Int(n) => n.to_string(),
+                Literal::Float(n) => format!("{:?}", n),
+                Literal::Char(c) => string_literal(&c.to_string(), '\''),
+                Literal::String(s) => string_literal(s, '"'),
+                Literal::Type(_) => self.type_hint(expr),
+                lit => lit.to_string()
+            }, if matches!(lit, Literal::Int(n) if *n < 0) || matches!(lit, Literal::Float(n) if *n < 0.0) { NEGATION } else { ATOM }),
+            Expr::ArrIdx(tkn, idx1, idx2, _) => (self.index(&tkn.lexeme, idx1, idx2.as_deref()), ATOM),
+            Expr::ArrType(..) => (self.type_hint(expr), ATOM),
+        }
+    }
+}
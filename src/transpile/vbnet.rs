@@ -0,0 +1,510 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::env::Type;
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::{Literal, Token, TokenType};
+use super::{AND, ATOM, COMPARISON, CONCAT, Exprs, NEGATION, NOT, OR, PRODUCT, SUM, Scopes, Signature, Writer,
+    int_literal, paren, signatures, type_expr};
+
+pub fn transpile(prog: &Stmt) -> String {
+    let mut vb = VbNet {
+        out: Writer::new("    "),
+        sigs: signatures(prog),
+        scopes: Scopes::new(prog),
+        func: None,
+        shown: BTreeSet::new()
+    };
+    let globals = vb.scopes.globals(prog);
+    vb.out.line("' Transpiled from pseudocode by camps");
+    vb.out.blank();
+    vb.out.line("Module Program");
+    vb.out.depth += 1;
+    if let Stmt::Block(stmts) = prog {
+        vb.fields(&globals);
+        for stmt in stmts {
+            if matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..)) { vb.stmt(stmt) }
+        }
+        vb.out.line("Sub Main()");
+        vb.out.depth += 1;
+        for stmt in stmts {
+            if !matches!(stmt, Stmt::Procedure(..) | Stmt::Function(..) | Stmt::Declare(..) | Stmt::Constant(..)) { vb.stmt(stmt) }
+        }
+        vb.out.depth -= 1;
+        vb.out.line("End Sub");
+        vb.show_functions();
+    }
+    vb.out.depth -= 1;
+    vb.out.line("End Module");
+    vb.out.finish()
+}
+
+// names that can't be used as variables, or that the generated code relies on; VB.NET
+// ignores case, so these are in lowercase
+const RESERVED: &[&str] = &[
+    "addhandler", "addressof", "alias", "and", "andalso", "as", "boolean", "byref", "byte", "byval", "call", "case",
+    "catch", "cbool", "cbyte", "cchar", "cdate", "cdbl", "cdec", "char", "cint", "class", "clng", "cobj", "const",
+    "continue", "csbyte", "cshort", "csng", "cstr", "ctype", "cuint", "culng", "cushort", "date", "decimal", "declare",
+    "default", "delegate", "dim", "directcast", "do", "double", "each", "else", "elseif", "end", "endif", "enum",
+    "erase", "error", "event", "exit", "false", "finally", "for", "friend", "function", "get", "gettype",
+    "getxmlnamespace", "global", "gosub", "goto", "handles", "if", "implements", "imports", "in", "inherits",
+    "integer", "interface", "is", "isnot", "let", "lib", "like", "long", "loop", "me", "mod", "module",
+    "mustinherit", "mustoverride", "mybase", "myclass", "namespace", "narrowing", "new", "next", "not", "nothing",
+    "notinheritable", "notoverridable", "object", "of", "on", "operator", "option", "optional", "or", "orelse",
+    "overloads", "overridable", "overrides", "paramarray", "partial", "private", "property", "protected", "public",
+    "raiseevent", "readonly", "redim", "rem", "removehandler", "resume", "return", "sbyte", "select", "set",
+    "shadows", "shared", "short", "single", "static", "step", "stop", "string", "structure", "sub", "synclock",
+    "then", "throw", "to", "true", "try", "trycast", "typeof", "uinteger", "ulong", "ushort", "using", "variant",
+    "wend", "when", "while", "widening", "with", "withevents", "writeonly", "xor",
+    "main", "program", "console", "math", "len", "ucase", "lcase", "left", "right", "mid", "int", "asc", "chr", "rnd", "vblf", "show"
+];
+
+struct VbNet {
+    out: Writer,
+    sigs: HashMap<String, Signature>,
+    scopes: Scopes,
+    func: Option<bool>, // whether the procedure or function being transpiled returns a value
+    shown: BTreeSet<(String, bool)> // the types of the arrays that are output and whether they're 2D, which each need a function to show them
+}
+
+fn name(name: &str) -> String {
+    if RESERVED.contains(&name.to_lowercase().as_str()) { format!("{}_", name) } else { name.to_string() }
+}
+
+fn type_name(dtype: &Expr) -> String {
+    match dtype {
+        Expr::Literal(Literal::Type(dtype)) => match dtype {
+            Type::Int => "Integer",
+            Type::Float => "Double",
+            Type::Bool => "Boolean",
+            Type::Char => "Char",
+            Type::String => "String",
+            Type::Date => "Date",
+            Type::Array(..) | Type::Type => "Object"
+        }.into(),
+        Expr::ArrType(_, idx2, inner) => format!("{}({})", type_name(inner), if idx2.is_some() { "," } else { "" }),
+        _ => "Object".into()
+    }
+}
+
+// VB.NET strings have no escapes: quotes are doubled and line breaks joined on
+fn string_literal(val: &str) -> (String, u8) {
+    let lines: Vec<String> = val.split('\n').map(|line| format!("\"{}\"", line.replace('"', "\"\""))).collect();
+    let prec = if lines.len() > 1 { CONCAT } else { ATOM };
+    (lines.join(" & vbLf & "), prec)
+}
+
+// arrays starting at 0 or 1 keep their indices, the element at 0 going unused for 1, and
+// any others are shifted to start at 0
+fn keeps_indices(lower: &Expr) -> bool {
+    matches!(int_literal(lower), Some(0 | 1))
+}
+
+impl VbNet {
+    // the statements in the main program's block that declare a variable become module-level
+    // variables, so procedures and functions can use them
+    fn fields(&mut self, globals: &[&Stmt]) {
+        for &stmt in globals {
+            let tkn = match stmt {
+                Stmt::Declare(tkn, _) | Stmt::Constant(tkn, _) | Stmt::Assign(Expr::IdentExpr(tkn, _), _)
+                    | Stmt::Input(Expr::IdentExpr(tkn, _)) => tkn,
+                _ => continue
+            };
+            let dtype = self.scopes.globals[&tkn.lexeme].clone();
+            match stmt {
+                Stmt::Constant(_, val) => {
+                    let val = self.expr(val).0;
+                    self.out.line(format!("Const {} As {} = {}", name(&tkn.lexeme), type_name(&dtype), val))
+                },
+                _ => {
+                    let decl = self.dim(&tkn.lexeme, &dtype);
+                    self.out.line(decl)
+                }
+            }
+        }
+        self.out.blank();
+    }
+
+    fn dim(&mut self, var: &str, dtype: &Expr) -> String {
+        match dtype {
+            Expr::ArrType((start1, end1), idx2, inner) => {
+                let mut bounds = self.upper_bound(start1, end1);
+                if let Some((start2, end2)) = idx2 { bounds = format!("{}, {}", bounds, self.upper_bound(start2, end2)) }
+                format!("Dim {}({}) As {}", name(var), bounds, type_name(inner))
+            },
+            _ => format!("Dim {} As {}", name(var), type_name(dtype))
+        }
+    }
+    fn upper_bound(&mut self, start: &Expr, end: &Expr) -> String {
+        if keeps_indices(start) { self.expr(end).0 } else { self.zero_based(end, start) }
+    }
+    fn index(&mut self, arr: &str, idx1: &Expr, idx2: Option<&Expr>) -> String {
+        let one = Expr::Literal(Literal::Int(1));
+        let (lower1, lower2) = match self.scopes.get(arr) {
+            Some(Expr::ArrType((start1, _), idx2, _)) => ((**start1).clone(), idx2.as_ref().map_or(one.clone(), |idx2| (*idx2.0).clone())),
+            _ => (one.clone(), one)
+        };
+        let mut indices = vec![self.shifted(idx1, &lower1)];
+        if let Some(idx2) = idx2 { indices.push(self.shifted(idx2, &lower2)) }
+        format!("{}({})", name(arr), indices.join(", "))
+    }
+    fn shifted(&mut self, idx: &Expr, lower: &Expr) -> String {
+        if keeps_indices(lower) { self.expr(idx).0 } else { self.zero_based(idx, lower) }
+    }
+    fn target(&mut self, target: &Expr) -> String {
+        match target {
+            Expr::IdentExpr(tkn, _) => name(&tkn.lexeme),
+            Expr::ArrIdx(tkn, idx1, idx2, _) => self.index(&tkn.lexeme, idx1, idx2.as_deref()),
+            _ => self.expr(target).0
+        }
+    }
+
+    // a value to OUTPUT, written so it's shown the way the interpreter shows it
+    fn show(&mut self, expr: &Expr) -> (String, u8) {
+        if let Expr::IdentExpr(tkn, _) = expr {
+            if let Some(Expr::ArrType((start1, _), idx2, inner)) = self.scopes.get(&tkn.lexeme).cloned() {
+                // the element at 0 of an array starting at 1 goes unused, so it's skipped
+                let first = |start: &Expr| if int_literal(start) == Some(1) { "1" } else { "0" };
+                let mut firsts = vec![first(&start1)];
+                if let Some((start2, _)) = &idx2 { firsts.push(first(start2)) }
+                self.shown.insert((type_name(&inner), idx2.is_some()));
+                return (format!("Show({}, {})", name(&tkn.lexeme), firsts.join(", ")), ATOM)
+            }
+        }
+        match expr {
+            Expr::Literal(Literal::TRUE) => ("\"TRUE\"".into(), ATOM),
+            Expr::Literal(Literal::FALSE) => ("\"FALSE\"".into(), ATOM),
+            _ if self.scopes.type_of(expr) == Some(Type::Bool) => (format!("If({}, \"TRUE\", \"FALSE\")", self.expr(expr).0), ATOM),
+            _ => self.expr(expr)
+        }
+    }
+    // the functions that show each type of array that's output, like the interpreter does
+    fn show_functions(&mut self) {
+        for (dtype, rows) in std::mem::take(&mut self.shown) {
+            let value = if rows { "values(i, j)" } else { "values(i)" };
+            let value = match dtype.as_str() {
+                "Boolean" => format!("If({}, \"TRUE\", \"FALSE\")", value),
+                "Char" => format!("\"'\" & {} & \"'\"", value),
+                "String" => format!("\"\"\"\" & {} & \"\"\"\"", value),
+                _ => value.to_string()
+            };
+            self.out.blank();
+            match rows {
+                true => {
+                    self.out.line(format!("Function Show(values As {}(,), first1 As Integer, first2 As Integer) As String", dtype));
+                    self.out.depth += 1;
+                    self.out.line("Dim text As String = \"[ \"");
+                    self.out.line("For i As Integer = first1 To UBound(values, 1)");
+                    self.out.depth += 1;
+                    self.out.line("text &= \"[ \"");
+                    self.out.line("For j As Integer = first2 To UBound(values, 2)");
+                    self.out.depth += 1;
+                    self.out.line(format!("text &= {} & \" \"", value));
+                    self.out.depth -= 1;
+                    self.out.line("Next");
+                    self.out.line("text &= \"] \"");
+                },
+                false => {
+                    self.out.line(format!("Function Show(values As {}(), first As Integer) As String", dtype));
+                    self.out.depth += 1;
+                    self.out.line("Dim text As String = \"[ \"");
+                    self.out.line("For i As Integer = first To UBound(values)");
+                    self.out.depth += 1;
+                    self.out.line(format!("text &= {} & \" \"", value));
+                }
+            }
+            self.out.depth -= 1;
+            self.out.line("Next");
+            self.out.line("Return text & \"]\"");
+            self.out.depth -= 1;
+            self.out.line("End Function");
+        }
+    }
+
+    fn body(&mut self, stmt: &Stmt) {
+        self.out.depth += 1;
+        self.scopes.push();
+        self.stmt(stmt);
+        self.scopes.pop();
+        self.out.depth -= 1;
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts { self.stmt(stmt) },
+            Stmt::ExprStmt(expr) => {
+                let expr = self.expr(expr).0;
+                self.out.line(expr)
+            },
+            Stmt::Declare(tkn, dtype) => {
+                self.scopes.declare(&tkn.lexeme, dtype.clone());
+                let decl = self.dim(&tkn.lexeme, dtype);
+                self.out.line(decl)
+            },
+            Stmt::Constant(tkn, val) => {
+                let dtype = self.scopes.infer(val);
+                let val = self.expr(val).0;
+                self.out.line(format!("Const {} As {} = {}", name(&tkn.lexeme), type_name(&dtype), val));
+                self.scopes.declare(&tkn.lexeme, dtype);
+            },
+            Stmt::Assign(target, val) => {
+                let (target_text, val_text) = (self.target(target), self.expr(val).0);
+                match target {
+                    // the first value given to a variable declares it
+                    Expr::IdentExpr(tkn, _) if self.scopes.get(&tkn.lexeme).is_none() => {
+                        let dtype = self.scopes.infer(val);
+                        self.out.line(format!("Dim {} As {} = {}", target_text, type_name(&dtype), val_text));
+                        self.scopes.declare(&tkn.lexeme, dtype);
+                    },
+                    _ => self.out.line(format!("{} = {}", target_text, val_text))
+                }
+            },
+            Stmt::ProcCall(tkn, args) => {
+                let call = self.call(&tkn.lexeme, args);
+                self.out.line(call)
+            },
+            Stmt::Input(target) => {
+                let line = match self.scopes.type_of(target) {
+                    Some(Type::Int) => "CInt(Console.ReadLine())",
+                    Some(Type::Float) => "CDbl(Console.ReadLine())",
+                    Some(Type::Bool) => "Console.ReadLine() = \"TRUE\"",
+                    Some(Type::Char) => "CChar(Console.ReadLine())",
+                    _ => "Console.ReadLine()"
+                };
+                let target_text = self.target(target);
+                match target {
+                    Expr::IdentExpr(tkn, _) if self.scopes.get(&tkn.lexeme).is_none() => {
+                        self.out.line(format!("Dim {} As String = {}", target_text, line));
+                        self.scopes.declare(&tkn.lexeme, type_expr(Type::String));
+                    },
+                    _ => self.out.line(format!("{} = {}", target_text, line))
+                }
+            },
+            Stmt::Output(_, exprs) => {
+                let parts: Vec<String> = exprs.iter().map(|expr| {
+                    let shown = self.show(expr);
+                    paren(shown, CONCAT)
+                }).collect();
+                self.out.line(format!("Console.WriteLine({})", parts.join(" & ")))
+            },
+            Stmt::Ret(_, val) => match self.func {
+                Some(true) => {
+                    let val = self.expr(val).0;
+                    self.out.line(format!("Return {}", val))
+                },
+                _ => self.out.line("Return")
+            },
+            Stmt::Procedure(tkn, params, block) => self.module(tkn, params, None, block),
+            Stmt::Function(tkn, params, ret_type, block) => self.module(tkn, params, Some(ret_type), block),
+            Stmt::ForTo(counter, start, end, step, block) => {
+                // a counter that's already a local variable is reused, as it can't be declared twice
+                let reuse = self.scopes.is_local(&counter.lexeme);
+                let dtype = self.scopes.infer(start);
+                let mut header = match reuse {
+                    true => format!("For {} = {} To {}", name(&counter.lexeme), self.expr(start).0, self.expr(end).0),
+                    false => format!("For {} As {} = {} To {}", name(&counter.lexeme), type_name(&dtype), self.expr(start).0, self.expr(end).0)
+                };
+                if let Some(step) = step {
+                    if int_literal(step) != Some(1) { header.push_str(&format!(" Step {}", self.expr(step).0)) }
+                }
+                self.out.line(header);
+                self.scopes.push();
+                if !reuse { self.scopes.declare(&counter.lexeme, dtype) }
+                self.body(block);
+                self.scopes.pop();
+                self.out.line("Next")
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let cond = self.expr(cond).0;
+                self.out.line(format!("If {} Then", cond));
+                self.body(then_block);
+                let mut else_block = else_block.as_deref();
+                while let Some(block) = else_block {
+                    // ELSE followed by just another IF is written as ElseIf
+                    match block {
+                        Stmt::Block(stmts) if matches!(stmts.as_slice(), [Stmt::IfThen(..)]) => {
+                            if let Stmt::IfThen(_, cond, then_block, next) = &stmts[0] {
+                                let cond = self.expr(cond).0;
+                                self.out.line(format!("ElseIf {} Then", cond));
+                                self.body(then_block);
+                                else_block = next.as_deref();
+                            }
+                        },
+                        _ => {
+                            self.out.line("Else");
+                            self.body(block);
+                            else_block = None;
+                        }
+                    }
+                }
+                self.out.line("End If")
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                let selector = self.expr(val).0;
+                self.out.line(format!("Select Case {}", selector));
+                self.out.depth += 1;
+                for (vals, stmt) in cases {
+                    let vals: Vec<String> = vals.iter().map(|(from, to)| match to {
+                        Some(to) => format!("{} To {}", self.expr(from).0, self.expr(to).0),
+                        None => self.expr(from).0
+                    }).collect();
+                    self.out.line(format!("Case {}", vals.join(", ")));
+                    self.body(stmt);
+                }
+                if let Some(stmt) = otherwise {
+                    self.out.line("Case Else");
+                    self.body(stmt);
+                }
+                self.out.depth -= 1;
+                self.out.line("End Select")
+            },
+            Stmt::Repeat(_, cond, block) => {
+                self.out.line("Do");
+                self.out.depth += 1;
+                self.scopes.push();
+                self.stmt(block);
+                let cond = self.expr(cond).0;
+                self.scopes.pop();
+                self.out.depth -= 1;
+                self.out.line(format!("Loop Until {}", cond))
+            },
+            Stmt::WhileDo(_, cond, block) => {
+                let cond = self.expr(cond).0;
+                self.out.line(format!("Do While {}", cond));
+                self.body(block);
+                self.out.line("Loop")
+            },
+        }
+    }
+
+    fn module(&mut self, tkn: &Token, params: &[(Token, Expr, bool)], ret_type: Option<&Expr>, block: &Stmt) {
+        self.scopes.push();
+        let mut param_list = Vec::new();
+        let mut copies = Vec::new();
+        for (param, dtype, byref) in params {
+            self.scopes.declare(&param.lexeme, dtype.clone());
+            let pass = if *byref { "ByRef" } else { "ByVal" };
+            match dtype {
+                Expr::ArrType(_, idx2, inner) => {
+                    let dims = if idx2.is_some() { "(,)" } else { "()" };
+                    param_list.push(format!("{} {}{} As {}", pass, name(&param.lexeme), dims, type_name(inner)));
+                    // arrays are always passed by reference, so a BYVALUE array is copied
+                    if !byref { copies.push(format!("{0} = CType({0}.Clone(), {1})", name(&param.lexeme), type_name(dtype))) }
+                },
+                _ => param_list.push(format!("{} {} As {}", pass, name(&param.lexeme), type_name(dtype)))
+            }
+        }
+        let (keyword, ret) = match ret_type {
+            Some(ret_type) => ("Function", format!(" As {}", type_name(ret_type))),
+            None => ("Sub", String::new())
+        };
+
+        self.out.line(format!("{} {}({}){}", keyword, name(&tkn.lexeme), param_list.join(", "), ret));
+        self.out.depth += 1;
+        for copy in copies { self.out.line(copy) }
+        self.func = Some(ret_type.is_some());
+        self.stmt(block);
+        self.func = None;
+        self.out.depth -= 1;
+        self.out.line(format!("End {}", keyword));
+        self.out.blank();
+        self.scopes.pop();
+    }
+
+    fn call(&mut self, callee: &str, args: &[Expr]) -> String {
+        let args: Vec<String> = args.iter().map(|arg| self.expr(arg).0).collect();
+        format!("{}({})", name(callee), args.join(", "))
+    }
+    // the pseudocode library functions, unless the program defines its own with the same name
+    fn builtin(&mut self, callee: &str, args: &[Expr]) -> Option<(String, u8)> {
+        if self.sigs.contains_key(callee) { return None }
+        let arg = |vb: &mut VbNet, idx: usize, min: u8| paren(vb.expr(&args[idx]), min);
+        let function = |vb: &mut VbNet, function: &str| {
+            let args: Vec<String> = args.iter().map(|arg| vb.expr(arg).0).collect();
+            (format!("{}({})", function, args.join(", ")), ATOM)
+        };
+        Some(match (callee, args.len()) {
+            ("LENGTH", 1) => function(self, "Len"),
+            ("UCASE" | "TO_UPPER", 1) => function(self, "UCase"),
+            ("LCASE" | "TO_LOWER", 1) => function(self, "LCase"),
+            ("LEFT", 2) => function(self, "Left"),
+            ("RIGHT", 2) => function(self, "Right"),
+            ("MID" | "SUBSTRING", 3) => function(self, "Mid"),
+            ("INT", 1) => function(self, "Int"),
+            ("NUM_TO_STR", 1) => function(self, "CStr"),
+            ("STR_TO_NUM", 1) => function(self, "CDbl"),
+            ("ROUND", 2) => function(self, "Math.Round"),
+            ("ASC", 1) => function(self, "Asc"),
+            ("CHR", 1) => function(self, "Chr"),
+            ("MOD", 2) => (format!("{} Mod {}", arg(self, 0, PRODUCT), arg(self, 1, PRODUCT)), SUM),
+            ("DIV", 2) => (format!("{} \\ {}", arg(self, 0, PRODUCT), arg(self, 1, PRODUCT)), SUM),
+            ("RAND", 1) => (format!("Rnd() * {}", arg(self, 0, NEGATION)), PRODUCT),
+            ("RANDOMBETWEEN", 2) => {
+                let (low, high) = (arg(self, 0, SUM), arg(self, 1, PRODUCT));
+                (format!("{0} + CInt(Int(Rnd() * ({1} - {0} + 1)))", low, high), SUM)
+            },
+            _ => return None
+        })
+    }
+}
+
+impl Exprs for VbNet {
+    fn expr(&mut self, expr: &Expr) -> (String, u8) {
+        match expr {
+            Expr::Unary(op, right) => match op.ttype {
+                TokenType::NOT => (format!("Not {}", paren(self.expr(right), NOT)), NOT),
+                _ => (format!("-{}", paren(self.expr(right), NEGATION)), NEGATION)
+            },
+            Expr::Binary(left, op, right) => {
+                let strings = self.scopes.type_of(expr) == Some(Type::String);
+                let (text, prec) = match op.ttype {
+                    TokenType::OR => ("Or", OR),
+                    TokenType::AND => ("And", AND),
+                    TokenType::Equal => ("=", COMPARISON),
+                    TokenType::NotEqual => ("<>", COMPARISON),
+                    TokenType::Less => ("<", COMPARISON),
+                    TokenType::Greater => (">", COMPARISON),
+                    TokenType::LessEqual => ("<=", COMPARISON),
+                    TokenType::GreaterEqual => (">=", COMPARISON),
+                    TokenType::Plus if strings => ("&", CONCAT),
+                    TokenType::Plus => ("+", SUM),
+                    TokenType::Minus => ("-", SUM),
+                    TokenType::Star => ("*", PRODUCT),
+                    TokenType::Slash => ("/", PRODUCT),
+                    // Mod and \ come between + and * in VB.NET, so anything but a single value
+                    // next to them gets brackets
+                    TokenType::MOD | TokenType::DIV => {
+                        let (left, right) = (paren(self.expr(left), PRODUCT), paren(self.expr(right), PRODUCT));
+                        let op = if op.ttype == TokenType::MOD { "Mod" } else { "\\" };
+                        return (format!("{} {} {}", left, op, right), SUM)
+                    },
+                    _ => (op.lexeme.as_str(), COMPARISON)
+                };
+                let left_min = if prec == COMPARISON { prec + 1 } else { prec };
+                let left = paren(self.expr(left), left_min);
+                let right = paren(self.expr(right), prec + 1);
+                (format!("{} {} {}", left, text, right), prec)
+            },
+            Expr::Grouping(inner) => match self.expr(inner) {
+                (text, ATOM) => (text, ATOM),
+                (text, _) => (format!("({})", text), ATOM)
+            },
+            Expr::IdentExpr(tkn, _) => (name(&tkn.lexeme), ATOM),
+            Expr::FnCall(tkn, args) => match self.builtin(&tkn.lexeme, args) {
+                Some(builtin) => builtin,
+                None => (self.call(&tkn.lexeme, args), ATOM)
+            },
+            Expr::Literal(lit) => match lit {
+                Literal::TRUE => ("True".into(), ATOM),
+                Literal::FALSE => ("False".into(), ATOM),
+                Literal::Int(n) => (n.to_string(), if *n < 0 { NEGATION } else { ATOM }),
+                Literal::Float(n) => (format!("{:?}", n), if *n < 0.0 { NEGATION } else { ATOM }),
+                Literal::Char(c) => (format!("{}c", string_literal(&c.to_string()).0), ATOM),
+                Literal::String(s) => string_literal(s),
+                Literal::Type(_) => (type_name(expr), ATOM),
+                lit => (lit.to_string(), ATOM)
+            },
+            Expr::ArrIdx(tkn, idx1, idx2, _) => (self.index(&tkn.lexeme, idx1, idx2.as_deref()), ATOM),
+            Expr::ArrType(..) => (type_name(expr), ATOM),
+        }
+    }
+}
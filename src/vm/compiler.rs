@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use crate::env::Type;
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::{Literal, Token, TokenType};
+use super::{Arg, Def, Op, Program, Var};
+
+/// Turns a resolved program into bytecode. The main program comes first and ends with `Halt`,
+/// followed by the body of each procedure and function.
+pub fn compile(prog: &Stmt) -> Program {
+    let mut compiler = Compiler {
+        code: Vec::new(),
+        steps: Vec::new(),
+        pending_steps: 0,
+        consts: Vec::new(),
+        names: Vec::new(),
+        name_ids: HashMap::new(),
+        messages: Vec::new(),
+        defs: Vec::new(),
+        params: Vec::new(),
+        def_ids: HashMap::new(),
+        globals: HashMap::new(),
+        scopes: Vec::new(),
+        locals: 0,
+        next_def: 0
+    };
+    // calls can come before the definition they call, so find every definition first
+    let mut modules = Vec::new();
+    collect_modules(prog, &mut modules);
+    for module in &modules {
+        let (name, params, func) = match module {
+            Stmt::Procedure(name, params, _) => (name, params, false),
+            Stmt::Function(name, params, _, _) => (name, params, true),
+            _ => unreachable!()
+        };
+        let def = Def {
+            name: compiler.name(&name.lexeme),
+            func,
+            line: name.line,
+            entry: 0,
+            locals: 0,
+            byref: params.iter().map(|param| param.2).collect()
+        };
+        compiler.def_ids.insert((name.lexeme.clone(), func), compiler.defs.len());
+        compiler.defs.push(def);
+        compiler.params.push(params.iter().map(|param| param.0.lexeme.clone()).collect());
+    }
+
+    compiler.stmt(prog);
+    compiler.emit(Op::Halt);
+    let main_locals = compiler.locals;
+
+    for (idx, module) in modules.iter().enumerate() {
+        let (params, block, func) = match module {
+            Stmt::Procedure(_, params, block) => (params, block, false),
+            Stmt::Function(_, params, _, block) => (params, block, true),
+            _ => unreachable!()
+        };
+        // the parameters take the first slots, in order
+        compiler.locals = 0;
+        let mut scope = HashMap::new();
+        for param in params.iter() {
+            scope.insert(param.0.lexeme.clone(), compiler.locals);
+            compiler.locals += 1;
+        }
+        compiler.scopes.push(scope);
+        compiler.defs[idx].entry = compiler.here();
+        compiler.stmt(block);
+        compiler.emit(Op::EndBody(func));
+        compiler.scopes.pop();
+        compiler.defs[idx].locals = compiler.locals as usize;
+    }
+
+    Program {
+        code: compiler.code,
+        steps: compiler.steps,
+        consts: compiler.consts,
+        names: compiler.names,
+        messages: compiler.messages,
+        defs: compiler.defs,
+        globals: compiler.globals.len(),
+        main_locals: main_locals as usize
+    }
+}
+
+// every procedure and function definition, in the order they appear
+fn collect_modules<'a>(stmt: &'a Stmt, modules: &mut Vec<&'a Stmt>) {
+    match stmt {
+        Stmt::Procedure(_, _, _) | Stmt::Function(_, _, _, _) => modules.push(stmt),
+        Stmt::Block(stmts) => for stmt in stmts { collect_modules(stmt, modules) },
+        Stmt::Case(_, _, cases, otherwise) => {
+            for (_, stmt) in cases { collect_modules(stmt, modules) }
+            if let Some(stmt) = otherwise { collect_modules(stmt, modules) }
+        },
+        _ => {} // anywhere else is a scope error
+    }
+}
+
+// the variable and amount in a variable plus or minus an integer; subtracting 0 is left alone, as
+// it can't be told apart from adding it
+fn offset(expr: &Expr) -> Option<(&Token, Option<usize>, i32)> {
+    match expr {
+        Expr::Binary(left, op, right) => match (&**left, &op.ttype, &**right) {
+            (Expr::IdentExpr(name, depth), TokenType::Plus, Expr::Literal(Literal::Int(n))) => Some((name, *depth, *n)),
+            (Expr::IdentExpr(name, depth), TokenType::Minus, Expr::Literal(Literal::Int(n))) if *n > 0 => Some((name, *depth, -n)),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// the variable and offset in an array index that's read by the op using the element: a
+// variable on its own has an offset of 0, so adding 0 is left to be worked out
+fn index(expr: &Expr) -> Option<(&Token, Option<usize>, i32)> {
+    match expr {
+        Expr::IdentExpr(name, depth) => Some((name, *depth, 0)),
+        expr => offset(expr).filter(|(_, _, offset)| *offset != 0)
+    }
+}
+
+struct Compiler {
+    code: Vec<Op>,
+    steps: Vec<u32>, // how many statements start with each op
+    pending_steps: u32, // statements started that haven't emitted an op yet
+    consts: Vec<Literal>,
+    names: Vec<String>,
+    name_ids: HashMap<String, u32>,
+    messages: Vec<String>,
+    defs: Vec<Def>,
+    params: Vec<Vec<String>>, // parameter names of each definition
+    def_ids: HashMap<(String, bool), usize>, // by name and whether it's a function
+    globals: HashMap<String, u32>,
+    scopes: Vec<HashMap<String, u32>>, // local scopes like the resolver's, with each variable's slot
+    locals: u32, // slots used so far by the main program or the procedure/function being compiled
+    next_def: u32
+}
+
+impl Compiler {
+    fn emit(&mut self, op: Op) -> usize {
+        self.code.push(op);
+        self.steps.push(std::mem::take(&mut self.pending_steps));
+        self.code.len() - 1
+    }
+    // where the next op will go, for a jump to it. A statement that has started there is counted
+    // before it, so going back to it doesn't count the statement again
+    fn here(&mut self) -> usize {
+        if self.pending_steps > 0 { self.emit(Op::Step); }
+        self.code.len()
+    }
+    // points the jump at `at` to the next op
+    fn patch(&mut self, at: usize) {
+        let here = self.here();
+        match &mut self.code[at] {
+            Op::Jump(target) | Op::JumpIf(_, _, target, _) | Op::JumpCompare(_, _, _, _, target) => *target = here,
+            op => unreachable!("{:?} is not a jump", op)
+        }
+    }
+    fn constant(&mut self, lit: Literal) {
+        self.consts.push(lit);
+        self.emit(Op::Const(self.consts.len() as u32 - 1));
+    }
+    fn error(&mut self, msg: String) {
+        self.messages.push(msg);
+        self.emit(Op::Error(self.messages.len() as u32 - 1));
+    }
+    fn name(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.name_ids.get(name) { return *id }
+        self.names.push(name.to_string());
+        self.name_ids.insert(name.to_string(), self.names.len() as u32 - 1);
+        self.names.len() as u32 - 1
+    }
+
+    // a slot only the compiler knows about
+    fn hidden(&mut self) -> u32 {
+        self.locals += 1;
+        self.locals - 1
+    }
+    fn local(&mut self, scope: usize, name: &str) -> Var {
+        let slot = match self.scopes[scope].get(name) {
+            Some(slot) => *slot,
+            None => {
+                let slot = self.hidden();
+                self.scopes[scope].insert(name.to_string(), slot);
+                slot
+            }
+        };
+        Var { slot, name: self.name(name), global: false }
+    }
+    fn global(&mut self, name: &str) -> Var {
+        let next = self.globals.len() as u32;
+        let slot = *self.globals.entry(name.to_string()).or_insert(next);
+        Var { slot, name: self.name(name), global: true }
+    }
+    // the variable the resolver found `depth` scopes out
+    fn var(&mut self, name: &Token, depth: Option<usize>) -> Var {
+        match depth {
+            Some(depth) => self.local(self.scopes.len() - 1 - depth, &name.lexeme),
+            None => self.global(&name.lexeme)
+        }
+    }
+    // the variable a DECLARE or CONSTANT creates, in the innermost scope
+    fn new_var(&mut self, name: &Token) -> Var {
+        match self.scopes.len() {
+            0 => self.global(&name.lexeme),
+            len => self.local(len - 1, &name.lexeme)
+        }
+    }
+    // the variables in a scope are cleared when it ends, so they start off undefined the next time it runs
+    fn end_scope(&mut self) {
+        let mut slots: Vec<u32> = self.scopes.pop().unwrap().into_values().collect();
+        slots.sort();
+        for slot in slots { self.emit(Op::Clear(slot)); }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        if !matches!(stmt, Stmt::Block(_)) { self.pending_steps += 1 }
+        match stmt {
+            Stmt::Block(stmts) => for stmt in stmts { self.stmt(stmt) },
+            Stmt::ExprStmt(expr) => {
+                self.expr(expr);
+                self.emit(Op::Pop);
+            },
+            Stmt::Declare(name, dtype) => {
+                self.expr(dtype);
+                let var = self.new_var(name);
+                self.emit(Op::Declare(var));
+            },
+            Stmt::Constant(name, val) => {
+                self.expr(val);
+                let var = self.new_var(name);
+                self.emit(Op::DefConst(var));
+            },
+            Stmt::Assign(target, val) => match target {
+                Expr::IdentExpr(name, depth) => {
+                    let val = self.arg(val);
+                    let var = self.var(name, *depth);
+                    self.emit(Op::Store(var, val));
+                },
+                Expr::ArrIdx(name, idx1, _, depth) => {
+                    let (val, idx) = self.operands(val, idx1);
+                    let var = self.var(name, *depth);
+                    self.emit(Op::StoreIdx(var, idx, val));
+                },
+                _ => {
+                    self.expr(val);
+                    self.error("expected identifier".into())
+                }
+            },
+            Stmt::ProcCall(name, args) => self.call(name, args, false),
+            Stmt::Input(target) => match target {
+                Expr::IdentExpr(name, depth) => {
+                    let var = self.var(name, *depth);
+                    self.emit(Op::Input(var));
+                },
+                _ => self.error("expected identifier".into())
+            },
+            Stmt::Output(_, exprs) => {
+                for expr in exprs {
+                    self.expr(expr);
+                    self.emit(Op::Print);
+                }
+                self.emit(Op::PrintLn);
+            },
+            Stmt::Ret(_, val) => {
+                self.expr(val);
+                self.emit(Op::Return);
+            },
+            Stmt::Procedure(_, params, _) => {
+                for param in params { self.expr(&param.1) }
+                self.emit(Op::Define(self.next_def));
+                self.next_def += 1;
+            },
+            Stmt::Function(_, params, ret_type, _) => {
+                for param in params { self.expr(&param.1) }
+                self.expr(ret_type);
+                self.emit(Op::Define(self.next_def));
+                self.next_def += 1;
+            },
+            Stmt::ForTo(name, val1, val2, step, block) => {
+                self.expr(val1);
+                self.expr(val2);
+                self.emit(Op::CheckInt("expected integer expression1"));
+                let end = self.hidden();
+                self.emit(Op::SetHidden(end));
+                match step {
+                    Some(step) => {
+                        self.expr(step);
+                        self.emit(Op::CheckInt("expected integer expression2"));
+                    },
+                    None => self.constant(Literal::Int(1))
+                }
+                let step = self.hidden();
+                self.emit(Op::SetHidden(step));
+
+                self.scopes.push(HashMap::new());
+                let counter = self.var(name, Some(0));
+                self.constant(Literal::Type(Type::Int));
+                self.emit(Op::Declare(counter));
+                self.emit(Op::Store(counter, Arg::Stack));
+                let body = self.here();
+                self.stmt(block);
+                self.emit(Op::ForNext { counter, end, step, body, line: name.line });
+                self.end_scope();
+            },
+            Stmt::IfThen(_, cond, then_block, else_block) => {
+                let to_else = self.jump_if(cond, false, 0, "expected boolean expression");
+                self.scopes.push(HashMap::new());
+                self.stmt(then_block);
+                self.end_scope();
+                match else_block {
+                    Some(block) => {
+                        let to_end = self.emit(Op::Jump(0));
+                        self.patch(to_else);
+                        self.scopes.push(HashMap::new());
+                        self.stmt(block);
+                        self.end_scope();
+                        self.patch(to_end);
+                    },
+                    None => self.patch(to_else)
+                }
+            },
+            Stmt::Case(_, val, cases, otherwise) => {
+                self.expr(val);
+                // all the values go on the stack so they can be checked together, then each one jumps to
+                // its clause's statements
+                let mut vals = Vec::new();
+                for (clause, (clause_vals, _)) in cases.iter().enumerate() {
+                    for (from, to) in clause_vals {
+                        self.expr(from);
+                        if let Some(to) = to { self.expr(to) }
+                        vals.push((to.is_some(), clause));
+                    }
+                }
+                let at = self.emit(Op::Case(vals, 0));
+                let mut to_end = Vec::new();
+                let mut starts = Vec::new();
+                for (_, stmt) in cases {
+                    starts.push(self.here());
+                    self.stmt(stmt);
+                    to_end.push(self.emit(Op::Jump(0)));
+                }
+                let otherwise_start = self.here();
+                if let Some(stmt) = otherwise { self.stmt(stmt) }
+                if let Op::Case(vals, fallback) = &mut self.code[at] {
+                    for (_, target) in vals.iter_mut() { *target = starts[*target] }
+                    *fallback = otherwise_start;
+                }
+                for at in to_end { self.patch(at) }
+            },
+            Stmt::Repeat(keyword, cond, block) => {
+                self.scopes.push(HashMap::new());
+                let start = self.here();
+                self.stmt(block);
+                self.emit(Op::CheckLimits("REPEAT", keyword.line));
+                self.jump_if(cond, false, start, "Expected boolean expression");
+                self.end_scope();
+            },
+            // the condition goes after the body, so each time round only needs the one jump back
+            Stmt::WhileDo(keyword, cond, block) => {
+                self.scopes.push(HashMap::new());
+                let to_cond = self.emit(Op::Jump(0));
+                let start = self.here();
+                self.stmt(block);
+                self.patch(to_cond);
+                self.emit(Op::CheckLimits("WHILE", keyword.line));
+                self.jump_if(cond, true, start, "Expected boolean expression");
+                self.end_scope();
+            },
+        }
+    }
+
+    fn call(&mut self, name: &Token, args: &[Expr], func: bool) {
+        let name_id = self.name(&name.lexeme);
+        self.emit(Op::CheckCall { name: name_id, argc: args.len(), func });
+        // if there's no such definition the check always fails, so there's nothing more to compile
+        if let Some(def) = self.def_ids.get(&(name.lexeme.clone(), func)).copied() {
+            let params: Vec<(String, bool)> = self.params[def].iter().cloned().zip(self.defs[def].byref.iter().copied()).collect();
+            for (arg, (param, byref)) in args.iter().zip(params) {
+                if !byref {
+                    self.expr(arg);
+                    continue
+                }
+                match arg {
+                    Expr::IdentExpr(name, depth) => {
+                        let var = self.var(name, *depth);
+                        self.emit(Op::PlaceOf(var));
+                    },
+                    Expr::ArrIdx(name, idx1, _, depth) => {
+                        let idx = self.arg(idx1);
+                        let var = self.var(name, *depth);
+                        self.emit(Op::PlaceIdx(var, idx));
+                    },
+                    _ => self.error(format!("BYREF parameter {} must be passed a variable or array element", param))
+                }
+            }
+        }
+        self.emit(Op::Call { name: name_id, func, line: name.line });
+    }
+
+    // a variable or constant on the left is only read by the op when the right one is too, so that
+    // it's still read before anything on the right runs
+    fn operands(&mut self, left: &Expr, right: &Expr) -> (Arg, Arg) {
+        let read_by_op = match right {
+            Expr::IdentExpr(..) | Expr::Literal(_) => true,
+            Expr::ArrIdx(_, idx1, _, _) => index(idx1).is_some(),
+            right => offset(right).is_some()
+        };
+        let left = if read_by_op { self.arg(left) } else {
+            self.expr(left);
+            Arg::Stack
+        };
+        (left, self.arg(right))
+    }
+    // jumps to `target` if `cond` is `when`, comparing the operands directly when it's a comparison
+    fn jump_if(&mut self, cond: &Expr, when: bool, target: usize, msg: &'static str) -> usize {
+        match cond {
+            Expr::Binary(left, op, right) if matches!(op.ttype, TokenType::Equal | TokenType::NotEqual | TokenType::Less
+                | TokenType::Greater | TokenType::LessEqual | TokenType::GreaterEqual) => {
+                let (left, right) = self.operands(left, right);
+                self.emit(Op::JumpCompare(op.ttype.clone(), left, right, when, target))
+            },
+            cond => {
+                let cond = self.arg(cond);
+                self.emit(Op::JumpIf(cond, when, target, msg))
+            }
+        }
+    }
+    // a variable, constant, offset or array element indexed by one of those is left for the op
+    // using it to read, anything else is worked out onto the stack
+    fn arg(&mut self, expr: &Expr) -> Arg {
+        match expr {
+            Expr::IdentExpr(name, depth) => Arg::Var(self.var(name, *depth)),
+            Expr::Literal(lit) => {
+                self.consts.push(lit.clone());
+                Arg::Const(self.consts.len() as u32 - 1)
+            },
+            Expr::ArrIdx(name, idx1, _, depth) => match index(idx1) {
+                Some((idx, idx_depth, offset)) => Arg::Elem(self.var(name, *depth), self.var(idx, idx_depth), offset),
+                None => {
+                    self.expr(expr);
+                    Arg::Stack
+                }
+            },
+            expr => match offset(expr) {
+                Some((name, depth, offset)) => Arg::Offset(self.var(name, depth), offset),
+                None => {
+                    self.expr(expr);
+                    Arg::Stack
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Unary(op, right) => {
+                self.expr(right);
+                self.emit(Op::Unary(op.ttype.clone()));
+            },
+            Expr::Binary(left, op, right) => match op.ttype {
+                // the right operand of AND and OR is evaluated first
+                TokenType::AND | TokenType::OR => {
+                    self.expr(right);
+                    self.expr(left);
+                    self.emit(Op::BinaryRev(op.ttype.clone()));
+                },
+                _ => {
+                    let (left, right) = self.operands(left, right);
+                    self.emit(Op::Binary(op.ttype.clone(), left, right));
+                }
+            },
+            Expr::Grouping(inner) => self.expr(inner),
+            Expr::IdentExpr(name, depth) => {
+                let var = self.var(name, *depth);
+                self.emit(Op::Load(var));
+            },
+            Expr::FnCall(name, args) => self.call(name, args, true),
+            Expr::Literal(lit) => self.constant(lit.clone()),
+            Expr::ArrIdx(name, idx1, _, depth) => {
+                let idx = self.arg(idx1);
+                let var = self.var(name, *depth);
+                self.emit(Op::LoadIdx(var, idx));
+            },
+            Expr::ArrType((start1, end1), idx2, dtype) => {
+                self.expr(start1);
+                self.expr(end1);
+                if let Some((start2, end2)) = idx2 {
+                    self.expr(start2);
+                    self.expr(end2);
+                }
+                self.expr(dtype);
+                self.emit(Op::ArrType(idx2.is_some()));
+            },
+        }
+    }
+}
@@ -0,0 +1,772 @@
+mod compiler;
+
+use std::{rc::Rc, time::Instant};
+use crate::env::{self, Decl, Frame, Limits, Observer, Type};
+use crate::interpreter::{ops, RuntimeError};
+use crate::stmt::Stmt;
+use crate::token::{Literal, TokenType};
+
+/// Compiles `prog` to bytecode and runs it. This gives the same output and errors as
+/// interpreting the syntax tree, but variables live in numbered slots instead of being
+/// looked up by name.
+pub fn run(prog: &Stmt, limits: Limits, observer: Option<Box<dyn Observer>>) -> Result<(), RuntimeError> {
+    let program = compiler::compile(prog);
+    let mut vm = Vm::new(&program, limits, observer);
+    vm.run().map_err(|err| if vm.returns.is_empty() { err } else {
+        let trace = env::call_trace(&vm.frames());
+        err.with_trace(trace)
+    })
+}
+
+// calls are kept on the heap rather than the native stack, so with no maximum depth they're
+// still stopped here, well before runaway recursion uses up all the memory
+const MAX_CALLS: usize = 1_000_000;
+
+/// A variable slot, and the name it goes by in error messages
+#[derive(Clone, Copy, Debug)]
+pub struct Var {
+    slot: u32,
+    name: u32,
+    global: bool // otherwise a local slot in the current call (or the main program's blocks)
+}
+
+/// Where an op gets one of its values from. A variable or constant can be read by the op that
+/// uses it, saving an op to push it first
+#[derive(Clone, Copy, Debug)]
+pub enum Arg {
+    Stack, // popped
+    Const(u32),
+    Var(Var),
+    Offset(Var, i32), // a variable plus or minus an integer, like the `I + 1` in `A[I + 1]`
+    Elem(Var, Var, i32) // an array element, indexed by a variable with any offset added
+}
+
+#[derive(Clone, Debug)]
+pub enum Op {
+    Step, // does nothing, for statements that have to be counted before a jump target
+    CheckLimits(&'static str, usize), // counts a step for a loop going round again, and errors if it's over the limits; loop kind and line
+    Const(u32),
+    Pop,
+    Load(Var),
+    LoadIdx(Var, Arg), // the index
+    Store(Var, Arg), // the value
+    StoreIdx(Var, Arg, Arg), // the index and the value
+    SetHidden(u32), // pops into a local slot the program can't name, like the end of a FOR loop
+    Declare(Var), // pops the type
+    DefConst(Var),
+    Input(Var),
+    Print,
+    PrintLn,
+    CheckInt(&'static str), // errors with the message if the top of the stack isn't an integer
+    Unary(TokenType),
+    Binary(TokenType, Arg, Arg), // the left operand is read first, or is below the right one on the stack
+    BinaryRev(TokenType), // right operand below the left one, for AND and OR
+    ArrType(bool), // whether there is a second dimension
+    Jump(usize),
+    JumpIf(Arg, bool, usize, &'static str), // jumps if the boolean matches, errors with the message if it isn't one
+    JumpCompare(TokenType, Arg, Arg, bool, usize), // compares two values like Binary, and jumps if the result matches
+    Case(Vec<(bool, usize)>, usize), // pops a CASE's values (both ends of those that are ranges) and the one being compared, and jumps to where the first match goes, or else to the second
+    ForNext { counter: Var, end: u32, step: u32, body: usize, line: usize }, // checks the limits like CheckLimits first
+    Clear(u32), // a local slot goes out of scope
+    Define(u32), // pops the parameter (and return) types of a procedure/function and makes it callable
+    CheckCall { name: u32, argc: usize, func: bool },
+    Call { name: u32, func: bool, line: usize },
+    PlaceOf(Var), // pushes a reference to the variable, for a BYREF argument
+    PlaceIdx(Var, Arg), // the index
+    Return, // pops the value
+    EndBody(bool), // the end of a procedure's (false) or function's (true) body
+    Error(u32),
+    Halt
+}
+
+pub struct Program {
+    code: Vec<Op>,
+    steps: Vec<u32>, // how many statements start with each op, counted towards the step limit when it runs
+    consts: Vec<Literal>,
+    names: Vec<String>,
+    messages: Vec<String>,
+    defs: Vec<Def>,
+    globals: usize,
+    main_locals: usize
+}
+
+/// A compiled procedure or function
+pub struct Def {
+    name: u32,
+    func: bool,
+    line: usize,
+    entry: usize,
+    locals: usize,
+    byref: Vec<bool>
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Place {
+    global: bool,
+    slot: usize, // for locals, counted from the bottom of the locals stack rather than the call's
+    name: u32,
+    idx: Option<usize> // an element of the array in the slot
+}
+
+#[derive(Clone, Debug)]
+enum Slot {
+    Undeclared,
+    Var(Decl, Option<Literal>),
+    Ref(Decl, Place) // a BYREF parameter
+}
+
+struct Signature {
+    params: Vec<Type>,
+    ret: Option<Type>
+}
+
+// where to carry on from when a call returns
+struct Return {
+    ip: usize,
+    base: usize,
+    def: usize,
+    line: usize // the call's
+}
+
+struct Vm<'a> {
+    prog: &'a Program,
+    ip: usize,
+    stack: Vec<Literal>,
+    places: Vec<Place>, // BYREF arguments waiting for their call
+    globals: Vec<Slot>,
+    locals: Vec<Slot>,
+    base: usize, // where the current call's locals start
+    types: Vec<Option<Rc<Signature>>>, // of each procedure/function that has been defined
+    procs: Vec<Option<usize>>, // by name, the procedure defined with that name
+    funcs: Vec<Option<usize>>,
+    returns: Vec<Return>,
+    limits: Limits,
+    steps: u64,
+    start: Instant,
+    // there's no syntax tree or environment to show an observer, so it only sees the program's output and gives it input
+    observer: Option<Box<dyn Observer>>
+}
+
+// what `ops::binary` gives for two integers, for the operators it handles the same way every time
+fn int_binary(op: &TokenType, l: i32, r: i32) -> Option<Literal> {
+    Some(match op {
+        TokenType::Plus => Literal::Int(l + r),
+        TokenType::Minus => Literal::Int(l - r),
+        TokenType::Star => Literal::Int(l * r),
+        op => if int_compare(op, l, r)? { Literal::TRUE } else { Literal::FALSE }
+    })
+}
+fn int_compare(op: &TokenType, l: i32, r: i32) -> Option<bool> {
+    Some(match op {
+        TokenType::Equal => l == r,
+        TokenType::NotEqual => l != r,
+        TokenType::Less => l < r,
+        TokenType::Greater => l > r,
+        TokenType::LessEqual => l <= r,
+        TokenType::GreaterEqual => l >= r,
+        _ => return None
+    })
+}
+
+// a copy of a value that doesn't own anything, like a number or a boolean
+fn scalar(val: &Literal) -> Option<Literal> {
+    Some(match val {
+        Literal::TRUE => Literal::TRUE,
+        Literal::FALSE => Literal::FALSE,
+        Literal::Int(n) => Literal::Int(*n),
+        Literal::Float(n) => Literal::Float(*n),
+        Literal::Char(c) => Literal::Char(*c),
+        _ => return None
+    })
+}
+// clones a value, without going through the general clone for the scalars that most are
+fn copy(val: &Literal) -> Literal {
+    scalar(val).unwrap_or_else(|| val.clone())
+}
+// whether a scalar can be stored in a variable of the type without converting it
+fn fits(val: &Literal, dtype: &Type) -> bool {
+    matches!((val, dtype), (Literal::TRUE | Literal::FALSE, Type::Bool) | (Literal::Int(_), Type::Int)
+        | (Literal::Float(_), Type::Float) | (Literal::Char(_), Type::Char))
+}
+
+fn expect_int(val: Literal, msg: &str) -> Result<i32, RuntimeError> {
+    match val {
+        Literal::Int(n) => Ok(n),
+        _ => Err(RuntimeError::new(msg.into()))
+    }
+}
+
+impl<'a> Vm<'a> {
+    fn new(prog: &'a Program, limits: Limits, observer: Option<Box<dyn Observer>>) -> Self {
+        Self {
+            prog,
+            ip: 0,
+            stack: Vec::new(),
+            places: Vec::new(),
+            globals: vec![Slot::Undeclared; prog.globals],
+            locals: vec![Slot::Undeclared; prog.main_locals],
+            base: 0,
+            types: vec![None; prog.defs.len()],
+            procs: vec![None; prog.names.len()],
+            funcs: vec![None; prog.names.len()],
+            returns: Vec::new(),
+            limits,
+            steps: 0,
+            start: Instant::now(),
+            observer
+        }
+    }
+
+    // like `Environment::write`, the observer can take the output instead of stdout
+    fn write(&mut self, text: &str) {
+        if !self.observer.as_mut().is_some_and(|observer| observer.output(text)) { print!("{}", text) }
+    }
+
+    fn pop(&mut self) -> Literal {
+        self.stack.pop().expect("empty stack")
+    }
+    fn name(&self, name: u32) -> &'a str {
+        &self.prog.names[name as usize]
+    }
+    fn slot(&mut self, var: &Var) -> &mut Slot {
+        if var.global { &mut self.globals[var.slot as usize] }
+        else { &mut self.locals[self.base + var.slot as usize] }
+    }
+
+    #[inline(always)]
+    fn slot_ref(&self, var: &Var) -> &Slot {
+        if var.global { &self.globals[var.slot as usize] }
+        else { &self.locals[self.base + var.slot as usize] }
+    }
+
+    // a variable's value, following a BYREF parameter to what it refers to, or None where `get`
+    // would give an error
+    #[inline(always)]
+    fn value(&self, var: &Var) -> Option<&Literal> {
+        let place = match self.slot_ref(var) {
+            Slot::Var(_, val) => return val.as_ref(),
+            Slot::Ref(_, place) => place,
+            Slot::Undeclared => return None
+        };
+        let val = match if place.global { &self.globals[place.slot] } else { &self.locals[place.slot] } {
+            Slot::Var(_, Some(val)) => val,
+            _ => return None
+        };
+        match (place.idx, val) {
+            (None, val) => Some(val),
+            (Some(idx), Literal::Array(arr)) => arr.get(idx),
+            _ => None
+        }
+    }
+
+    fn get_place(&mut self, place: &Place) -> Result<&mut Literal, RuntimeError> {
+        let name = self.name(place.name);
+        let slot = if place.global { &mut self.globals[place.slot] } else { &mut self.locals[place.slot] };
+        let mut val = match slot {
+            Slot::Var(_, Some(val)) => val,
+            _ => return Err(RuntimeError::new(format!("variable {} is used before it is assigned a value", name)))
+        };
+        if let Some(idx) = place.idx {
+            val = match val {
+                Literal::Array(arr) => arr.get_mut(idx)
+                    .ok_or_else(|| RuntimeError::new(format!("index {} is out of bounds for array {}", idx + 1, name)))?,
+                _ => return Err(RuntimeError::new(format!("{} is not an array", name)))
+            }
+        }
+        Ok(val)
+    }
+    fn get(&mut self, var: &Var) -> Result<&mut Literal, RuntimeError> {
+        let name = self.name(var.name);
+        let place = match self.slot(var) {
+            Slot::Undeclared => return Err(RuntimeError::new(format!("reference to undefined variable {}", name))),
+            Slot::Ref(_, place) => *place,
+            Slot::Var(_, _) => match self.slot(var) {
+                Slot::Var(_, Some(val)) => return Ok(val),
+                _ => return Err(RuntimeError::new(format!("variable {} is used before it is assigned a value", name)))
+            }
+        };
+        self.get_place(&place)
+    }
+    fn assign(&mut self, var: &Var, val: Literal) -> Result<(), RuntimeError> {
+        let place = match self.slot(var) {
+            slot @ Slot::Undeclared => {
+                *slot = Slot::Var(Decl::new(true, Type::from_literal(&val)), Some(val));
+                return Ok(())
+            },
+            Slot::Var(decl, _) | Slot::Ref(decl, _) if !decl.mutable =>
+                return Err(RuntimeError::new("Cannot assign to constant".into())),
+            Slot::Var(decl, _) | Slot::Ref(decl, _) if decl.dtype != Type::from_literal(&val) =>
+                return Err(RuntimeError::new(format!("Type of variable {:?} does not match with value {:?}", decl.dtype, val))),
+            Slot::Var(_, old) => {
+                *old = Some(val);
+                return Ok(())
+            },
+            Slot::Ref(_, place) => *place
+        };
+        *self.get_place(&place)? = val;
+        Ok(())
+    }
+    fn declare(&mut self, var: &Var, dtype: Type) {
+        let decl = Decl::new(true, dtype);
+        match self.slot(var) {
+            slot @ Slot::Undeclared => *slot = Slot::Var(decl, None),
+            Slot::Var(old, _) | Slot::Ref(old, _) => *old = decl
+        }
+    }
+    fn place(&mut self, var: &Var) -> Result<Place, RuntimeError> {
+        let name = self.name(var.name);
+        let abs_slot = if var.global { var.slot as usize } else { self.base + var.slot as usize };
+        match self.slot(var) {
+            Slot::Undeclared => Err(RuntimeError::new(format!("reference to undefined variable {}", name))),
+            Slot::Var(decl, _) | Slot::Ref(decl, _) if !decl.mutable =>
+                Err(RuntimeError::new(format!("constant {} can't be passed BYREF", name))),
+            Slot::Ref(_, place) => Ok(*place),
+            Slot::Var(_, _) => Ok(Place { global: var.global, slot: abs_slot, name: var.name, idx: None })
+        }
+    }
+
+    // the value of an argument if it's a scalar, read where it is; the caller still has to pop it
+    // if it's on the stack
+    #[inline(always)]
+    fn peek(&self, arg: &Arg) -> Option<Literal> {
+        match arg {
+            Arg::Stack => scalar(self.stack.last()?),
+            Arg::Const(idx) => scalar(&self.prog.consts[*idx as usize]),
+            Arg::Var(var) => scalar(self.value(var)?),
+            Arg::Offset(var, offset) => match self.value(var)? {
+                Literal::Int(n) => Some(Literal::Int(n + offset)),
+                _ => None
+            },
+            Arg::Elem(var, idx, offset) => {
+                let idx = match self.value(idx)? {
+                    Literal::Int(n) => n + offset,
+                    _ => return None
+                };
+                match self.value(var)? {
+                    Literal::Array(arr) => scalar(arr.get((idx as usize).wrapping_sub(1))?),
+                    _ => None
+                }
+            }
+        }
+    }
+    #[inline(always)]
+    fn peek_int(&self, arg: &Arg) -> Option<i32> {
+        match self.peek(arg)? {
+            Literal::Int(n) => Some(n),
+            _ => None
+        }
+    }
+    // both of an op's operands, if they're integers
+    #[inline(always)]
+    fn int_operands(&self, left: &Arg, right: &Arg) -> Option<(i32, i32)> {
+        match (left, right) {
+            (Arg::Stack, Arg::Stack) => match self.stack.get(self.stack.len().checked_sub(2)?..)? {
+                [Literal::Int(l), Literal::Int(r)] => Some((*l, *r)),
+                _ => None
+            },
+            _ => Some((self.peek_int(left)?, self.peek_int(right)?))
+        }
+    }
+    // takes the operands that `peek_int` left on the stack off it
+    #[inline(always)]
+    fn pop_args(&mut self, args: &[&Arg]) {
+        let count = args.iter().filter(|arg| matches!(arg, Arg::Stack)).count();
+        self.stack.truncate(self.stack.len() - count)
+    }
+
+    fn arg(&mut self, arg: &Arg) -> Result<Literal, RuntimeError> {
+        Ok(match arg {
+            Arg::Stack => self.pop(),
+            Arg::Const(idx) => copy(&self.prog.consts[*idx as usize]),
+            Arg::Var(var) => copy(self.get(var)?),
+            Arg::Offset(var, offset) => {
+                let val = copy(self.get(var)?);
+                let (op, offset) = if *offset < 0 { (TokenType::Minus, -offset) } else { (TokenType::Plus, *offset) };
+                ops::binary(&op, val, Literal::Int(offset))?
+            },
+            Arg::Elem(var, idx, offset) => {
+                let idx = self.arg(&if *offset == 0 { Arg::Var(*idx) } else { Arg::Offset(*idx, *offset) })?;
+                self.elem(var, idx)?
+            }
+        })
+    }
+    fn elem(&mut self, var: &Var, idx: Literal) -> Result<Literal, RuntimeError> {
+        let idx = expect_int(idx, "expected integer")? as usize;
+        let val = match self.get(var)? {
+            Literal::Array(arr) => match idx.checked_sub(1).and_then(|idx| arr.get(idx)) {
+                Some(val) => copy(val),
+                None => return Err(RuntimeError::new(format!("index {} is out of bounds for array {}", idx, self.name(var.name))))
+            },
+            val => val.clone()
+        };
+        Ok(val)
+    }
+    fn binary(&mut self, op: &TokenType, left: &Arg, right: &Arg) -> Result<Literal, RuntimeError> {
+        if !matches!(left, Arg::Stack) {
+            let left = self.arg(left)?;
+            self.stack.push(left)
+        }
+        let right = self.arg(right)?;
+        let left = self.pop();
+        ops::binary(op, left, right)
+    }
+
+    #[inline(always)]
+    fn check_limits(&self, loop_kind: &str, line: usize) -> Result<(), RuntimeError> {
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.steps > max_steps {
+                return Err(RuntimeError::new(format!("{} loop at line {} did not finish within {} steps", loop_kind, line, max_steps)))
+            }
+        }
+        if let Some(timeout) = self.limits.timeout {
+            if self.start.elapsed() > timeout {
+                return Err(RuntimeError::new(format!("{} loop at line {} did not finish within {}s", loop_kind, line, timeout.as_secs_f64())))
+            }
+        }
+        Ok(())
+    }
+
+    fn check_call(&self, name: u32, argc: usize, func: bool) -> Result<(), RuntimeError> {
+        let (found, other, kind) = if func { (self.funcs[name as usize], self.procs[name as usize], "function") }
+            else { (self.procs[name as usize], self.funcs[name as usize], "procedure") };
+        match found {
+            Some(def) if self.prog.defs[def].byref.len() != argc => Err(RuntimeError::new("wrong number of arguments".into())),
+            Some(_) => Ok(()),
+            None if other.is_some() => Err(RuntimeError::new(format!("{} is not a {}", self.name(name), kind))),
+            None => Err(RuntimeError::new(format!("reference to undefined {} {}", kind, self.name(name))))
+        }
+    }
+    fn call(&mut self, name: u32, func: bool, line: usize) -> Result<(), RuntimeError> {
+        let idx = if func { self.funcs[name as usize] } else { self.procs[name as usize] }.unwrap();
+        let def = &self.prog.defs[idx];
+        self.returns.push(Return { ip: self.ip, base: self.base, def: idx, line });
+        match self.limits.max_depth {
+            Some(max_depth) if self.returns.len() > max_depth =>
+                return Err(RuntimeError::new(format!("maximum recursion depth of {} exceeded", max_depth))),
+            None if self.returns.len() > MAX_CALLS =>
+                return Err(RuntimeError::new(format!("maximum recursion depth exceeded, the VM ran out of room after {} calls", MAX_CALLS))),
+            _ => {}
+        }
+
+        // the arguments go straight into the new call's slots: values from the stack, and the
+        // variables BYREF parameters refer to from `places`, each in the order they were passed
+        let byrefs = def.byref.iter().filter(|byref| **byref).count();
+        let mut next_val = self.stack.len() - (def.byref.len() - byrefs);
+        let mut next_place = self.places.len() - byrefs;
+        let first_val = next_val;
+        let base = self.locals.len();
+        for (i, byref) in def.byref.iter().enumerate() {
+            let param = if *byref {
+                let place = self.places[next_place];
+                next_place += 1;
+                Slot::Ref(Decl::new(true, Type::from_literal(self.get_place(&place)?)), place)
+            } else {
+                let val = std::mem::replace(&mut self.stack[next_val], Literal::FALSE);
+                next_val += 1;
+                Slot::Var(Decl::new(true, Type::from_literal(&val)), Some(val))
+            };
+            match &param {
+                Slot::Var(decl, _) | Slot::Ref(decl, _) if decl.dtype != self.types[idx].as_ref().unwrap().params[i] => {
+                    let kind = if func { "function" } else { "procedure" };
+                    return Err(RuntimeError::new(format!("mismatched types of {} argument", kind)))
+                },
+                _ => self.locals.push(param)
+            }
+        }
+        self.stack.truncate(first_val);
+        self.places.truncate(self.places.len() - byrefs);
+
+        self.base = base;
+        self.locals.resize(self.base + def.locals, Slot::Undeclared);
+        self.ip = def.entry;
+        Ok(())
+    }
+    fn ret(&mut self) {
+        let ret = self.returns.pop().unwrap();
+        self.locals.truncate(self.base);
+        self.ip = ret.ip;
+        self.base = ret.base;
+    }
+    // the procedure and function calls running, for an error's call trace
+    fn frames(&self) -> Vec<Frame> {
+        self.returns.iter().map(|ret| {
+            let def = &self.prog.defs[ret.def];
+            Frame::new(if def.func { "FUNCTION" } else { "PROCEDURE" }, self.name(def.name), def.line, ret.line)
+        }).collect()
+    }
+
+    fn run(&mut self) -> Result<(), RuntimeError> {
+        let prog = self.prog;
+        loop {
+            let op = &prog.code[self.ip];
+            self.steps += prog.steps[self.ip] as u64;
+            self.ip += 1;
+            match op {
+                Op::Step => {},
+                Op::CheckLimits(loop_kind, line) => {
+                    self.steps += 1;
+                    self.check_limits(loop_kind, *line)?
+                },
+                Op::Const(idx) => self.stack.push(copy(&prog.consts[*idx as usize])),
+                Op::Pop => { self.pop(); },
+                Op::Load(var) => {
+                    let val = copy(self.get(var)?);
+                    self.stack.push(val)
+                },
+                Op::LoadIdx(var, idx) => {
+                    // an integer element, indexed by an integer in bounds, is read straight from the array
+                    let elem = self.peek_int(idx).and_then(|idx| match self.value(var) {
+                        Some(Literal::Array(arr)) => match arr.get((idx as usize).wrapping_sub(1)) {
+                            Some(Literal::Int(n)) => Some(*n),
+                            _ => None
+                        },
+                        _ => None
+                    });
+                    if let Some(n) = elem {
+                        self.pop_args(&[idx]);
+                        self.stack.push(Literal::Int(n));
+                        continue
+                    }
+                    let idx = self.arg(idx)?;
+                    let val = self.elem(var, idx)?;
+                    self.stack.push(val)
+                },
+                Op::Store(var, val) => {
+                    // a scalar into a new variable or one of its type, or a BYREF parameter referring
+                    // to one, is stored in place
+                    if let Some(new) = self.peek(val) {
+                        let stored = match self.slot(var) {
+                            slot @ Slot::Undeclared => {
+                                *slot = Slot::Var(Decl::new(true, Type::from_literal(&new)), Some(new));
+                                true
+                            },
+                            Slot::Var(decl, old) if decl.mutable && fits(&new, &decl.dtype) => {
+                                *old = Some(new);
+                                true
+                            },
+                            Slot::Ref(decl, place) if decl.mutable && fits(&new, &decl.dtype) => {
+                                let place = *place;
+                                match self.get_place(&place) {
+                                    Ok(old) => {
+                                        *old = new;
+                                        true
+                                    },
+                                    Err(_) => false
+                                }
+                            },
+                            _ => false
+                        };
+                        if stored {
+                            self.pop_args(&[val]);
+                            continue
+                        }
+                    }
+                    let val = self.arg(val)?;
+                    self.assign(var, val)?
+                },
+                Op::StoreIdx(var, idx, val) => {
+                    // an integer into an integer array, at an index in bounds, is stored in place
+                    if let Some((n, i)) = self.int_operands(val, idx) {
+                        if let Slot::Var(_, Some(Literal::Array(arr))) = self.slot(var) {
+                            if let Some(elem @ Literal::Int(_)) = arr.get_mut((i as usize).wrapping_sub(1)) {
+                                *elem = Literal::Int(n);
+                                self.pop_args(&[val, idx]);
+                                continue
+                            }
+                        }
+                    }
+                    // the value was worked out first, so an index on the stack is on top of it
+                    let (val, idx) = if let Arg::Stack = idx {
+                        let idx = self.pop();
+                        (self.arg(val)?, idx)
+                    } else {
+                        let val = self.arg(val)?;
+                        (val, self.arg(idx)?)
+                    };
+                    let idx = expect_int(idx, "expected integer")? as usize;
+                    let name = self.name(var.name);
+                    let vec = match self.get(var)? {
+                        Literal::Array(vec) => vec,
+                        _ => return Err(RuntimeError::new("variable is not an array".into()))
+                    };
+                    match idx.checked_sub(1).and_then(|idx| vec.get_mut(idx)) {
+                        Some(elem) => *elem = val,
+                        None => return Err(RuntimeError::new(format!("index {} is out of bounds for array {}", idx, name)))
+                    }
+                },
+                Op::SetHidden(slot) => {
+                    let val = self.pop();
+                    self.locals[self.base + *slot as usize] = Slot::Var(Decl::new(true, Type::from_literal(&val)), Some(val));
+                },
+                Op::Declare(var) => {
+                    let dtype = match self.pop() {
+                        Literal::Type(inner) => inner,
+                        _ => return Err(RuntimeError::new("expected type".into()))
+                    };
+                    self.declare(var, dtype.clone());
+                    if let Some(val) = dtype.default_value()? { self.assign(var, val)? }
+                },
+                Op::DefConst(var) => {
+                    let val = self.pop();
+                    *self.slot(var) = Slot::Var(Decl::new(false, Type::from_literal(&val)), Some(val));
+                },
+                Op::Input(var) => {
+                    let val = match self.observer.as_mut().and_then(|observer| observer.input()) {
+                        Some(line) => line?,
+                        None => env::read_stdin()
+                    };
+                    self.assign(var, Literal::String(val))?
+                },
+                Op::Print => match self.pop() {
+                    Literal::String(val) => self.write(&val),
+                    Literal::Char(val) => self.write(&val.to_string()),
+                    val => self.write(&val.to_string())
+                },
+                Op::PrintLn => self.write("\n"),
+                Op::CheckInt(msg) => if !matches!(self.stack.last(), Some(Literal::Int(_))) {
+                    return Err(RuntimeError::new(msg.to_string()))
+                },
+                Op::Unary(op) => {
+                    let right = self.pop();
+                    self.stack.push(ops::unary(op, right)?)
+                },
+                Op::Binary(op, left, right) => {
+                    // arithmetic and comparisons on two integers, by far the most common, skip
+                    // the general version
+                    let val = match self.int_operands(left, right).and_then(|(l, r)| int_binary(op, l, r)) {
+                        Some(val) => {
+                            self.pop_args(&[left, right]);
+                            val
+                        },
+                        None => self.binary(op, left, right)?
+                    };
+                    self.stack.push(val)
+                },
+                Op::BinaryRev(op) => {
+                    let left = self.pop();
+                    let right = self.pop();
+                    self.stack.push(ops::binary(op, left, right)?)
+                },
+                Op::ArrType(two_dimensional) => {
+                    let dtype = match self.pop() {
+                        Literal::Type(t) => t,
+                        _ => return Err(RuntimeError::new("expected integer".into()))
+                    };
+                    let idx2 = if *two_dimensional {
+                        let idx2end = expect_int(self.pop(), "expected integer")?;
+                        let idx2start = expect_int(self.pop(), "expected integer")? as usize;
+                        Some((idx2start, (idx2end-(idx2start as i32)+1) as usize))
+                    } else { None };
+                    let idx1end = expect_int(self.pop(), "expected integer")? as usize;
+                    let idx1start = expect_int(self.pop(), "expected integer")? as usize;
+                    self.stack.push(Literal::Type(Type::Array(Box::new(dtype), (idx1start, idx1end-idx1start+1), idx2)))
+                },
+                Op::Jump(target) => self.ip = *target,
+                Op::Case(vals, otherwise) => {
+                    let count = vals.iter().map(|(range, _)| 1 + *range as usize).sum::<usize>();
+                    let mut values = self.stack.split_off(self.stack.len() - count).into_iter();
+                    let selector = self.pop();
+                    let mut matched = None;
+                    for (range, clause) in vals {
+                        let from = values.next().unwrap();
+                        let to = if *range { values.next() } else { None };
+                        ops::check_case_value(&selector, &from, to.as_ref())?;
+                        if matched.is_none() && ops::case_matches(&selector, &from, to.as_ref()) { matched = Some(*clause) }
+                    }
+                    self.ip = matched.unwrap_or(*otherwise)
+                },
+                Op::JumpCompare(op, left, right, when, target) => {
+                    let result = match self.int_operands(left, right).and_then(|(l, r)| int_compare(op, l, r)) {
+                        Some(result) => {
+                            self.pop_args(&[left, right]);
+                            result
+                        },
+                        None => self.binary(op, left, right)? == Literal::TRUE
+                    };
+                    if result == *when { self.ip = *target }
+                },
+                Op::JumpIf(cond, when, target, msg) => match self.arg(cond)? {
+                    Literal::TRUE => if *when { self.ip = *target },
+                    Literal::FALSE => if !*when { self.ip = *target },
+                    _ => return Err(RuntimeError::new(msg.to_string()))
+                },
+                Op::ForNext { counter, end, step, body, line } => {
+                    self.steps += 1;
+                    self.check_limits("FOR", *line)?;
+                    let hidden = |vm: &Self, slot: &u32| match &vm.locals[vm.base + *slot as usize] {
+                        Slot::Var(_, Some(Literal::Int(n))) => *n,
+                        _ => unreachable!()
+                    };
+                    let (end, step) = (hidden(self, end), hidden(self, step));
+                    // the counter is nearly always still an integer variable, so it can be counted on in place
+                    if let Slot::Var(Decl { mutable: true, dtype: Type::Int }, Some(Literal::Int(n))) = self.slot(counter) {
+                        if *n + step <= end {
+                            *n += step;
+                            self.ip = *body;
+                        }
+                        continue
+                    }
+                    let prev = expect_int(self.get(counter)?.clone(), "expected integer expression3")?;
+                    let next = prev + step;
+                    if next <= end {
+                        self.assign(counter, Literal::Int(next))?;
+                        self.ip = *body;
+                    }
+                },
+                Op::Clear(slot) => self.locals[self.base + *slot as usize] = Slot::Undeclared,
+                Op::Define(idx) => {
+                    let def = &prog.defs[*idx as usize];
+                    let as_type = |val: Literal| match val {
+                        Literal::Type(dtype) => Ok(dtype),
+                        _ => Err(RuntimeError::new("expected type".into()))
+                    };
+                    let ret_type = if def.func { Some(as_type(self.pop())?) } else { None };
+                    let param_types = self.stack.split_off(self.stack.len() - def.byref.len())
+                        .into_iter().map(as_type).collect::<Result<Vec<_>, _>>()?;
+                    self.types[*idx as usize] = Some(Rc::new(Signature { params: param_types, ret: ret_type }));
+                    if def.func { self.funcs[def.name as usize] = Some(*idx as usize) }
+                    else { self.procs[def.name as usize] = Some(*idx as usize) }
+                },
+                Op::CheckCall { name, argc, func } => self.check_call(*name, *argc, *func)?,
+                Op::Call { name, func, line } => self.call(*name, *func, *line)?,
+                Op::PlaceOf(var) => {
+                    let place = self.place(var)?;
+                    self.places.push(place)
+                },
+                Op::PlaceIdx(var, idx) => {
+                    let idx1 = match self.arg(idx)? {
+                        Literal::Int(n) if n >= 1 => n as usize - 1,
+                        Literal::Int(n) => return Err(RuntimeError::new(format!("index {} is out of bounds for array {}", n, self.name(var.name)))),
+                        _ => return Err(RuntimeError::new("expected integer".into()))
+                    };
+                    let mut place = self.place(var)?;
+                    // a BYREF parameter that's already an element can't be indexed again
+                    if place.idx.is_some() { return Err(RuntimeError::new(format!("{} is not an array", self.name(var.name)))) }
+                    place.idx = Some(idx1);
+                    self.get_place(&place)?;
+                    self.places.push(place)
+                },
+                Op::Return => {
+                    let val = self.pop();
+                    let def = match self.returns.last() {
+                        Some(ret) => ret.def,
+                        None => return Err(RuntimeError::new("RETURN outside of a procedure or function".into()))
+                    };
+                    if let Some(ret_type) = &self.types[def].as_ref().unwrap().ret {
+                        if Type::from_literal(&val) != *ret_type {
+                            return Err(RuntimeError::new("mismatched type of return value".into()))
+                        }
+                        self.ret();
+                        self.stack.push(val);
+                    } else { self.ret() }
+                },
+                Op::EndBody(func) => {
+                    if *func { return Err(RuntimeError::new("expected RETURN statement".into())) }
+                    self.ret()
+                },
+                Op::Error(msg) => return Err(RuntimeError::new(prog.messages[*msg as usize].clone())),
+                Op::Halt => return Ok(())
+            }
+        }
+    }
+}
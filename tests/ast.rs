@@ -0,0 +1,34 @@
+// `camps ast` tests: every `tests/ast/<name>.pseudo` is printed as a tree diagram, as an
+// S-expression and as JSON, and compared against `<name>.tree`, `<name>.sexp` and `<name>.json`.
+// Run with `BLESS=1` to rewrite the expected files.
+
+mod common;
+
+use std::{path::Path, process::Command};
+
+fn ast(program: &Path, flag: Option<&str>) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"));
+    command.arg("ast");
+    if let Some(flag) = flag { command.arg(flag); }
+    let output = command.arg(program).output().expect("couldn't start interpreter");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn printed_trees() {
+    let programs = common::programs("ast");
+
+    let mut failures = Vec::new();
+    for program in &programs {
+        for (ext, flag) in [("tree", None), ("sexp", Some("--sexp")), ("json", Some("--json"))] {
+            let expected_path = program.with_extension(ext);
+            let actual = ast(program, flag);
+            if let Err(diff) = common::check(&expected_path, &actual) {
+                failures.push(format!("{}: {} differs\n{}", program.display(), ext, diff));
+            }
+        }
+    }
+
+    assert!(!programs.is_empty(), "no ast tests found");
+    assert!(failures.is_empty(), "{} printed trees differ:\n\n{}", failures.len(), failures.join("\n"));
+}
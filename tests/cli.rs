@@ -0,0 +1,46 @@
+// Command line tests: options whose output can't be a golden file, like errors that depend on how
+// much stack a build uses, are checked by their exit code and what they print to stderr.
+
+use std::process::{Command, Output};
+
+fn run(args: &[&str], program: &str) -> Output {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("programs").join(program);
+    Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .args(args)
+        .arg(path)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .expect("couldn't start interpreter")
+}
+
+#[test]
+fn unlimited_recursion_stops_with_an_error() {
+    // how many calls fit depends on the build, so only the start of the error is checked
+    for args in [&["--max-depth", "0"][..], &["--vm", "--max-depth", "0"]] {
+        let output = run(args, "err_recursion_default_depth.pseudo");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(1), "{:?}: {}", args, stderr);
+        assert!(stderr.starts_with("runtime error: maximum recursion depth exceeded, "), "{:?}: {}", args, stderr);
+        assert!(stderr.ends_with("    in PROCEDURE Forever (line 1) called from line 5\n"), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn invalid_option_values_are_errors() {
+    let cases: &[(&[&str], &str)] = &[
+        (&["--max-steps", "lots"], "--max-steps expects a whole number\n"),
+        (&["--timeout", "-1"], "--timeout expects a number of seconds\n"),
+        (&["--max-depth", "deep"], "--max-depth expects a whole number\n"),
+        (&["--trace-format", "html"], "--trace-format expects text, csv or markdown\n"),
+        (&["--trace", "Nums[0]"], "--trace Nums[0] isn't a variable or an array element like Name[1]\n"),
+        (&["--syllabus", "1234"], "--syllabus expects 0478 or 9618\n"),
+        (&["transpile", "--to", "cobol"], "can't transpile to \"cobol\", expected python, java or vb\n"),
+        (&["flowchart", "--format", "svg"], "can't draw flowcharts as \"svg\", expected dot or mermaid\n")
+    ];
+    for (args, error) in cases {
+        let output = run(args, "recursion.pseudo");
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "", "{:?}", args);
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), *error, "{:?}", args);
+    }
+}
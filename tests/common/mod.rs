@@ -0,0 +1,54 @@
+// Helpers shared by the integration tests: finding the test programs in a directory, and checking
+// output against expected files, which running with `BLESS=1` rewrites instead.
+
+// each test uses only some of these
+#![allow(dead_code)]
+
+use std::{fs, path::{Path, PathBuf}};
+
+/// Every `.pseudo` file in `tests/<dir>`, sorted by name
+pub fn programs(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir);
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("couldn't read {}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pseudo"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Whether the expected files are being rewritten rather than checked
+pub fn bless() -> bool {
+    std::env::var_os("BLESS").is_some()
+}
+
+/// Checks output against the expected file, or writes it there when blessing. A difference comes
+/// back as a line-by-line diff.
+pub fn check(expected_path: &Path, actual: &str) -> Result<(), String> {
+    if bless() {
+        fs::write(expected_path, actual).unwrap();
+        return Ok(())
+    }
+    let expected = fs::read_to_string(expected_path)
+        .unwrap_or_else(|_| panic!("missing {}", expected_path.display()));
+    if expected == actual { Ok(()) } else { Err(diff(&expected, actual)) }
+}
+
+/// Compares two outputs line by line, marking the lines only in `expected` with `-` and the ones
+/// only in `actual` with `+`
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out.push_str(&format!("   {}\n", e)),
+            (e, a) => {
+                if let Some(e) = e { out.push_str(&format!(" - {}\n", e)) }
+                if let Some(a) = a { out.push_str(&format!(" + {}\n", a)) }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,186 @@
+// Debug adapter tests: `camps dap` is driven the way an editor would, one request at a time, and its
+// responses and events are checked for the breakpoints, call stacks and variables they should show.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const PROGRAM: &str = "DECLARE Names : ARRAY[1:3] OF STRING
+DECLARE Name : STRING
+DECLARE Total : INTEGER
+PROCEDURE Add(BYREF Sum : INTEGER, N : INTEGER)
+    DECLARE Doubled : INTEGER
+    Doubled <- N * 2
+    Sum <- Sum + Doubled
+ENDPROCEDURE
+Total <- 0
+FOR I <- 1 TO 3
+    INPUT Name
+    Names[I] <- Name
+ENDFOR
+CALL Add(Total, 5)
+OUTPUT Names[2], \" \", Total
+";
+
+struct Adapter {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    seq: usize,
+    seen: Vec<String> // every message sent back so far
+}
+
+impl Adapter {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+            .arg("dap")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("couldn't start interpreter");
+        let input = child.stdin.take().unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+        Self { child, input, output, seq: 0, seen: Vec::new() }
+    }
+
+    fn read(&mut self) -> String {
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            assert!(self.output.read_line(&mut line).unwrap() > 0, "the adapter stopped early, after {:#?}", self.seen);
+            match line.trim_end() {
+                "" => break,
+                line => if let Some(val) = line.strip_prefix("Content-Length: ") { len = val.parse().unwrap() }
+            }
+        }
+        let mut body = vec![0; len];
+        self.output.read_exact(&mut body).unwrap();
+        let msg = String::from_utf8(body).unwrap();
+        self.seen.push(msg.clone());
+        msg
+    }
+    fn until(&mut self, pred: impl Fn(&str) -> bool) -> String {
+        loop {
+            let msg = self.read();
+            if pred(&msg) { return msg }
+        }
+    }
+
+    // sends a request and returns its response
+    fn request(&mut self, command: &str, args: &str) -> String {
+        self.seq += 1;
+        let msg = format!(r#"{{"seq": {}, "type": "request", "command": "{}", "arguments": {}}}"#, self.seq, command, args);
+        write!(self.input, "Content-Length: {}\r\n\r\n{}", msg.len(), msg).unwrap();
+        self.input.flush().unwrap();
+        let prefix = format!(r#""type": "response", "request_seq": {}, "#, self.seq);
+        self.until(|msg| msg.contains(&prefix))
+    }
+    fn event(&mut self, event: &str) -> String {
+        let name = format!(r#""event": "{}""#, event);
+        self.until(|msg| msg.contains(&name))
+    }
+}
+
+fn launch(name: &str, args: &str) -> (Adapter, std::path::PathBuf) {
+    launch_program(name, PROGRAM, args)
+}
+fn launch_program(name: &str, program: &str, args: &str) -> (Adapter, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("camps_dap_{}_{}.pseudo", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let mut adapter = Adapter::start();
+    assert!(adapter.request("initialize", r#"{"adapterID": "camps"}"#).contains(r#""supportsConfigurationDoneRequest": true"#));
+    let launch = adapter.request("launch", &format!(r#"{{"program": {:?}, {}}}"#, path.to_str().unwrap(), args));
+    assert!(launch.contains(r#""success": true"#), "{}", launch);
+    adapter.event("initialized");
+    (adapter, path)
+}
+
+#[test]
+fn stops_at_breakpoints_and_shows_the_call_stack_and_variables() {
+    let (mut adapter, path) = launch("breakpoints", r#""stopOnEntry": true, "input": "Ann\nBo\nCy""#);
+    let breakpoints = adapter.request("setBreakpoints", r#"{"breakpoints": [{"line": 7}, {"line": 8}]}"#);
+    assert!(breakpoints.contains(r#"{"verified": true, "line": 7}"#));
+    // ENDPROCEDURE isn't a statement that can be paused before
+    assert!(breakpoints.contains(r#"{"verified": false, "line": 8, "#));
+    adapter.request("configurationDone", "{}");
+    assert!(adapter.event("stopped").contains(r#""reason": "entry""#));
+    assert!(adapter.request("stackTrace", r#"{"threadId": 1}"#).contains(r#""name": "main program""#));
+
+    adapter.request("continue", r#"{"threadId": 1}"#);
+    assert!(adapter.event("stopped").contains(r#""reason": "breakpoint""#));
+    let stack = adapter.request("stackTrace", r#"{"threadId": 1}"#);
+    assert!(stack.contains(r#"{"id": 0, "name": "PROCEDURE Add", "#) && stack.contains(r#""line": 7, "#));
+    // the caller is shown on the line it made the call from
+    assert!(stack.contains(r#"{"id": 1, "name": "main program", "#) && stack.contains(r#""line": 14, "#));
+
+    let scopes = adapter.request("scopes", r#"{"frameId": 0}"#);
+    assert!(scopes.contains(r#"{"name": "Locals", "variablesReference": 1, "#));
+    assert!(scopes.contains(r#"{"name": "Globals", "variablesReference": 2, "#));
+    let locals = adapter.request("variables", r#"{"variablesReference": 1}"#);
+    assert!(locals.contains(r#"{"name": "Doubled", "value": "10", "type": "INTEGER", "variablesReference": 0}"#));
+    assert!(locals.contains(r#"{"name": "Sum", "value": "0", "#));
+    let globals = adapter.request("variables", r#"{"variablesReference": 2}"#);
+    assert!(globals.contains(r#"{"name": "Names", "value": "ARRAY[1:3]", "variablesReference": 3}"#));
+    let names = adapter.request("variables", r#"{"variablesReference": 3}"#);
+    assert!(names.contains(r#"{"name": "[2]", "value": "\"Bo\"", "type": "STRING", "variablesReference": 0}"#));
+    assert!(adapter.request("evaluate", r#"{"expression": "N", "frameId": 0}"#).contains(r#""result": "5""#));
+
+    adapter.request("stepOut", r#"{"threadId": 1}"#);
+    assert!(adapter.event("stopped").contains(r#""reason": "step""#));
+    assert!(adapter.request("stackTrace", r#"{"threadId": 1}"#).contains(r#""line": 15, "#));
+    adapter.request("continue", r#"{"threadId": 1}"#);
+    assert!(adapter.event("exited").contains(r#""exitCode": 0"#));
+    let output: String = adapter.seen.iter()
+        .filter(|msg| msg.contains(r#""category": "stdout""#))
+        .map(|msg| msg.split(r#""output": ""#).nth(1).unwrap().trim_end_matches("\"}}").to_string())
+        .collect();
+    assert_eq!(output, r"Bo 10\n");
+
+    adapter.request("disconnect", "{}");
+    assert!(adapter.child.wait().unwrap().success());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reports_runtime_errors_and_running_out_of_input() {
+    let (mut adapter, path) = launch("errors", r#""input": ["Ann"]"#);
+    adapter.request("configurationDone", "{}");
+    let error = adapter.event("output");
+    assert!(error.contains(r#""category": "stderr""#) && error.contains("\"input\\\" has run out"), "{}", error);
+    assert!(adapter.event("exited").contains(r#""exitCode": 1"#));
+    adapter.event("terminated");
+    assert!(adapter.request("stackTrace", r#"{"threadId": 1}"#).contains(r#""success": false"#));
+    adapter.request("disconnect", "{}");
+    assert!(adapter.child.wait().unwrap().success());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn shows_the_calls_that_led_to_a_runtime_error() {
+    let program = "PROCEDURE Show(N : INTEGER)
+    DECLARE Nums : ARRAY[1:2] OF INTEGER
+    OUTPUT Nums[N]
+ENDPROCEDURE
+CALL Show(5)
+";
+    let (mut adapter, path) = launch_program("trace", program, r#""input": []"#);
+    adapter.request("configurationDone", "{}");
+    let error = adapter.event("output");
+    assert!(error.contains(r#""output": "runtime error: index 5 is out of bounds for array Nums\n    in PROCEDURE Show (line 1) called from line 5\n""#), "{}", error);
+    assert!(adapter.event("exited").contains(r#""exitCode": 1"#));
+    adapter.request("disconnect", "{}");
+    assert!(adapter.child.wait().unwrap().success());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn refuses_programs_that_do_not_parse() {
+    let path = std::env::temp_dir().join(format!("camps_dap_bad_{}.pseudo", std::process::id()));
+    std::fs::write(&path, "DECLARE X INTEGER\n").unwrap();
+    let mut adapter = Adapter::start();
+    adapter.request("initialize", "{}");
+    let launch = adapter.request("launch", &format!(r#"{{"program": {:?}}}"#, path.to_str().unwrap()));
+    assert!(launch.contains(r#""success": false"#) && launch.contains("error on line 1"), "{}", launch);
+    adapter.request("disconnect", "{}");
+    assert!(adapter.child.wait().unwrap().success());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,45 @@
+// Flowchart and structure chart tests: every `tests/flowchart/<name>.pseudo` is drawn as each chart
+// that has an expected output file next to it (`<name>.dot` or `<name>.mmd` for its flowcharts,
+// `<name>.structure.dot` or `<name>.structure.mmd` for its structure chart), and the output is
+// compared against that file. Run with `BLESS=1` to rewrite the expected files.
+
+mod common;
+
+use std::{path::Path, process::Command};
+
+const CHARTS: &[(&str, &str, &str)] = &[
+    ("flowchart", "dot", "dot"),
+    ("flowchart", "mermaid", "mmd"),
+    ("structure", "dot", "structure.dot"),
+    ("structure", "mermaid", "structure.mmd")
+];
+
+
+fn draw(program: &Path, chart: &str, format: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .args([chart, "--format", format])
+        .arg(program)
+        .output()
+        .expect("couldn't start interpreter");
+    String::from_utf8(output.stdout).unwrap() + &String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn drawn_programs() {
+    let mut failures = Vec::new();
+    let mut compared = 0;
+    for program in &common::programs("flowchart") {
+        for (chart, format, ext) in CHARTS {
+            let expected_path = program.with_extension(ext);
+            if !expected_path.exists() && !common::bless() { continue }
+            let actual = draw(program, chart, format);
+            compared += 1;
+            if let Err(diff) = common::check(&expected_path, &actual) {
+                failures.push(format!("{} as a {} in {}: output differs\n{}", program.display(), chart, format, diff));
+            }
+        }
+    }
+
+    assert!(compared > 0, "no chart tests found");
+    assert!(failures.is_empty(), "{} charts differ:\n\n{}", failures.len(), failures.join("\n"));
+}
@@ -0,0 +1,52 @@
+// Formatter tests: every `tests/fmt/<name>.pseudo` is formatted and compared against
+// `<name>.expected`, and formatting any of the golden programs twice gives the same result as
+// formatting it once. Run with `BLESS=1` to rewrite the expected files.
+
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+// the formatted program, or None if it couldn't be parsed
+fn format(program: &Path) -> Option<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .arg("fmt")
+        .arg(program)
+        .output()
+        .expect("couldn't start interpreter");
+    if output.status.success() { Some(String::from_utf8(output.stdout).unwrap()) } else { None }
+}
+
+#[test]
+fn formatted_programs() {
+    let mut failures = Vec::new();
+    let programs = common::programs("fmt");
+    for program in &programs {
+        let expected_path = program.with_extension("expected");
+        let actual = format(program).unwrap_or_else(|| panic!("{} didn't parse", program.display()));
+        if let Err(diff) = common::check(&expected_path, &actual) {
+            failures.push(format!("{}: output differs\n{}", program.display(), diff));
+        }
+    }
+
+    assert!(!programs.is_empty(), "no formatter tests found");
+    assert!(failures.is_empty(), "{} formatted programs differ:\n\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn formatting_twice_changes_nothing() {
+    let dir = std::env::temp_dir().join(format!("camps-fmt-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut failures = Vec::new();
+    for program in common::programs("programs").iter().chain(&common::programs("fmt")) {
+        let Some(once) = format(program) else { continue };
+        let formatted = dir.join(program.file_name().unwrap());
+        fs::write(&formatted, &once).unwrap();
+        let twice = format(&formatted).unwrap_or_else(|| panic!("formatted {} didn't parse", program.display()));
+        if once != twice {
+            failures.push(format!("{}\n--- once\n{}--- twice\n{}", program.display(), once, twice));
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(failures.is_empty(), "{} programs format differently the second time:\n\n{}", failures.len(), failures.join("\n"));
+}
@@ -0,0 +1,74 @@
+// Golden-output tests: every `tests/programs/<name>.pseudo` is run through the interpreter
+// with `<name>.in` (if present) piped to stdin and the options in `<name>.args` (if present),
+// and its stdout followed by its stderr is compared against `<name>.expected`. Each program is
+// run both by the tree-walking interpreter and with `--vm`, which must give the same output.
+// Run with `BLESS=1` to rewrite the expected files.
+
+mod common;
+
+use std::{fs, io::{Read, Write}, path::Path, process::{Command, Stdio}, thread, time::{Duration, Instant}};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+
+fn run(program: &Path, args: &[&str], input: String) -> Result<String, String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .args(args)
+        .arg(program)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("couldn't start interpreter: {}", err))?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    thread::spawn(move || { let _ = stdin.write_all(input.as_bytes()); });
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout = thread::spawn(move || { let mut buf = String::new(); let _ = stdout.read_to_string(&mut buf); buf });
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || { let mut buf = String::new(); let _ = stderr.read_to_string(&mut buf); buf });
+
+    let start = Instant::now();
+    while child.try_wait().map_err(|err| err.to_string())?.is_none() {
+        if start.elapsed() > TIMEOUT {
+            let _ = child.kill();
+            return Err(format!("timed out after {:?}", TIMEOUT));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    Ok(stdout.join().unwrap() + &stderr.join().unwrap())
+}
+
+#[test]
+fn golden_programs() {
+    let programs = common::programs("programs");
+    assert!(!programs.is_empty(), "no test programs found");
+
+    let mut failures = Vec::new();
+    for program in &programs {
+        let input = fs::read_to_string(program.with_extension("in")).unwrap_or_default();
+        let args = fs::read_to_string(program.with_extension("args")).unwrap_or_default();
+        let args: Vec<&str> = args.split_whitespace().collect();
+        let expected_path = program.with_extension("expected");
+        for mode in [&[][..], &["--vm"][..]] {
+            // the VM can't be traced
+            if !mode.is_empty() && args.contains(&"--trace") { continue }
+            let mode_args: Vec<&str> = mode.iter().chain(&args).copied().collect();
+            let name = format!("{}{}", program.display(), mode.iter().map(|arg| format!(" {}", arg)).collect::<String>());
+            let actual = match run(program, &mode_args, input.clone()) {
+                Ok(output) => output,
+                Err(err) => { failures.push(format!("{}: {}", name, err)); continue }
+            };
+
+            // the expected output is the interpreter's, which the VM has to match
+            if common::bless() && !mode.is_empty() { continue }
+            if let Err(diff) = common::check(&expected_path, &actual) {
+                failures.push(format!("{}: output differs\n{}", name, diff));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} programs failed:\n\n{}", failures.len(), programs.len(), failures.join("\n"));
+}
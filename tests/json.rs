@@ -0,0 +1,61 @@
+// JSON tests: every golden program exported with `camps ast --json` and loaded back with
+// `--from-json` has the same syntax tree and runs the same way, and broken documents are rejected.
+
+mod common;
+
+use std::{fs, process::Command};
+
+fn camps(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .args(args)
+        .output()
+        .expect("couldn't start interpreter");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+
+#[test]
+fn exported_programs_load_back() {
+    let dir = std::env::temp_dir().join(format!("camps-json-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut failures = Vec::new();
+    for program in common::programs("programs") {
+        // programs that need input are left out, and ones that don't parse have nothing to export
+        if program.with_extension("in").exists() { continue }
+        let path = program.to_str().unwrap();
+        let doc = camps(&["ast", "--json", path]);
+        if !doc.starts_with('{') { continue }
+        let json = dir.join(program.file_name().unwrap()).with_extension("json");
+        fs::write(&json, doc).unwrap();
+        let json = json.to_str().unwrap();
+
+        if camps(&["ast", path]) != camps(&["ast", "--from-json", json]) {
+            failures.push(format!("{}: tree differs after loading", program.display()));
+        }
+        if program.with_extension("args").exists() { continue }
+        if camps(&[path]) != camps(&["--from-json", json]) {
+            failures.push(format!("{}: output differs after loading", program.display()));
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(failures.is_empty(), "{} programs changed:\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn broken_documents_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("camps-json-err-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let cases = [
+        ("{\"version\": 2, \"ast\": ", "json err: unexpected end of the document at line 1"),
+        ("{\"version\": 99, \"ast\": {\"kind\": \"Block\", \"body\": []}}", "json err: version 99 isn't supported, expected 2"),
+        ("{\"version\": 2, \"ast\": {\"kind\": \"Loop\"}}", "json err: unknown statement kind Loop"),
+        ("{\"version\": 2, \"ast\": {\"kind\": \"Input\"}}", "json err: missing \"target\" in Input"),
+    ];
+    for (i, (doc, expected)) in cases.iter().enumerate() {
+        let path = dir.join(format!("{}.json", i));
+        fs::write(&path, doc).unwrap();
+        assert_eq!(camps(&["--from-json", path.to_str().unwrap()]).trim_end(), *expected, "loading {}", doc);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,93 @@
+// Language server tests: `camps lsp` is sent a session's worth of messages and its replies are
+// checked for the diagnostics, hovers, definitions, symbols and completions they should contain.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const PROGRAM: &str = r#"DECLARE Total : INTEGER\nPROCEDURE Add(BYREF Sum : INTEGER, N : INTEGER)\n    DECLARE Doubled : INTEGER\n    Doubled <- N * 2\n    Sum <- Sum + Doubled\nENDPROCEDURE\nTotal <- 0\nCALL Add(Total, 5)\nOUTPUT Total\n"#;
+
+fn frame(msg: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", msg.len(), msg)
+}
+fn request(id: usize, method: &str, params: &str) -> String {
+    frame(&format!(r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {}}}"#, id, method, params))
+}
+fn notify(method: &str, params: &str) -> String {
+    frame(&format!(r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}}}"#, method, params))
+}
+fn at(line: usize, character: usize) -> String {
+    format!(r#"{{"textDocument": {{"uri": "file:///a.pseudo"}}, "position": {{"line": {}, "character": {}}}}}"#, line, character)
+}
+
+// runs a session and returns each reply's JSON
+fn session(msgs: &[String]) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("couldn't start interpreter");
+    child.stdin.take().unwrap().write_all(msgs.concat().as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "the server didn't exit cleanly");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    stdout.split("Content-Length: ").skip(1).map(|msg| msg.split_once("\r\n\r\n").unwrap().1.to_string()).collect()
+}
+
+fn reply(replies: &[String], id: usize) -> &str {
+    let prefix = format!(r#"{{"jsonrpc": "2.0", "id": {}, "#, id);
+    replies.iter().find(|reply| reply.starts_with(&prefix)).unwrap_or_else(|| panic!("no reply to request {}", id))
+}
+
+#[test]
+fn answers_requests_about_a_program() {
+    let replies = session(&[
+        request(1, "initialize", "{}"),
+        notify("initialized", "{}"),
+        notify("textDocument/didOpen", &format!(r#"{{"textDocument": {{"uri": "file:///a.pseudo", "text": "{}"}}}}"#, PROGRAM)),
+        request(2, "textDocument/hover", &at(8, 8)),
+        request(3, "textDocument/hover", &at(4, 19)),
+        request(4, "textDocument/definition", &at(7, 6)),
+        request(5, "textDocument/definition", &at(4, 4)),
+        request(6, "textDocument/documentSymbol", r#"{"textDocument": {"uri": "file:///a.pseudo"}}"#),
+        request(7, "textDocument/completion", &at(3, 4)),
+        request(8, "shutdown", "null"),
+        notify("exit", "null")
+    ]);
+
+    assert!(reply(&replies, 1).contains(r#""hoverProvider": true"#));
+    assert!(replies.iter().any(|reply| reply.contains(r#""method": "textDocument/publishDiagnostics""#) && reply.contains(r#""diagnostics": []"#)));
+    assert!(reply(&replies, 2).contains("DECLARE Total : INTEGER"));
+    assert!(reply(&replies, 3).contains("DECLARE Doubled : INTEGER"));
+    // the procedure's name on line 2, then the parameter on the same line
+    assert!(reply(&replies, 4).contains(r#""range": {"start": {"line": 1, "character": 10}, "end": {"line": 1, "character": 13}}"#));
+    assert!(reply(&replies, 5).contains(r#""range": {"start": {"line": 1, "character": 20}, "end": {"line": 1, "character": 23}}"#));
+    let symbols = reply(&replies, 6);
+    assert!(symbols.contains(r#""name": "Add", "detail": "PROCEDURE Add(BYREF Sum : INTEGER, N : INTEGER)", "kind": 12"#));
+    assert!(symbols.contains(r#""range": {"start": {"line": 1, "character": 0}, "end": {"line": 6, "character": 0}}"#));
+    assert!(symbols.contains(r#""name": "Doubled""#));
+    let completions = reply(&replies, 7);
+    assert!(completions.contains(r#"{"label": "ENDPROCEDURE", "kind": 14}"#));
+    assert!(completions.contains(r#""label": "Doubled""#) && completions.contains(r#""label": "Total""#));
+    assert_eq!(reply(&replies, 8), r#"{"jsonrpc": "2.0", "id": 8, "result": null}"#);
+}
+
+#[test]
+fn publishes_errors_as_the_program_changes() {
+    let replies = session(&[
+        request(1, "initialize", "{}"),
+        notify("textDocument/didOpen", r#"{"textDocument": {"uri": "file:///a.pseudo", "text": "OUTPUT 1\n"}}"#),
+        notify("textDocument/didChange", r#"{"textDocument": {"uri": "file:///a.pseudo"}, "contentChanges": [{"text": "DECLARE X INTEGER\n"}]}"#),
+        notify("textDocument/didChange", r#"{"textDocument": {"uri": "file:///a.pseudo"}, "contentChanges": [{"text": "OUTPUT $\n"}]}"#),
+        request(2, "unknown/method", "{}"),
+        request(3, "shutdown", "null"),
+        notify("exit", "null")
+    ]);
+
+    let diagnostics: Vec<&String> = replies.iter().filter(|reply| reply.contains("publishDiagnostics")).collect();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics[0].contains(r#""diagnostics": []"#));
+    assert!(diagnostics[1].contains(r#""range": {"start": {"line": 0, "character": 10}, "end": {"line": 0, "character": 17}}, "severity": 1, "source": "camps", "message": "Expected newline after statement""#));
+    assert!(diagnostics[2].contains(r#""message": "unexpected character $""#));
+    assert!(reply(&replies, 2).contains(r#""error": {"code": -32601"#));
+}
@@ -0,0 +1,45 @@
+// Tests for `camps test <program> <cases>`, using the programs and case files in `tests/cases`.
+
+use std::{path::Path, process::Command};
+
+fn camps_test(program: &str, cases: &str) -> (bool, String) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("cases");
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .arg("test")
+        .arg(dir.join(program))
+        .arg(dir.join(cases))
+        .output()
+        .expect("couldn't start interpreter");
+    (output.status.success(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn reports_each_case_and_score() {
+    let (success, report) = camps_test("average.pseudo", "average.toml");
+    assert!(!success);
+    assert!(report.contains("[1] PASS three marks"), "{}", report);
+    assert!(report.contains("[2] FAIL wrong expectation"), "{}", report);
+    assert!(report.contains("    - mark 3: 4\n    + mark 3: 3\n"), "{}", report);
+    assert!(report.ends_with("1/2 cases passed, score 1/3\n"), "{}", report);
+}
+
+#[test]
+fn stops_programs_that_run_too_long() {
+    let (success, report) = camps_test("loop.pseudo", "loop.toml");
+    assert!(!success);
+    assert!(report.contains("[1] FAIL never finishes (timed out after 0.2s)"), "{}", report);
+}
+
+#[test]
+fn reports_bad_case_files() {
+    let (success, report) = camps_test("average.pseudo", "average.pseudo");
+    assert!(!success);
+    assert!(report.starts_with("test case file err at line 1:"), "{}", report);
+}
+
+#[test]
+fn rejects_timeouts_that_are_not_positive() {
+    let (success, report) = camps_test("average.pseudo", "bad_timeout.toml");
+    assert!(!success);
+    assert!(report.starts_with("test case file err at line 5: `timeout` must be a positive number of seconds"), "{}", report);
+}
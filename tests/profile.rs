@@ -0,0 +1,68 @@
+// Profiler tests: `--profile` is run on a sort, and the line counts and call counts it prints are
+// checked. How long things took changes from run to run, so only the counts are.
+
+use std::process::Command;
+
+fn profile(program: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("programs").join(program);
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .arg("--profile")
+        .arg(path)
+        .output()
+        .expect("couldn't start interpreter");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn counts_how_often_each_line_runs() {
+    let report = profile("debug_bubble_sort.pseudo");
+    assert!(report.starts_with("[ 1 2 3 4 ]\n\n   hits line\n"), "{}", report);
+    // comments and lines that close a block have no count
+    assert!(report.contains("\n           1 | // stepping through a bubble sort in the debugger\n"));
+    assert!(report.contains("\n          12 | ENDPROCEDURE\n"));
+    assert!(report.contains("\n      1    2 | DECLARE Nums : ARRAY[1:4] OF INTEGER\n"));
+    // the comparison runs 3 + 2 + 1 times, and 5 of those find a pair to swap
+    assert!(report.contains("\n      6   15 |         IF Nums[I] > Nums[I + 1]\n"));
+    assert!(report.contains("\n      5   17 |             CALL Swap(Nums[I], Nums[I + 1])\n"));
+    assert!(report.contains("\n      5   11 |     B <- Temp\n"));
+    assert!(report.contains("\n41 statements ran in "));
+    assert!(report.contains("\ncalls:\n    PROCEDURE Swap       5 calls  "));
+}
+
+#[test]
+fn shows_statements_that_never_ran_and_counts_recursive_calls() {
+    let report = profile("recursion.pseudo");
+    assert!(report.contains("\n    PROCEDURE CountDown       6 calls  "), "{}", report);
+    let report = profile("optimize.pseudo");
+    // statements after a RETURN are never reached
+    assert!(report.lines().any(|line| line.starts_with("      0 ")), "{}", report);
+}
+
+#[test]
+fn cant_profile_the_vm() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("programs").join("recursion.pseudo");
+    // the VM doesn't run the syntax tree, which is what the profiler and tracer watch
+    for flags in [&["--vm", "--profile"][..], &["--vm", "--trace", "N"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+            .args(flags)
+            .arg(&path)
+            .output()
+            .expect("couldn't start interpreter");
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "--vm can't be used with --trace or --profile\n");
+    }
+}
+
+#[test]
+fn counts_each_check_of_a_loop_condition() {
+    let report = profile("profile_loops.pseudo");
+    // the WHILE condition is true 3 times and false once, and the FOR counter is set once and
+    // stepped 4 times, the last taking it past the end
+    assert!(report.contains("\n      4    3 | WHILE Count < 3 DO\n"), "{}", report);
+    assert!(report.contains("\n      3    4 |     Count <- Count + 1\n"));
+    // a REPEAT loop starts once, and its condition is checked after each time round
+    assert!(report.contains("\n      1    6 | REPEAT\n"));
+    assert!(report.contains("\n      3    8 | UNTIL Count = 0\n"));
+    assert!(report.contains("\n      5    9 | FOR I <- 1 TO 4\n"));
+    assert!(report.contains("\n      4   10 |     Count <- Count + I\n"));
+}
@@ -0,0 +1,120 @@
+// Transpiler tests: every `tests/transpile/<name>.pseudo` is transpiled to each language that has
+// an expected output file next to it (`<name>.py`, `<name>.java` or `<name>.vb`), and the output
+// is compared against that file. Run with `BLESS=1` to rewrite the expected files. A program with
+// a `<name>.expected` file is also run by the interpreter, with `<name>.in` (if present) as its
+// input, and so are its Python and Java versions wherever python3 and a JDK are installed, all of
+// which must print what's in that file.
+
+mod common;
+
+use std::{fs, io::Write, path::Path, process::{Command, Stdio}};
+
+const TARGETS: &[(&str, &str)] = &[("python", "py"), ("java", "java"), ("vb", "vb")];
+
+
+fn transpile(program: &Path, target: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter"))
+        .args(["transpile", "--to", target])
+        .arg(program)
+        .output()
+        .expect("couldn't start interpreter");
+    String::from_utf8(output.stdout).unwrap() + &String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn transpiled_programs() {
+    let mut failures = Vec::new();
+    let mut compared = 0;
+    for program in &common::programs("transpile") {
+        for (target, ext) in TARGETS {
+            let expected_path = program.with_extension(ext);
+            if !expected_path.exists() && !common::bless() { continue }
+            let actual = transpile(program, target);
+            compared += 1;
+            if let Err(diff) = common::check(&expected_path, &actual) {
+                failures.push(format!("{} to {}: output differs\n{}", program.display(), target, diff));
+            }
+        }
+    }
+
+    assert!(compared > 0, "no transpiler tests found");
+    assert!(failures.is_empty(), "{} transpiled programs differ:\n\n{}", failures.len(), failures.join("\n"));
+}
+
+// VB.NET can't join a whole array onto a string with `&`, so the arrays in every program
+// transpiled to it have to be shown some other way
+#[test]
+fn vb_never_joins_arrays() {
+    let mut failures = Vec::new();
+    for program in common::programs("transpile").iter().chain(&common::programs("programs")) {
+        let vb = transpile(program, "vb");
+        let arrays: Vec<&str> = vb.lines()
+            .filter_map(|line| line.trim().strip_prefix("Dim ")?.split_once('(').map(|(name, _)| name))
+            .collect();
+        for line in vb.lines().filter(|line| line.contains(" & ")) {
+            let joined = line.trim().split(" & ")
+                .map(|operand| operand.trim_start_matches("Console.WriteLine(").trim_matches(['(', ')']))
+                .find(|operand| arrays.contains(operand));
+            if let Some(operand) = joined {
+                failures.push(format!("{}: {} is joined with & in `{}`", program.display(), operand, line.trim()));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// runs a command with `input` piped to stdin, returning its stdout followed by its stderr, or
+// None if it couldn't be started
+fn output_of(command: &mut Command, input: &str) -> Option<String> {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().ok()?;
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    Some(String::from_utf8(output.stdout).unwrap() + &String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn transpiled_programs_run_like_the_interpreter() {
+    let dir = std::env::temp_dir().join(format!("camps_transpiled_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let python = output_of(Command::new("python3").arg("--version"), "").is_some();
+    let java = output_of(Command::new("javac").arg("-version"), "").is_some();
+    let mut failures = Vec::new();
+    let mut compared = 0;
+    for program in &common::programs("transpile") {
+        let expected_path = program.with_extension("expected");
+        if !expected_path.exists() { continue }
+        let input = fs::read_to_string(program.with_extension("in")).unwrap_or_default();
+        let interpreted = output_of(Command::new(env!("CARGO_BIN_EXE_pseudocode_interpreter")).arg(program), &input).unwrap();
+        if let Err(diff) = common::check(&expected_path, &interpreted) {
+            failures.push(format!("{}: the interpreter's output differs\n{}", program.display(), diff));
+            continue
+        }
+        compared += 1;
+
+        let mut runs = Vec::new();
+        if python {
+            let path = dir.join("program.py");
+            fs::write(&path, transpile(program, "python")).unwrap();
+            runs.push(("python", output_of(Command::new("python3").arg(&path), &input)));
+        }
+        if java {
+            // each program is a class called Program, so it needs a file of that name
+            fs::write(dir.join("Program.java"), transpile(program, "java")).unwrap();
+            let compiled = output_of(Command::new("javac").arg("-d").arg(&dir).arg(dir.join("Program.java")), "");
+            runs.push(("java", match compiled {
+                Some(errors) if !errors.is_empty() => Some(errors),
+                _ => output_of(Command::new("java").arg("-cp").arg(&dir).arg("Program"), &input)
+            }));
+        }
+        for (target, output) in runs {
+            let output = output.unwrap_or_else(|| format!("couldn't run the {} program", target));
+            if output != interpreted {
+                failures.push(format!("{} in {}: output differs\n{}", program.display(), target, common::diff(&interpreted, &output)));
+            }
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(compared > 0, "no transpiled programs with expected output found");
+    assert!(failures.is_empty(), "{} transpiled programs differ:\n\n{}", failures.len(), failures.join("\n"));
+}
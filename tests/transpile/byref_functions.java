@@ -0,0 +1,64 @@
+// Transpiled from pseudocode by camps
+
+public class Program {
+    static int Stock;
+    static int Taken;
+    static int Rest;
+    static int Selector;
+    static int selector;
+
+    static boolean TakeOne(int[] Stock) {
+        if (Stock[0] == 0) {
+            return false;
+        }
+        Stock[0] = Stock[0] - 1;
+        return true;
+    }
+
+    static int Split(int Total, int[] Rest) {
+        int Tens = 0;
+        while (Total >= 10) {
+            Total = Total - 10;
+            Tens = Tens + 1;
+        }
+        Rest[0] = Total;
+        return Tens;
+    }
+
+    public static void main(String[] args) {
+        Stock = 3;
+        Taken = 0;
+        while (true) {
+            int[] refStock = {Stock};
+            boolean result = TakeOne(refStock);
+            Stock = refStock[0];
+            if (!result) break;
+            Taken = Taken + 1;
+        }
+        System.out.println("" + Taken + " taken, " + Stock + " left");
+        Rest = 0;
+        Selector = 0;
+        selector = 5;
+        int[] refRest = {Rest};
+        int result2 = Split(47, refRest);
+        Rest = refRest[0];
+        switch (result2 + selector) {
+            case 9:
+                System.out.println("nine and " + Rest);
+                break;
+            default:
+                System.out.println("something else");
+                break;
+        }
+        if (Rest > 10) {
+            System.out.println("big");
+        } else {
+            int[] refRest2 = {Rest};
+            int result3 = Split(Rest, refRest2);
+            Rest = refRest2[0];
+            if (result3 == 0) {
+                System.out.println("one digit, " + Rest);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+# Transpiled from pseudocode by camps
+
+def TakeOne(Stock: int) -> tuple[bool, int]:
+    if Stock == 0:
+        return False, Stock
+    Stock = Stock - 1
+    return True, Stock
+
+def Split(Total: int, Rest: int) -> tuple[int, int]:
+    Tens = 0
+    while Total >= 10:
+        Total = Total - 10
+        Tens = Tens + 1
+    Rest = Total
+    return Tens, Rest
+
+Stock = 3
+Taken = 0
+while True:
+    result, Stock = TakeOne(Stock)
+    if not result:
+        break
+    Taken = Taken + 1
+print(Taken, " taken, ", Stock, " left", sep="")
+Rest = 0
+Selector = 0
+selector = 5
+result2, Rest = Split(47, Rest)
+selector2 = result2 + selector
+if selector2 == 9:
+    print("nine and ", Rest, sep="")
+else:
+    print("something else")
+if Rest > 10:
+    print("big")
+else:
+    result3, Rest = Split(Rest, Rest)
+    if result3 == 0:
+        print("one digit, ", Rest, sep="")
@@ -0,0 +1 @@
+transpile error at line 2: OPENFILE can't be transpiled, records and file handling aren't supported yet
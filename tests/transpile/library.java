@@ -0,0 +1,44 @@
+// Transpiled from pseudocode by camps
+import java.util.Scanner;
+
+public class Program {
+    static Scanner input = new Scanner(System.in);
+
+    static char[][] Grid = new char[3][3];
+    static int[] Offsets = new int[5];
+    static String Name;
+    static int Jump;
+    static int N;
+
+    static String Initial(String Word) {
+        return Word.substring(0, 1);
+    }
+
+    public static void main(String[] args) {
+        Name = input.nextLine();
+        System.out.println("Hello, " + Name.toUpperCase() + " (" + Name.length() + " letters)");
+        System.out.println("pseudocode".substring(1, 6) + " " + Name.substring(Name.length() - 2) + " " + (Initial(Name).equals("A") ? "TRUE" : "FALSE"));
+        for (int Index = -2; Index <= 2; Index++) {
+            Offsets[Index + 2] = (Index + 2) * 7 % 4;
+        }
+        Jump = 2;
+        for (int Index = 2; -Jump > 0 ? Index <= -2 : Index >= -2; Index += -Jump) {
+            System.out.println("" + Offsets[Index + 2] + " " + Index / 2 + " " + 7 % Jump);
+        }
+        Grid[0][0] = 'x';
+        for (int Row = 2; Row >= 0; Row--) {
+            System.out.println("" + Row + ": " + (Grid[Row][0] == 'x' ? "TRUE" : "FALSE"));
+        }
+        N = 27;
+        while (N != 1) {
+            switch (N % 2) {
+                case 0:
+                    N = N / 2;
+                    break;
+                default:
+                    N = 3 * N + 1;
+                    break;
+            }
+        }
+    }
+}
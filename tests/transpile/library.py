@@ -0,0 +1,27 @@
+# Transpiled from pseudocode by camps
+
+Grid: list[list[str]] = [[""] * 3 for _ in range(3)]
+Offsets: list[int] = [0] * 5
+Name: str
+
+def Initial(Word: str) -> str:
+    return Word[:1]
+
+Name = input()
+print("Hello, ", Name.upper(), " (", len(Name), " letters)", sep="")
+print("pseudocode"[1:6], " ", Name[-2:], " ", "TRUE" if Initial(Name) == "A" else "FALSE", sep="")
+for Index in range(-2, 3):
+    Offsets[Index + 2] = (Index + 2) * 7 % 4
+Jump = 2
+for Index in range(2, -2 + (1 if (-Jump) > 0 else -1), (-Jump)):
+    print(Offsets[Index + 2], " ", Index // 2, " ", 7 % Jump, sep="")
+Grid[0][0] = 'x'
+for Row in range(2, -1, -1):
+    print(Row, ": ", "TRUE" if Grid[Row][0] == 'x' else "FALSE", sep="")
+N = 27
+while N != 1:
+    selector = N % 2
+    if selector == 0:
+        N = N // 2
+    else:
+        N = 3 * N + 1
@@ -0,0 +1,50 @@
+// Transpiled from pseudocode by camps
+
+public class Program {
+    static int FirstSquareOver(int Limit) {
+        int N = 0;
+        int Square = 0;
+        do {
+            N = N + 1;
+            Square = N * N;
+        } while (!(Square > Limit));
+        return N;
+    }
+
+    static int Halve(int Value) {
+        do {
+            if (Value < 10) {
+                return Value;
+            }
+            Value = Value - 10;
+        } while (true);
+    }
+
+    static void Count(int Limit) {
+        int I = 0;
+        while (true) {
+            I = I + 1;
+            if (I == Limit) {
+                System.out.println("counted to " + I);
+                return;
+            }
+        }
+    }
+
+    public static void main(String[] args) {
+        System.out.println("" + FirstSquareOver(50) + " " + Halve(47));
+        Count(3);
+        while (Boolean.FALSE) {
+            System.out.println("never");
+        }
+        do {
+            System.out.println("once");
+        } while (!true);
+        System.out.println(show(1.5 + 2) + " " + (7 > 3 ? "TRUE" : "FALSE"));
+    }
+
+    static String show(double value) {
+        // a whole number is shown without a fraction, like the pseudocode interpreter does
+        return value == Math.rint(value) ? String.valueOf((long) value) : String.valueOf(value);
+    }
+}
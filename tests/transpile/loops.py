@@ -0,0 +1,40 @@
+# Transpiled from pseudocode by camps
+
+def show_real(value: float) -> str:
+    # a whole number is shown without a fraction, like the pseudocode interpreter does
+    return str(int(value)) if value.is_integer() else str(value)
+
+def FirstSquareOver(Limit: int) -> int:
+    N = 0
+    while True:
+        N = N + 1
+        Square = N * N
+        if Square > Limit:
+            break
+    return N
+
+def Halve(Value: int) -> int:
+    while True:
+        if Value < 10:
+            return Value
+        Value = Value - 10
+        if False:
+            break
+
+def Count(Limit: int):
+    I = 0
+    while True:
+        I = I + 1
+        if I == Limit:
+            print("counted to ", I, sep="")
+            return
+
+print(FirstSquareOver(50), " ", Halve(47), sep="")
+Count(3)
+while False:
+    print("never")
+while True:
+    print("once")
+    if True:
+        break
+print(show_real(1.5 + 2), " ", "TRUE" if 7 > 3 else "FALSE", sep="")
@@ -0,0 +1,51 @@
+// Transpiled from pseudocode by camps
+
+public class Program {
+    static boolean[] Flags = new boolean[3];
+    static char[] Letters = new char[2];
+    static String[] Words = new String[2];
+    static int[] Numbers = new int[4];
+    static boolean Done;
+    static boolean Ready;
+
+    public static void main(String[] args) {
+        Done = false;
+        Flags[1] = true;
+        Letters[0] = 'a';
+        Letters[1] = 'b';
+        Words[0] = "one";
+        Words[1] = "two";
+        Numbers[3] = 7;
+        Ready = Numbers[3] > 5;
+        System.out.println(Done ? "TRUE" : "FALSE");
+        System.out.println("ready: " + (Ready ? "TRUE" : "FALSE") + ", not done: " + (!Done ? "TRUE" : "FALSE"));
+        System.out.println("TRUE" + " " + (3 > 4 || Done ? "TRUE" : "FALSE"));
+        System.out.println(show(Flags));
+        System.out.println(show(Letters) + show(Words));
+        System.out.println("numbers " + show(Numbers));
+    }
+
+    static String show(String[] values) {
+        String text = "[ ";
+        for (String value : values) text += "\"" + value + "\" ";
+        return text + "]";
+    }
+
+    static String show(boolean[] values) {
+        String text = "[ ";
+        for (boolean value : values) text += (value ? "TRUE" : "FALSE") + " ";
+        return text + "]";
+    }
+
+    static String show(char[] values) {
+        String text = "[ ";
+        for (char value : values) text += "'" + value + "' ";
+        return text + "]";
+    }
+
+    static String show(int[] values) {
+        String text = "[ ";
+        for (int value : values) text += value + " ";
+        return text + "]";
+    }
+}
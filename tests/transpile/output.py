@@ -0,0 +1,21 @@
+# Transpiled from pseudocode by camps
+
+Flags: list[bool] = [False] * 3
+Letters: list[str] = [""] * 2
+Words: list[str] = [""] * 2
+Numbers: list[int] = [0] * 4
+Done: bool
+Done = False
+Flags[1] = True
+Letters[0] = 'a'
+Letters[1] = 'b'
+Words[0] = "one"
+Words[1] = "two"
+Numbers[3] = 7
+Ready = Numbers[3] > 5
+print("TRUE" if Done else "FALSE")
+print("ready: ", "TRUE" if Ready else "FALSE", ", not done: ", "TRUE" if not Done else "FALSE", sep="")
+print("TRUE", " ", "TRUE" if 3 > 4 or Done else "FALSE", sep="")
+print("[ " + " ".join("TRUE" if value else "FALSE" for value in Flags) + " ]")
+print("[ " + " ".join(f"'{value}'" for value in Letters) + " ]", "[ " + " ".join(f'"{value}"' for value in Words) + " ]", sep="")
+print("numbers ", "[ " + " ".join(map(str, Numbers)) + " ]", sep="")
@@ -0,0 +1,122 @@
+// Transpiled from pseudocode by camps
+import java.util.Scanner;
+
+public class Program {
+    static Scanner input = new Scanner(System.in);
+
+    static final int MaxScore = 100;
+    static int[] Scores = new int[5];
+    static char[] Marks = new char[3];
+    static int Count;
+    static String Name;
+    static int Total;
+    static int N;
+    static int Steps;
+
+    static void Swap(int[] A, int[] B) {
+        int Temp = A[0];
+        A[0] = B[0];
+        B[0] = Temp;
+    }
+
+    static void Fill(int[] Values, int[] Total) {
+        Values = Values.clone();
+        Total[0] = 0;
+        for (int I = 1; I <= 5; I++) {
+            Values[I - 1] = I * I - 3 * I;
+            Total[0] = Total[0] + Values[I - 1];
+        }
+    }
+
+    static int Largest(int[] Values) {
+        Values = Values.clone();
+        int Best = Values[0];
+        for (int I = 2; I <= 5; I++) {
+            if (Values[I - 1] > Best) {
+                Best = Values[I - 1];
+            }
+        }
+        return Best;
+    }
+
+    static void Record(int Score) {
+        Count = Count + 1;
+        if (Score > MaxScore) {
+            System.out.println("too high");
+            return;
+        }
+        Scores[Count - 1] = Score;
+    }
+
+    public static void main(String[] args) {
+        Count = 0;
+        Name = input.nextLine();
+        System.out.println("Hello, " + Name);
+        Record(42);
+        Record(7);
+        Record(150);
+        {
+            int[] refA = {Scores[0]};
+            int[] refB = {Scores[1]};
+            Swap(refA, refB);
+            Scores[0] = refA[0];
+            Scores[1] = refB[0];
+        }
+        System.out.println("" + Scores[0] + " " + Scores[1] + " of " + Count);
+        Total = 0;
+        {
+            int[] refTotal = {Total};
+            Fill(Scores, refTotal);
+            Total = refTotal[0];
+        }
+        System.out.println("total " + Total + " largest " + Largest(Scores));
+        Marks[0] = 'x';
+        for (int Row = 1; Row <= 3; Row++) {
+            System.out.println("" + Row + ": " + (Marks[Row - 1] == 'x' ? "TRUE" : "FALSE"));
+        }
+        N = 27;
+        Steps = 0;
+        while (N != 1) {
+            if (1 <= N && N <= 9) {
+                N = N - 1;
+            } else {
+                N = N - 7;
+            }
+            Steps = Steps + 1;
+        }
+        do {
+            Steps = Steps - 50;
+        } while (!(Steps < 0 || !(Steps > 10)));
+        if (Steps < -40) {
+            System.out.println("far below");
+        } else if (Steps < 0) {
+            System.out.println("below");
+        } else {
+            System.out.println("not below");
+        }
+        System.out.println("" + Steps + " " + show((double) -(Steps - 1) / 2));
+        if (-50 <= Steps && Steps <= -1) {
+            System.out.println("below zero");
+        } else if (Steps == 0 || Steps == 1) {
+            System.out.println("none");
+        } else {
+            System.out.println("other");
+            System.out.println(Steps);
+        }
+        switch (N) {
+            case 2:
+            case 3:
+                System.out.println("small");
+                break;
+            case 4:
+                System.out.println("four");
+                break;
+        }
+        System.out.println("done");
+    }
+
+    static String show(double value) {
+        // a whole number is shown without a fraction, like the pseudocode interpreter does
+        return value == Math.rint(value) ? String.valueOf((long) value) : String.valueOf(value);
+    }
+}
@@ -0,0 +1,87 @@
+# Transpiled from pseudocode by camps
+
+def show_real(value: float) -> str:
+    # a whole number is shown without a fraction, like the pseudocode interpreter does
+    return str(int(value)) if value.is_integer() else str(value)
+
+MaxScore = 100
+Scores: list[int] = [0] * 5
+Marks: list[str] = [""] * 3
+Count: int
+Name: str
+
+def Swap(A: int, B: int):
+    Temp = A
+    A = B
+    B = Temp
+    return A, B
+
+def Fill(Values: list[int], Total: int):
+    Values = list(Values)
+    Total = 0
+    for I in range(1, 6):
+        Values[I - 1] = I * I - 3 * I
+        Total = Total + Values[I - 1]
+    return Total
+
+def Largest(Values: list[int]) -> int:
+    Values = list(Values)
+    Best = Values[0]
+    for I in range(2, 6):
+        if Values[I - 1] > Best:
+            Best = Values[I - 1]
+    return Best
+
+def Record(Score: int):
+    global Count
+    Count = Count + 1
+    if Score > MaxScore:
+        print("too high")
+        return
+    Scores[Count - 1] = Score
+
+Count = 0
+Name = input()
+print("Hello, ", Name, sep="")
+Record(42)
+Record(7)
+Record(150)
+Scores[0], Scores[1] = Swap(Scores[0], Scores[1])
+print(Scores[0], " ", Scores[1], " of ", Count, sep="")
+Total = 0
+Total = Fill(Scores, Total)
+print("total ", Total, " largest ", Largest(Scores), sep="")
+Marks[0] = 'x'
+for Row in range(1, 4):
+    print(Row, ": ", "TRUE" if Marks[Row - 1] == 'x' else "FALSE", sep="")
+N = 27
+Steps = 0
+while N != 1:
+    if 1 <= N and N <= 9:
+        N = N - 1
+    else:
+        N = N - 7
+    Steps = Steps + 1
+while True:
+    Steps = Steps - 50
+    if Steps < 0 or not (Steps > 10):
+        break
+if Steps < -40:
+    print("far below")
+elif Steps < 0:
+    print("below")
+else:
+    print("not below")
+print(Steps, " ", show_real(-(Steps - 1) / 2), sep="")
+if -50 <= Steps and Steps <= -1:
+    print("below zero")
+elif Steps == 0 or Steps == 1:
+    print("none")
+else:
+    print("other")
+    print(Steps)
+if N == 2 or N == 3:
+    print("small")
+elif N == 4:
+    print("four")
+print("done")
@@ -0,0 +1,18 @@
+// Transpiled from pseudocode by camps
+
+public class Program {
+    static double Total;
+
+    public static void main(String[] args) {
+        Total = 20.0;
+        System.out.println(show(Total));
+        System.out.println("half " + show(Total / 8) + " quarter " + show((double) 4 / 2));
+        System.out.println(show((double) 7 / 2));
+        System.out.println(show(1.5 + 2.5));
+    }
+
+    static String show(double value) {
+        // a whole number is shown without a fraction, like the pseudocode interpreter does
+        return value == Math.rint(value) ? String.valueOf((long) value) : String.valueOf(value);
+    }
+}
@@ -0,0 +1,12 @@
+# Transpiled from pseudocode by camps
+
+def show_real(value: float) -> str:
+    # a whole number is shown without a fraction, like the pseudocode interpreter does
+    return str(int(value)) if value.is_integer() else str(value)
+
+Total: float
+Total = 20.0
+print(show_real(Total))
+print("half ", show_real(Total / 8), " quarter ", show_real(4 / 2), sep="")
+print(show_real(7 / 2))
+print(show_real(1.5 + 2.5))
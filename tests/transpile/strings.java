@@ -0,0 +1,82 @@
+// Transpiled from pseudocode by camps
+import java.util.Scanner;
+
+public class Program {
+    static Scanner input = new Scanner(System.in);
+
+    static int[] Offsets = new int[5];
+    static int[] Board = new int[4];
+    static int Age;
+    static String Answer;
+    static char Quote;
+    static String Name;
+    static int Jump;
+    static double Ratio;
+    static int Cleared;
+
+    static String Reply(String Word) {
+        switch (Word) {
+            case "yes":
+                return "agreed";
+            case "no":
+                return "refused";
+        }
+        return "unsure";
+    }
+
+    static int Clear(int[] Cells, int[] Count) {
+        Cells = Cells.clone();
+        Count[0] = 0;
+        for (int Cell = 1; Cell <= 4; Cell++) {
+            Cells[Cell - 1] = 0;
+            Count[0] = Count[0] + 1;
+        }
+        return Count[0];
+    }
+
+    static void Greet(String Person) {
+        if (Person.equals("Ada")) {
+            System.out.println("Hello again, " + Person);
+        } else if (Person.equals("")) {
+            System.out.println("Hello, whoever you are");
+        } else {
+            System.out.println("Hello, " + Person + "!");
+        }
+    }
+
+    public static void main(String[] args) {
+        Age = 18;
+        Answer = input.nextLine();
+        Name = input.nextLine();
+        for (int Index = 1; Index <= 5; Index++) {
+            Offsets[Index - 1] = (Index - 3) * Age;
+        }
+        Jump = 2;
+        for (int Index = 1; Jump > 0 ? Index <= 5 : Index >= 5; Index += Jump) {
+            System.out.println("" + Offsets[Index - 1] + " " + show((double) Age / 4));
+        }
+        System.out.println(Reply(Answer) + " " + Reply("no") + " " + Reply(Name));
+        Ratio = (double) Age / 3;
+        if (Ratio == 6.0) {
+            System.out.println("exactly six");
+        } else {
+            System.out.println("not six");
+        }
+        Board[1] = 5;
+        Cleared = 0;
+        int[] refCount = {Cleared};
+        int result = Clear(Board, refCount);
+        Cleared = refCount[0];
+        System.out.println("" + result + " " + Board[1] + " " + Cleared);
+        Greet("Ada");
+        Greet(Name);
+        Greet("");
+        Quote = '"';
+        System.out.println("it's " + Quote + "quoted" + Quote + " " + (!Answer.equals("yes") ? "TRUE" : "FALSE") + " " + (Reply(Answer).equals("agreed") ? "TRUE" : "FALSE"));
+    }
+
+    static String show(double value) {
+        // a whole number is shown without a fraction, like the pseudocode interpreter does
+        return value == Math.rint(value) ? String.valueOf((long) value) : String.valueOf(value);
+    }
+}
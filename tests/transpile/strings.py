@@ -0,0 +1,58 @@
+# Transpiled from pseudocode by camps
+
+def show_real(value: float) -> str:
+    # a whole number is shown without a fraction, like the pseudocode interpreter does
+    return str(int(value)) if value.is_integer() else str(value)
+
+Offsets: list[int] = [0] * 5
+Board: list[int] = [0] * 4
+Age: int
+Answer: str
+Quote: str
+
+def Reply(Word: str) -> str:
+    if Word == "yes":
+        return "agreed"
+    elif Word == "no":
+        return "refused"
+    return "unsure"
+
+def Clear(Cells: list[int], Count: int) -> tuple[int, int]:
+    Cells = list(Cells)
+    Count = 0
+    for Cell in range(1, 5):
+        Cells[Cell - 1] = 0
+        Count = Count + 1
+    return Count, Count
+
+def Greet(Person: str):
+    if Person == "Ada":
+        print("Hello again, ", Person, sep="")
+    elif Person == "":
+        print("Hello, whoever you are")
+    else:
+        print("Hello, ", Person, "!", sep="")
+
+Age = 18
+Answer = input()
+Name = input()
+for Index in range(1, 6):
+    Offsets[Index - 1] = (Index - 3) * Age
+Jump = 2
+for Index in range(1, 5 + (1 if Jump > 0 else -1), Jump):
+    print(Offsets[Index - 1], " ", show_real(Age / 4), sep="")
+print(Reply(Answer), " ", Reply("no"), " ", Reply(Name), sep="")
+Ratio = Age / 3
+if Ratio == 6.0:
+    print("exactly six")
+else:
+    print("not six")
+Board[1] = 5
+Cleared = 0
+result, Cleared = Clear(Board, Cleared)
+print(result, " ", Board[1], " ", Cleared, sep="")
+Greet("Ada")
+Greet(Name)
+Greet("")
+Quote = '"'
+print("it's ", Quote, "quoted", Quote, " ", "TRUE" if Answer != "yes" else "FALSE", " ", "TRUE" if Reply(Answer) == "agreed" else "FALSE", sep="")